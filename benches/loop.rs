@@ -19,6 +19,8 @@ fn run_loop_contract() {
 		block_gas_limit: Default::default(),
 		chain_id: U256::one(),
 		block_base_fee_per_gas: U256::zero(),
+		block_blob_base_fee: U256::zero(),
+		blob_hashes: Vec::new(),
 		block_randomness: None,
 	};
 
@@ -0,0 +1,922 @@
+//! EVM Object Format (EOF) v1 containers. See
+//! [EIP-3540](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3540.md)
+//! for the container format and
+//! [EIP-7692](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7692.md)
+//! for the list of EIPs it bundles.
+
+use crate::Opcode;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use core::ops::Range;
+
+/// Magic prefix of an EOF container.
+pub const EOF_MAGIC: [u8; 2] = [0xef, 0x00];
+/// Supported EOF version.
+pub const EOF_VERSION: u8 = 0x01;
+/// Outputs of a code section that never returns to its caller.
+pub const NON_RETURNING: u8 = 0x80;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_CONTAINER_SECTIONS: usize = 256;
+const MAX_SECTION_IO: u8 = 0x7f;
+const MAX_STACK_HEIGHT: usize = 1023;
+const STACK_LIMIT: usize = 1024;
+
+/// Whether the code starts with the EOF magic.
+pub fn is_eof(code: &[u8]) -> bool {
+	code.starts_with(&EOF_MAGIC)
+}
+
+/// Error of EOF container parsing and validation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EofError {
+	/// The container does not start with the EOF magic.
+	InvalidMagic,
+	/// The container version is not supported.
+	InvalidVersion,
+	/// The header is malformed, truncated, or declares invalid section counts
+	/// or sizes.
+	InvalidHeader,
+	/// The container is shorter or longer than what the header declares.
+	InvalidContainerSize,
+	/// The data section is shorter than what the header declares.
+	TruncatedData,
+	/// A type section entry has invalid inputs, outputs or stack increase.
+	InvalidTypeSection,
+	/// The code contains an opcode undefined in EOF.
+	UndefinedInstruction(Opcode),
+	/// An instruction immediate runs past the end of the code section.
+	TruncatedImmediate,
+	/// A relative jump targets outside of the code section or into an
+	/// immediate.
+	InvalidJumpDestination,
+	/// `CALLF` or `JUMPF` targets a code section that does not exist.
+	InvalidCodeSectionIndex,
+	/// `CALLF` targets a non-returning code section.
+	InvalidCallfTarget,
+	/// `JUMPF` targets a code section returning more outputs than the
+	/// current one.
+	InvalidJumpfTarget,
+	/// A code section is declared non-returning but returns, or the other way
+	/// around.
+	InvalidNonReturning,
+	/// `DATALOADN` reads past the declared data section.
+	InvalidDataOffset,
+	/// `EOFCREATE` or `RETURNCONTRACT` targets a subcontainer that does not
+	/// exist.
+	InvalidContainerIndex,
+	/// Execution can run past the end of a code section.
+	NoTerminatingInstruction,
+	/// An instruction can be reached with fewer stack items than it needs.
+	StackUnderflow,
+	/// The stack height can exceed the limit.
+	StackOverflow,
+	/// Stack heights differ between paths where they have to be equal.
+	StackHeightMismatch,
+	/// The declared maximum stack increase does not match the code.
+	InvalidMaxStackIncrease,
+	/// Some code of a section can never be reached.
+	UnreachableCode,
+	/// Some code section can never be reached from the first one.
+	UnreachableCodeSection,
+	/// Some subcontainer is never referenced.
+	UnreferencedContainer,
+	/// An instruction or subcontainer does not match the kind of its
+	/// container, for example `RETURN` in initcode.
+	IncompatibleContainerKind,
+}
+
+/// Kind of a container, which decides the instructions it may use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContainerKind {
+	/// Deployed code, terminated by `STOP`, `RETURN` or `REVERT`.
+	Runtime,
+	/// Initcode, deploying a subcontainer with `RETURNCONTRACT`.
+	Initcode,
+}
+
+/// Type section entry of a code section.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EofTypes {
+	/// Stack items the code section consumes.
+	pub inputs: u8,
+	/// Stack items the code section returns, or `NON_RETURNING`.
+	pub outputs: u8,
+	/// Maximum stack height the code section reaches above its inputs.
+	pub max_stack_increase: u16,
+}
+
+impl EofTypes {
+	/// Whether the code section returns to its caller.
+	pub fn is_returning(&self) -> bool {
+		self.outputs != NON_RETURNING
+	}
+}
+
+/// Parsed EOF container. Sections are byte ranges into the raw container.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EofContainer {
+	/// Type of each code section.
+	pub types: Vec<EofTypes>,
+	/// Code sections.
+	pub code_sections: Vec<Range<usize>>,
+	/// Subcontainer sections.
+	pub container_sections: Vec<Range<usize>>,
+	/// Data section, as much of it as is present.
+	pub data: Range<usize>,
+	/// Data section size declared in the header.
+	pub data_size: usize,
+	data_size_offset: usize,
+}
+
+struct Reader<'a> {
+	raw: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn u8(&mut self) -> Result<u8, EofError> {
+		let value = *self.raw.get(self.position).ok_or(EofError::InvalidHeader)?;
+		self.position += 1;
+		Ok(value)
+	}
+
+	fn u16(&mut self) -> Result<u16, EofError> {
+		Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+	}
+
+	fn kind(&mut self, kind: u8) -> Result<(), EofError> {
+		if self.u8()? == kind {
+			Ok(())
+		} else {
+			Err(EofError::InvalidHeader)
+		}
+	}
+
+	fn sizes(&mut self, max_count: usize) -> Result<Vec<usize>, EofError> {
+		let count = self.u16()? as usize;
+		if count == 0 || count > max_count {
+			return Err(EofError::InvalidHeader);
+		}
+		let mut sizes = Vec::with_capacity(count);
+		for _ in 0..count {
+			let size = self.u16()? as usize;
+			if size == 0 {
+				return Err(EofError::InvalidHeader);
+			}
+			sizes.push(size);
+		}
+		Ok(sizes)
+	}
+}
+
+fn sections(start: usize, sizes: &[usize]) -> (Vec<Range<usize>>, usize) {
+	let mut end = start;
+	let ranges = sizes
+		.iter()
+		.map(|size| {
+			let range = end..(end + size);
+			end = range.end;
+			range
+		})
+		.collect();
+	(ranges, end)
+}
+
+impl EofContainer {
+	/// Parse a container occupying exactly `raw`, without validating its code.
+	pub fn parse(raw: &[u8]) -> Result<Self, EofError> {
+		let container = Self::parse_truncated(raw)?;
+		if container.is_data_truncated() {
+			return Err(EofError::TruncatedData);
+		}
+		Ok(container)
+	}
+
+	/// Parse a container at the start of `raw`, followed by arbitrary bytes
+	/// such as the calldata of a creation transaction. See
+	/// [EIP-7698](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7698.md).
+	pub fn parse_prefix(raw: &[u8]) -> Result<Self, EofError> {
+		let mut container = Self::parse_header(raw)?;
+		if raw.len() < container.size() {
+			return Err(EofError::TruncatedData);
+		}
+		container.data.end = container.size();
+		Ok(container)
+	}
+
+	/// Parse a container whose data section may be shorter than declared, as
+	/// subcontainers deployed by `RETURNCONTRACT` are.
+	pub fn parse_truncated(raw: &[u8]) -> Result<Self, EofError> {
+		let container = Self::parse_header(raw)?;
+		if raw.len() > container.size() {
+			return Err(EofError::InvalidContainerSize);
+		}
+		Ok(container)
+	}
+
+	fn parse_header(raw: &[u8]) -> Result<Self, EofError> {
+		if !is_eof(raw) {
+			return Err(EofError::InvalidMagic);
+		}
+		if raw.get(2) != Some(&EOF_VERSION) {
+			return Err(EofError::InvalidVersion);
+		}
+
+		let mut reader = Reader { raw, position: 3 };
+		reader.kind(KIND_TYPES)?;
+		let types_size = reader.u16()? as usize;
+		reader.kind(KIND_CODE)?;
+		let code_sizes = reader.sizes(MAX_CODE_SECTIONS)?;
+		if types_size != code_sizes.len() * 4 {
+			return Err(EofError::InvalidHeader);
+		}
+		let container_sizes = match reader.u8()? {
+			KIND_CONTAINER => {
+				let sizes = reader.sizes(MAX_CONTAINER_SECTIONS)?;
+				reader.kind(KIND_DATA)?;
+				sizes
+			}
+			KIND_DATA => Vec::new(),
+			_ => return Err(EofError::InvalidHeader),
+		};
+		let data_size_offset = reader.position;
+		let data_size = reader.u16()? as usize;
+		reader.kind(TERMINATOR)?;
+
+		let types_start = reader.position;
+		let (code_sections, code_end) = sections(types_start + types_size, &code_sizes);
+		let (container_sections, containers_end) = sections(code_end, &container_sizes);
+		if raw.len() < containers_end {
+			return Err(EofError::InvalidContainerSize);
+		}
+
+		let types = raw[types_start..(types_start + types_size)]
+			.chunks(4)
+			.map(|entry| EofTypes {
+				inputs: entry[0],
+				outputs: entry[1],
+				max_stack_increase: u16::from_be_bytes([entry[2], entry[3]]),
+			})
+			.collect();
+
+		Ok(Self {
+			types,
+			code_sections,
+			container_sections,
+			data: containers_end..min(raw.len(), containers_end + data_size),
+			data_size,
+			data_size_offset,
+		})
+	}
+
+	/// Size of the container with its full declared data section.
+	pub fn size(&self) -> usize {
+		self.data.start + self.data_size
+	}
+
+	/// Whether the data section is shorter than declared.
+	pub fn is_data_truncated(&self) -> bool {
+		self.data.len() < self.data_size
+	}
+
+	/// Parse and validate a top-level container of the given kind, including
+	/// all of its subcontainers.
+	pub fn validate(raw: &[u8], kind: ContainerKind) -> Result<Self, EofError> {
+		validate_container(raw, kind, false)
+	}
+
+	/// Build the container deployed by `RETURNCONTRACT`, appending `aux_data`
+	/// to the data section of this container and updating the declared data
+	/// size. Returns `None` if the data section would stay shorter than
+	/// declared or outgrow the 16-bit size field.
+	pub fn with_aux_data(&self, raw: &[u8], aux_data: &[u8]) -> Option<Vec<u8>> {
+		let data_size = self.data.len() + aux_data.len();
+		if data_size < self.data_size || data_size > u16::MAX as usize {
+			return None;
+		}
+
+		let mut code = Vec::with_capacity(raw.len() + aux_data.len());
+		code.extend_from_slice(raw);
+		code.extend_from_slice(aux_data);
+		code[self.data_size_offset..(self.data_size_offset + 2)]
+			.copy_from_slice(&(data_size as u16).to_be_bytes());
+		Some(code)
+	}
+}
+
+fn validate_container(
+	raw: &[u8],
+	kind: ContainerKind,
+	allow_truncated_data: bool,
+) -> Result<EofContainer, EofError> {
+	let container = if allow_truncated_data {
+		EofContainer::parse_truncated(raw)?
+	} else {
+		EofContainer::parse(raw)?
+	};
+
+	for (index, types) in container.types.iter().enumerate() {
+		let first_is_valid = index != 0 || (types.inputs == 0 && !types.is_returning());
+		if !first_is_valid
+			|| types.inputs > MAX_SECTION_IO
+			|| (types.outputs > MAX_SECTION_IO && types.is_returning())
+			|| types.max_stack_increase as usize > MAX_STACK_HEIGHT
+		{
+			return Err(EofError::InvalidTypeSection);
+		}
+	}
+
+	let mut container_kinds = vec![None; container.container_sections.len()];
+	let mut reached = vec![false; container.code_sections.len()];
+	let mut queue = vec![0];
+	reached[0] = true;
+	while let Some(section) = queue.pop() {
+		let code = &raw[container.code_sections[section].clone()];
+		let targets = validate_code(&container, section, code, kind, &mut container_kinds)?;
+		for target in targets {
+			if !reached[target] {
+				reached[target] = true;
+				queue.push(target);
+			}
+		}
+	}
+	if reached.iter().any(|reached| !reached) {
+		return Err(EofError::UnreachableCodeSection);
+	}
+
+	for (range, kind) in container.container_sections.iter().zip(container_kinds) {
+		let kind = kind.ok_or(EofError::UnreferencedContainer)?;
+		validate_container(&raw[range.clone()], kind, kind == ContainerKind::Runtime)?;
+	}
+
+	Ok(container)
+}
+
+/// Stack inputs and outputs of instructions valid in EOF code, or `None` for
+/// undefined and deprecated ones. Instructions whose stack effect depends on
+/// their immediate or on the type section have placeholder entries.
+fn stack_io(opcode: Opcode) -> Option<(usize, usize)> {
+	let value = opcode.as_u8();
+	Some(match value {
+		0x00 => (0, 0),
+		0x01..=0x07 | 0x0a | 0x0b => (2, 1),
+		0x08 | 0x09 => (3, 1),
+		0x10..=0x14 | 0x16..=0x18 | 0x1a..=0x1d => (2, 1),
+		0x15 | 0x19 => (1, 1),
+		0x20 => (2, 1),
+		0x30 | 0x32..=0x34 | 0x36 | 0x3a | 0x3d => (0, 1),
+		0x31 | 0x35 => (1, 1),
+		0x37 | 0x3e => (3, 0),
+		0x40 | 0x49 => (1, 1),
+		0x41..=0x48 | 0x4a => (0, 1),
+		0x50 => (1, 0),
+		0x51 | 0x54 | 0x5c => (1, 1),
+		0x52 | 0x53 | 0x55 | 0x5d => (2, 0),
+		0x59 | 0x5f => (0, 1),
+		0x5b => (0, 0),
+		0x5e => (3, 0),
+		0x60..=0x7f => (0, 1),
+		0x80..=0x8f => {
+			let n = (value - 0x80 + 1) as usize;
+			(n, n + 1)
+		}
+		0x90..=0x9f => {
+			let n = (value - 0x90 + 2) as usize;
+			(n, n)
+		}
+		0xa0..=0xa4 => ((value - 0xa0 + 2) as usize, 0),
+		0xd0 => (1, 1),
+		0xd1 | 0xd2 => (0, 1),
+		0xd3 => (3, 0),
+		0xe0 => (0, 0),
+		0xe1 | 0xe2 => (1, 0),
+		0xe3..=0xe8 => (0, 0),
+		0xec => (4, 1),
+		0xee => (2, 0),
+		0xf3 | 0xfd => (2, 0),
+		0xf7 => (1, 1),
+		0xf8 => (4, 1),
+		0xf9 | 0xfb => (3, 1),
+		0xfe => (0, 0),
+		_ => return None,
+	})
+}
+
+fn is_terminating(opcode: Opcode) -> bool {
+	matches!(
+		opcode,
+		Opcode::STOP
+			| Opcode::RETURN
+			| Opcode::REVERT
+			| Opcode::INVALID
+			| Opcode::RETF
+			| Opcode::JUMPF
+			| Opcode::RETURNCONTRACT
+			| Opcode::RJUMP
+	)
+}
+
+/// Size of the immediate of the instruction at `position`, if it fits in the
+/// code.
+fn immediate_size(code: &[u8], position: usize) -> Result<usize, EofError> {
+	let opcode = Opcode(code[position]);
+	let size = match opcode {
+		Opcode::RJUMP | Opcode::RJUMPI | Opcode::CALLF | Opcode::JUMPF | Opcode::DATALOADN => 2,
+		Opcode::DUPN
+		| Opcode::SWAPN
+		| Opcode::EXCHANGE
+		| Opcode::EOFCREATE
+		| Opcode::RETURNCONTRACT => 1,
+		Opcode::RJUMPV => {
+			let max_index = *code.get(position + 1).ok_or(EofError::TruncatedImmediate)?;
+			1 + 2 * (max_index as usize + 1)
+		}
+		_ => opcode.is_push().unwrap_or(0) as usize,
+	};
+	if position + size >= code.len() && size > 0 {
+		return Err(EofError::TruncatedImmediate);
+	}
+	Ok(size)
+}
+
+fn read_u16(code: &[u8], position: usize) -> usize {
+	u16::from_be_bytes([code[position], code[position + 1]]) as usize
+}
+
+fn read_i16(code: &[u8], position: usize) -> isize {
+	i16::from_be_bytes([code[position], code[position + 1]]) as isize
+}
+
+/// Targets of the relative jump at `position`, whose next instruction is at
+/// `next`.
+fn jump_targets(code: &[u8], position: usize, next: usize) -> Vec<isize> {
+	match Opcode(code[position]) {
+		Opcode::RJUMP | Opcode::RJUMPI => vec![next as isize + read_i16(code, position + 1)],
+		Opcode::RJUMPV => (0..=code[position + 1] as usize)
+			.map(|case| next as isize + read_i16(code, position + 2 + 2 * case))
+			.collect(),
+		_ => Vec::new(),
+	}
+}
+
+/// Validate a code section, returning the code sections it calls or jumps to.
+fn validate_code(
+	container: &EofContainer,
+	section: usize,
+	code: &[u8],
+	kind: ContainerKind,
+	container_kinds: &mut [Option<ContainerKind>],
+) -> Result<Vec<usize>, EofError> {
+	let types = &container.types;
+	let section_types = types[section];
+
+	// Instruction pass: opcodes, immediates and their targets.
+	let mut instructions = vec![false; code.len()];
+	let mut targets = Vec::new();
+	let mut returns = false;
+	let mut position = 0;
+	while position < code.len() {
+		let opcode = Opcode(code[position]);
+		if stack_io(opcode).is_none() {
+			return Err(EofError::UndefinedInstruction(opcode));
+		}
+		instructions[position] = true;
+		let immediate_size = immediate_size(code, position)?;
+
+		match opcode {
+			Opcode::CALLF | Opcode::JUMPF => {
+				let target = read_u16(code, position + 1);
+				let target_types = types.get(target).ok_or(EofError::InvalidCodeSectionIndex)?;
+				if opcode == Opcode::CALLF && !target_types.is_returning() {
+					return Err(EofError::InvalidCallfTarget);
+				}
+				if opcode == Opcode::JUMPF && target_types.is_returning() {
+					returns = true;
+				}
+				targets.push(target);
+			}
+			Opcode::RETF => returns = true,
+			Opcode::DATALOADN if read_u16(code, position + 1) + 32 > container.data_size => {
+				return Err(EofError::InvalidDataOffset);
+			}
+			Opcode::EOFCREATE | Opcode::RETURNCONTRACT => {
+				let index = code[position + 1] as usize;
+				let referenced_kind = if opcode == Opcode::EOFCREATE {
+					ContainerKind::Initcode
+				} else {
+					ContainerKind::Runtime
+				};
+				let container_kind = container_kinds
+					.get_mut(index)
+					.ok_or(EofError::InvalidContainerIndex)?;
+				if matches!(container_kind, Some(kind) if *kind != referenced_kind) {
+					return Err(EofError::IncompatibleContainerKind);
+				}
+				*container_kind = Some(referenced_kind);
+			}
+			_ => (),
+		}
+
+		let is_compatible = match kind {
+			ContainerKind::Runtime => opcode != Opcode::RETURNCONTRACT,
+			ContainerKind::Initcode => opcode != Opcode::RETURN && opcode != Opcode::STOP,
+		};
+		if !is_compatible {
+			return Err(EofError::IncompatibleContainerKind);
+		}
+
+		position += 1 + immediate_size;
+	}
+	if returns != section_types.is_returning() {
+		return Err(EofError::InvalidNonReturning);
+	}
+
+	// Stack pass: instructions are visited in order, so every forward jump
+	// target is known before it is reached, and every backward jump target is
+	// already final.
+	let inputs = section_types.inputs as usize;
+	let mut heights: Vec<Option<(usize, usize)>> = vec![None; code.len()];
+	heights[0] = Some((inputs, inputs));
+	let mut max_height = inputs;
+	let mut position = 0;
+	while position < code.len() {
+		let opcode = Opcode(code[position]);
+		let (min_height, max_height_here) = heights[position].ok_or(EofError::UnreachableCode)?;
+		let next = position + 1 + immediate_size(code, position)?;
+
+		let (required, outputs) = match opcode {
+			Opcode::CALLF | Opcode::JUMPF => {
+				let target_types = types[read_u16(code, position + 1)];
+				let target_inputs = target_types.inputs as usize;
+				if max_height_here + target_types.max_stack_increase as usize > STACK_LIMIT {
+					return Err(EofError::StackOverflow);
+				}
+				if opcode == Opcode::JUMPF && target_types.is_returning() {
+					if target_types.outputs > section_types.outputs {
+						return Err(EofError::InvalidJumpfTarget);
+					}
+					let expected = section_types.outputs as usize + target_inputs
+						- target_types.outputs as usize;
+					if min_height != expected || max_height_here != expected {
+						return Err(EofError::StackHeightMismatch);
+					}
+				}
+				let target_outputs = if target_types.is_returning() {
+					target_types.outputs as usize
+				} else {
+					0
+				};
+				(target_inputs, target_outputs)
+			}
+			Opcode::RETF => {
+				let outputs = section_types.outputs as usize;
+				if min_height != outputs || max_height_here != outputs {
+					return Err(EofError::StackHeightMismatch);
+				}
+				(outputs, 0)
+			}
+			Opcode::DUPN => {
+				let n = code[position + 1] as usize + 1;
+				(n, n + 1)
+			}
+			Opcode::SWAPN => {
+				let n = code[position + 1] as usize + 2;
+				(n, n)
+			}
+			Opcode::EXCHANGE => {
+				let immediate = code[position + 1] as usize;
+				let n = (immediate >> 4) + (immediate & 0x0f) + 3;
+				(n, n)
+			}
+			_ => stack_io(opcode).expect("checked in the instruction pass"),
+		};
+		if min_height < required {
+			return Err(EofError::StackUnderflow);
+		}
+		let after = (
+			min_height - required + outputs,
+			max_height_here - required + outputs,
+		);
+		max_height = max(max_height, after.1);
+		if max_height > MAX_STACK_HEIGHT {
+			return Err(EofError::StackOverflow);
+		}
+
+		let mut successors = jump_targets(code, position, next);
+		if !is_terminating(opcode) {
+			if next >= code.len() {
+				return Err(EofError::NoTerminatingInstruction);
+			}
+			successors.push(next as isize);
+		}
+		for successor in successors {
+			if successor < 0
+				|| successor as usize >= code.len()
+				|| !instructions[successor as usize]
+			{
+				return Err(EofError::InvalidJumpDestination);
+			}
+			let successor = successor as usize;
+			if successor <= position {
+				if heights[successor] != Some(after) {
+					return Err(EofError::StackHeightMismatch);
+				}
+			} else {
+				heights[successor] = Some(match heights[successor] {
+					Some((low, high)) => (min(low, after.0), max(high, after.1)),
+					None => after,
+				});
+			}
+		}
+
+		position = next;
+	}
+
+	if max_height - inputs != section_types.max_stack_increase as usize {
+		return Err(EofError::InvalidMaxStackIncrease);
+	}
+
+	Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Build a container from code sections with their types, subcontainers
+	/// and data.
+	fn container(sections: &[(u8, u8, u16, &[u8])], containers: &[&[u8]], data: &[u8]) -> Vec<u8> {
+		let mut raw = vec![0xef, 0x00, 0x01, KIND_TYPES];
+		raw.extend_from_slice(&(sections.len() as u16 * 4).to_be_bytes());
+		raw.push(KIND_CODE);
+		raw.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+		for (_, _, _, code) in sections {
+			raw.extend_from_slice(&(code.len() as u16).to_be_bytes());
+		}
+		if !containers.is_empty() {
+			raw.push(KIND_CONTAINER);
+			raw.extend_from_slice(&(containers.len() as u16).to_be_bytes());
+			for container in containers {
+				raw.extend_from_slice(&(container.len() as u16).to_be_bytes());
+			}
+		}
+		raw.push(KIND_DATA);
+		raw.extend_from_slice(&(data.len() as u16).to_be_bytes());
+		raw.push(TERMINATOR);
+		for (inputs, outputs, max_stack_increase, _) in sections {
+			raw.push(*inputs);
+			raw.push(*outputs);
+			raw.extend_from_slice(&max_stack_increase.to_be_bytes());
+		}
+		for (_, _, _, code) in sections {
+			raw.extend_from_slice(code);
+		}
+		for container in containers {
+			raw.extend_from_slice(container);
+		}
+		raw.extend_from_slice(data);
+		raw
+	}
+
+	fn runtime(code: &[u8], max_stack_increase: u16) -> Vec<u8> {
+		container(&[(0, NON_RETURNING, max_stack_increase, code)], &[], &[])
+	}
+
+	fn validate(raw: &[u8]) -> Result<EofContainer, EofError> {
+		EofContainer::validate(raw, ContainerKind::Runtime)
+	}
+
+	#[test]
+	fn parses_sections() {
+		let raw = container(
+			&[
+				(0, NON_RETURNING, 1, &[0x60, 0x01, 0xe3, 0x00, 0x01, 0x00]),
+				(1, 1, 0, &[0xe4]),
+			],
+			&[],
+			&[0xaa, 0xbb],
+		);
+		let parsed = validate(&raw).unwrap();
+		assert_eq!(parsed.code_sections.len(), 2);
+		assert_eq!(&raw[parsed.code_sections[1].clone()], &[0xe4]);
+		assert_eq!(&raw[parsed.data.clone()], &[0xaa, 0xbb]);
+		assert_eq!(parsed.size(), raw.len());
+	}
+
+	#[test]
+	fn rejects_malformed_headers() {
+		let raw = runtime(&[0x00], 0);
+		assert_eq!(validate(&raw[..2]), Err(EofError::InvalidVersion));
+		assert_eq!(validate(&[0xef, 0x01, 0x01]), Err(EofError::InvalidMagic));
+		assert_eq!(validate(&raw[..6]), Err(EofError::InvalidHeader));
+
+		let mut trailing = raw.clone();
+		trailing.push(0x00);
+		assert_eq!(validate(&trailing), Err(EofError::InvalidContainerSize));
+
+		let mut no_code = raw.clone();
+		no_code[7..9].copy_from_slice(&[0x00, 0x00]);
+		assert_eq!(validate(&no_code), Err(EofError::InvalidHeader));
+	}
+
+	#[test]
+	fn parses_prefix_and_truncated_data() {
+		let raw = container(&[(0, NON_RETURNING, 0, &[0x00])], &[], &[1, 2, 3]);
+		let mut with_calldata = raw.clone();
+		with_calldata.extend_from_slice(&[9, 9]);
+		assert_eq!(
+			EofContainer::parse_prefix(&with_calldata).unwrap().size(),
+			raw.len()
+		);
+		assert_eq!(
+			EofContainer::parse(&raw[..raw.len() - 1]),
+			Err(EofError::TruncatedData)
+		);
+
+		let truncated = EofContainer::parse_truncated(&raw[..raw.len() - 1]).unwrap();
+		assert!(truncated.is_data_truncated());
+		assert_eq!(truncated.with_aux_data(&raw[..raw.len() - 1], &[]), None);
+		let deployed = truncated
+			.with_aux_data(&raw[..raw.len() - 1], &[7, 8])
+			.unwrap();
+		let deployed = EofContainer::parse(&deployed).unwrap();
+		assert_eq!(deployed.data_size, 4);
+	}
+
+	#[test]
+	fn rejects_invalid_instructions() {
+		// JUMP, GAS and CODESIZE do not exist in EOF.
+		for opcode in [0x56, 0x5a, 0x38, 0xf1, 0xff] {
+			assert_eq!(
+				validate(&runtime(&[opcode, 0x00], 0)),
+				Err(EofError::UndefinedInstruction(Opcode(opcode)))
+			);
+		}
+		assert_eq!(
+			validate(&runtime(&[0x61, 0x00], 1)),
+			Err(EofError::TruncatedImmediate)
+		);
+		assert_eq!(
+			validate(&runtime(&[0x60, 0x00, 0x50], 1)),
+			Err(EofError::NoTerminatingInstruction)
+		);
+	}
+
+	#[test]
+	fn validates_relative_jumps() {
+		// PUSH0 RJUMPI(+1) INVALID STOP
+		assert!(validate(&runtime(&[0x5f, 0xe1, 0x00, 0x01, 0xfe, 0x00], 1)).is_ok());
+		// Jumping into the PUSH1 immediate.
+		assert_eq!(
+			validate(&runtime(&[0xe0, 0x00, 0x01, 0x60, 0x00, 0x00], 1)),
+			Err(EofError::InvalidJumpDestination)
+		);
+		// Code after an unconditional jump is never reached.
+		assert_eq!(
+			validate(&runtime(&[0xe0, 0x00, 0x01, 0x00, 0x00], 0)),
+			Err(EofError::UnreachableCode)
+		);
+		// A loop pushing on every iteration.
+		assert_eq!(
+			validate(&runtime(&[0x5f, 0xe0, 0xff, 0xfc], 1)),
+			Err(EofError::StackHeightMismatch)
+		);
+		// PUSH0 RJUMPV[+0, +1] PUSH0 STOP: the STOP is reached with 0 or 1 items.
+		assert!(validate(&runtime(
+			&[0x5f, 0xe2, 0x01, 0x00, 0x00, 0x00, 0x01, 0x5f, 0x00],
+			1
+		))
+		.is_ok());
+	}
+
+	#[test]
+	fn validates_stack() {
+		assert_eq!(
+			validate(&runtime(&[0x01, 0x00], 0)),
+			Err(EofError::StackUnderflow)
+		);
+		assert_eq!(
+			validate(&runtime(&[0x5f, 0x5f, 0x00], 1)),
+			Err(EofError::InvalidMaxStackIncrease)
+		);
+		// DUPN 1 needs two items.
+		assert_eq!(
+			validate(&runtime(&[0x5f, 0xe6, 0x01, 0x00], 2)),
+			Err(EofError::StackUnderflow)
+		);
+		// EXCHANGE 0x00 swaps the second and third items.
+		assert!(validate(&runtime(&[0x5f, 0x5f, 0x5f, 0xe8, 0x00, 0x00], 3)).is_ok());
+	}
+
+	#[test]
+	fn validates_sections() {
+		// CALLF a section with 1 input and 1 output.
+		let callee: &[u8] = &[0xe4];
+		let caller: &[u8] = &[0x5f, 0xe3, 0x00, 0x01, 0x00];
+		assert!(validate(&container(
+			&[(0, NON_RETURNING, 1, caller), (1, 1, 0, callee)],
+			&[],
+			&[]
+		))
+		.is_ok());
+		// The section is never called.
+		assert_eq!(
+			validate(&container(
+				&[(0, NON_RETURNING, 0, &[0x00]), (1, 1, 0, callee)],
+				&[],
+				&[]
+			)),
+			Err(EofError::UnreachableCodeSection)
+		);
+		// CALLF a non-returning section.
+		assert_eq!(
+			validate(&container(
+				&[
+					(0, NON_RETURNING, 0, &[0xe3, 0x00, 0x01, 0x00]),
+					(0, NON_RETURNING, 0, &[0x00])
+				],
+				&[],
+				&[]
+			)),
+			Err(EofError::InvalidCallfTarget)
+		);
+		// A section declared returning without RETF.
+		assert_eq!(
+			validate(&container(
+				&[(0, NON_RETURNING, 1, caller), (1, 1, 0, &[0x00])],
+				&[],
+				&[]
+			)),
+			Err(EofError::InvalidNonReturning)
+		);
+		// The first section must not take inputs.
+		assert_eq!(
+			validate(&container(&[(1, NON_RETURNING, 0, &[0x00])], &[], &[])),
+			Err(EofError::InvalidTypeSection)
+		);
+	}
+
+	#[test]
+	fn validates_data_and_subcontainers() {
+		assert_eq!(
+			validate(&container(
+				&[(0, NON_RETURNING, 1, &[0xd1, 0x00, 0x01, 0x00])],
+				&[],
+				&[0; 32]
+			)),
+			Err(EofError::InvalidDataOffset)
+		);
+
+		let deployed = runtime(&[0x00], 0);
+		// PUSH0 PUSH0 RETURNCONTRACT 0
+		let initcode = container(
+			&[(0, NON_RETURNING, 2, &[0x5f, 0x5f, 0xee, 0x00])],
+			&[&deployed],
+			&[],
+		);
+		assert!(EofContainer::validate(&initcode, ContainerKind::Initcode).is_ok());
+		assert_eq!(
+			validate(&initcode),
+			Err(EofError::IncompatibleContainerKind)
+		);
+
+		// PUSH0 PUSH0 PUSH0 PUSH0 EOFCREATE 0 STOP
+		let creator = container(
+			&[(
+				0,
+				NON_RETURNING,
+				4,
+				&[0x5f, 0x5f, 0x5f, 0x5f, 0xec, 0x00, 0x00],
+			)],
+			&[&initcode],
+			&[],
+		);
+		assert!(validate(&creator).is_ok());
+		// Deploying the initcode itself is not allowed.
+		let creator = container(
+			&[(0, NON_RETURNING, 2, &[0x5f, 0x5f, 0xee, 0x00])],
+			&[&initcode],
+			&[],
+		);
+		assert_eq!(
+			EofContainer::validate(&creator, ContainerKind::Initcode),
+			Err(EofError::IncompatibleContainerKind)
+		);
+		assert_eq!(
+			validate(&container(
+				&[(0, NON_RETURNING, 0, &[0x00])],
+				&[&deployed],
+				&[]
+			)),
+			Err(EofError::UnreferencedContainer)
+		);
+	}
+}
@@ -161,9 +161,16 @@ pub enum ExitError {
 	#[cfg_attr(feature = "with-codec", codec(index = 14))]
 	MaxNonce,
 
-	// blockstm specific error
+	/// A value read depends on a lower transaction of the block whose writes
+	/// are only estimates yet (Block-STM).
 	#[cfg_attr(feature = "with-codec", codec(index = 16))]
-	NotEstimatedYet,
+	NotEstimatedYet {
+		/// Index of the blocking transaction in the block.
+		txn_idx: u64,
+		/// Incarnation of the blocking transaction whose writes became
+		/// estimates.
+		incarnation: u64,
+	},
 }
 
 impl From<ExitError> for ExitReason {
@@ -0,0 +1,235 @@
+use super::Control;
+use crate::{EofContainer, ExitError, ExitFatal, ExitSucceed, Machine, Opcode};
+use primitive_types::{H256, U256};
+
+/// Maximum depth of the `CALLF` return stack.
+const RETURN_STACK_LIMIT: usize = 1024;
+
+macro_rules! eof_or_fail {
+	( $machine:expr, $opcode:expr ) => {
+		match $machine.eof.as_mut() {
+			Some(eof) => eof,
+			None => return Control::Exit(ExitError::InvalidCode($opcode).into()),
+		}
+	};
+}
+
+fn immediate_u16(state: &Machine, position: usize) -> usize {
+	u16::from_be_bytes([state.code[position + 1], state.code[position + 2]]) as usize
+}
+
+fn relative_target(state: &Machine, offset_position: usize, next: usize) -> usize {
+	let offset = i16::from_be_bytes([state.code[offset_position], state.code[offset_position + 1]]);
+	(next as isize + offset as isize) as usize
+}
+
+#[inline]
+pub fn rjump(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	Control::Jump(relative_target(state, position + 1, position + 3))
+}
+
+#[inline]
+pub fn rjumpi(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	pop!(state, condition);
+
+	if condition != H256::zero() {
+		Control::Jump(relative_target(state, position + 1, position + 3))
+	} else {
+		Control::Continue(3)
+	}
+}
+
+#[inline]
+pub fn rjumpv(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	pop_u256!(state, case);
+
+	let count = state.code[position + 1] as usize + 1;
+	let next = position + 2 + 2 * count;
+	if case < U256::from(count) {
+		Control::Jump(relative_target(
+			state,
+			position + 2 + 2 * case.as_usize(),
+			next,
+		))
+	} else {
+		Control::Continue(next - position)
+	}
+}
+
+#[inline]
+pub fn callf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let section = immediate_u16(state, position);
+	let stack_len = state.stack.len();
+	let stack_limit = state.stack.limit();
+	let eof = eof_or_fail!(state, opcode);
+
+	let max_stack_increase = eof.container.types[section].max_stack_increase as usize;
+	if eof.return_stack.len() >= RETURN_STACK_LIMIT || stack_len + max_stack_increase > stack_limit
+	{
+		return Control::Exit(ExitError::StackOverflow.into());
+	}
+
+	eof.return_stack.push(position + 3);
+	Control::Jump(eof.container.code_sections[section].start)
+}
+
+#[inline]
+pub fn retf(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let eof = eof_or_fail!(state, opcode);
+
+	match eof.return_stack.pop() {
+		Some(position) => Control::Jump(position),
+		None => Control::Exit(ExitError::InvalidCode(opcode).into()),
+	}
+}
+
+#[inline]
+pub fn jumpf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let section = immediate_u16(state, position);
+	let stack_len = state.stack.len();
+	let stack_limit = state.stack.limit();
+	let eof = eof_or_fail!(state, opcode);
+
+	let max_stack_increase = eof.container.types[section].max_stack_increase as usize;
+	if stack_len + max_stack_increase > stack_limit {
+		return Control::Exit(ExitError::StackOverflow.into());
+	}
+
+	Control::Jump(eof.container.code_sections[section].start)
+}
+
+#[inline]
+pub fn dupn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	let n = state.code[position + 1] as usize;
+
+	let value = match state.stack.peek(n) {
+		Ok(value) => value,
+		Err(e) => return Control::Exit(e.into()),
+	};
+	push!(state, value);
+	Control::Continue(2)
+}
+
+#[inline]
+pub fn swapn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	let n = state.code[position + 1] as usize + 1;
+
+	exchange_items(state, 0, n)
+}
+
+#[inline]
+pub fn exchange(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	eof_or_fail!(state, opcode);
+	let immediate = state.code[position + 1] as usize;
+	let n = (immediate >> 4) + 1;
+	let m = (immediate & 0x0f) + 1;
+
+	exchange_items(state, n, n + m)
+}
+
+fn exchange_items(state: &mut Machine, a: usize, b: usize) -> Control {
+	let val1 = match state.stack.peek(a) {
+		Ok(value) => value,
+		Err(e) => return Control::Exit(e.into()),
+	};
+	let val2 = match state.stack.peek(b) {
+		Ok(value) => value,
+		Err(e) => return Control::Exit(e.into()),
+	};
+	try_or_fail!(state.stack.set(a, val2));
+	try_or_fail!(state.stack.set(b, val1));
+	Control::Continue(2)
+}
+
+fn data_word(state: &Machine, data: &core::ops::Range<usize>, offset: U256) -> H256 {
+	let mut load = [0u8; 32];
+	if offset < U256::from(data.len()) {
+		let start = data.start + offset.as_usize();
+		let end = core::cmp::min(start + 32, data.end);
+		load[..(end - start)].copy_from_slice(&state.code[start..end]);
+	}
+	H256::from(load)
+}
+
+#[inline]
+pub fn dataload(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let data = eof_or_fail!(state, opcode).container.data.clone();
+	pop_u256!(state, offset);
+
+	let value = data_word(state, &data, offset);
+	push!(state, value);
+	Control::Continue(1)
+}
+
+#[inline]
+pub fn dataloadn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let data = eof_or_fail!(state, opcode).container.data.clone();
+	let offset = U256::from(immediate_u16(state, position));
+
+	let value = data_word(state, &data, offset);
+	push!(state, value);
+	Control::Continue(3)
+}
+
+#[inline]
+pub fn datasize(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let size = U256::from(eof_or_fail!(state, opcode).container.data.len());
+	push_u256!(state, size);
+	Control::Continue(1)
+}
+
+#[inline]
+pub fn datacopy(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let data = eof_or_fail!(state, opcode).container.data.clone();
+	pop_u256!(state, memory_offset, data_offset, len);
+
+	try_or_fail!(state.memory.resize_offset(memory_offset, len));
+	if len == U256::zero() {
+		return Control::Continue(1);
+	}
+
+	match state
+		.memory
+		.copy_large(memory_offset, data_offset, len, &state.code[data])
+	{
+		Ok(()) => Control::Continue(1),
+		Err(e) => Control::Exit(e.into()),
+	}
+}
+
+#[inline]
+pub fn returncontract(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let index = state.code[position + 1] as usize;
+	let range = eof_or_fail!(state, opcode).container.container_sections[index].clone();
+	pop_u256!(state, aux_offset, aux_len);
+
+	try_or_fail!(state.memory.resize_offset(aux_offset, aux_len));
+	let aux_data = if aux_len == U256::zero() {
+		alloc::vec::Vec::new()
+	} else {
+		let aux_offset = as_usize_or_fail!(aux_offset);
+		let aux_len = as_usize_or_fail!(aux_len);
+		state.memory.get(aux_offset, aux_len)
+	};
+
+	let subcontainer = &state.code[range];
+	let deploy_code = match EofContainer::parse_truncated(subcontainer)
+		.ok()
+		.and_then(|container| container.with_aux_data(subcontainer, &aux_data))
+	{
+		Some(code) => code,
+		None => {
+			return Control::Exit(
+				ExitError::Other("deployed container data section is invalid".into()).into(),
+			)
+		}
+	};
+
+	eof_or_fail!(state, opcode).deploy_code = Some(deploy_code);
+	Control::Exit(ExitSucceed::Returned.into())
+}
@@ -1,6 +1,6 @@
 use super::Control;
 use crate::{ExitError, ExitFatal, ExitRevert, ExitSucceed, Machine};
-use core::cmp::min;
+use core::cmp::{max, min};
 use primitive_types::{H256, U256};
 
 #[inline]
@@ -151,6 +151,26 @@ pub fn msize(state: &mut Machine) -> Control {
 	Control::Continue(1)
 }
 
+#[inline]
+pub fn mcopy(state: &mut Machine) -> Control {
+	pop_u256!(state, dst, src, len);
+
+	try_or_fail!(state.memory.resize_offset(max(dst, src), len));
+	if len.is_zero() {
+		return Control::Continue(1);
+	}
+
+	let dst = as_usize_or_fail!(dst);
+	let src = as_usize_or_fail!(src);
+	let len = as_usize_or_fail!(len);
+	// Regions may overlap, so copy out of memory before writing back.
+	let data = state.memory.get(src, len);
+	match state.memory.set(dst, &data, Some(len)) {
+		Ok(()) => Control::Continue(1),
+		Err(e) => Control::Exit(e.into()),
+	}
+}
+
 #[inline]
 pub fn push(state: &mut Machine, n: usize, position: usize) -> Control {
 	let end = min(position + 1 + n, state.code.len());
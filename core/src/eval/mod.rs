@@ -2,6 +2,7 @@
 mod macros;
 mod arithmetic;
 mod bitwise;
+mod eof;
 mod misc;
 
 use crate::{ExitError, ExitReason, ExitSucceed, Machine, Opcode};
@@ -172,6 +173,10 @@ fn eval_msize(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control
 	self::misc::msize(state)
 }
 
+fn eval_mcopy(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	self::misc::mcopy(state)
+}
+
 fn eval_jumpdest(_state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
 	Control::Continue(1)
 }
@@ -448,6 +453,62 @@ fn eval_invalid(_state: &mut Machine, _opcode: Opcode, _position: usize) -> Cont
 	Control::Exit(ExitError::DesignatedInvalid.into())
 }
 
+fn eval_rjump(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::rjump(state, opcode, position)
+}
+
+fn eval_rjumpi(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::rjumpi(state, opcode, position)
+}
+
+fn eval_rjumpv(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::rjumpv(state, opcode, position)
+}
+
+fn eval_callf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::callf(state, opcode, position)
+}
+
+fn eval_retf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::retf(state, opcode, position)
+}
+
+fn eval_jumpf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::jumpf(state, opcode, position)
+}
+
+fn eval_dupn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::dupn(state, opcode, position)
+}
+
+fn eval_swapn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::swapn(state, opcode, position)
+}
+
+fn eval_exchange(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::exchange(state, opcode, position)
+}
+
+fn eval_dataload(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::dataload(state, opcode, position)
+}
+
+fn eval_dataloadn(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::dataloadn(state, opcode, position)
+}
+
+fn eval_datasize(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::datasize(state, opcode, position)
+}
+
+fn eval_datacopy(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::datacopy(state, opcode, position)
+}
+
+fn eval_returncontract(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::eof::returncontract(state, opcode, position)
+}
+
 fn eval_external(_state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
 	Control::Trap(opcode)
 }
@@ -497,6 +558,7 @@ pub fn eval(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
 		table[Opcode::PC.as_usize()] = eval_pc as _;
 		table[Opcode::MSIZE.as_usize()] = eval_msize as _;
 		table[Opcode::JUMPDEST.as_usize()] = eval_jumpdest as _;
+		table[Opcode::MCOPY.as_usize()] = eval_mcopy as _;
 
 		table[Opcode::PUSH0.as_usize()] = eval_push0 as _;
 		table[Opcode::PUSH1.as_usize()] = eval_push1 as _;
@@ -570,6 +632,21 @@ pub fn eval(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
 		table[Opcode::REVERT.as_usize()] = eval_revert as _;
 		table[Opcode::INVALID.as_usize()] = eval_invalid as _;
 
+		table[Opcode::RJUMP.as_usize()] = eval_rjump as _;
+		table[Opcode::RJUMPI.as_usize()] = eval_rjumpi as _;
+		table[Opcode::RJUMPV.as_usize()] = eval_rjumpv as _;
+		table[Opcode::CALLF.as_usize()] = eval_callf as _;
+		table[Opcode::RETF.as_usize()] = eval_retf as _;
+		table[Opcode::JUMPF.as_usize()] = eval_jumpf as _;
+		table[Opcode::DUPN.as_usize()] = eval_dupn as _;
+		table[Opcode::SWAPN.as_usize()] = eval_swapn as _;
+		table[Opcode::EXCHANGE.as_usize()] = eval_exchange as _;
+		table[Opcode::DATALOAD.as_usize()] = eval_dataload as _;
+		table[Opcode::DATALOADN.as_usize()] = eval_dataloadn as _;
+		table[Opcode::DATASIZE.as_usize()] = eval_datasize as _;
+		table[Opcode::DATACOPY.as_usize()] = eval_datacopy as _;
+		table[Opcode::RETURNCONTRACT.as_usize()] = eval_returncontract as _;
+
 		table
 	};
 
@@ -6,6 +6,7 @@
 
 extern crate alloc;
 
+mod eof;
 mod error;
 mod eval;
 mod external;
@@ -15,6 +16,9 @@ mod stack;
 mod utils;
 mod valids;
 
+pub use crate::eof::{
+	is_eof, ContainerKind, EofContainer, EofError, EofTypes, EOF_MAGIC, EOF_VERSION, NON_RETURNING,
+};
 pub use crate::error::{Capture, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Trap};
 pub use crate::external::ExternalOperation;
 pub use crate::memory::Memory;
@@ -44,6 +48,18 @@ pub struct Machine {
 	memory: Memory,
 	/// Stack.
 	stack: Stack,
+	/// EOF execution state, if the code is an EOF container.
+	eof: Option<EofState>,
+}
+
+/// Execution state of an EOF container.
+struct EofState {
+	/// Parsed container.
+	container: EofContainer,
+	/// Return positions of `CALLF`.
+	return_stack: Vec<usize>,
+	/// Container deployed by `RETURNCONTRACT`.
+	deploy_code: Option<Vec<u8>>,
 }
 
 impl Machine {
@@ -67,6 +83,14 @@ impl Machine {
 	pub fn position(&self) -> &Result<usize, ExitReason> {
 		&self.position
 	}
+	/// Reference of machine code.
+	pub fn code(&self) -> &[u8] {
+		&self.code
+	}
+	/// Reference of the EOF container, if the machine runs one.
+	pub fn eof(&self) -> Option<&EofContainer> {
+		self.eof.as_ref().map(|eof| &eof.container)
+	}
 
 	/// Create a new machine with given code and data.
 	pub fn new(
@@ -85,9 +109,47 @@ impl Machine {
 			valids,
 			memory: Memory::new(memory_limit),
 			stack: Stack::new(stack_limit),
+			eof: None,
+		}
+	}
+
+	/// Create a new machine running a validated EOF container, starting at its
+	/// first code section. Positions stay offsets into the whole container.
+	pub fn new_eof(
+		code: Rc<Vec<u8>>,
+		container: EofContainer,
+		data: Rc<Vec<u8>>,
+		stack_limit: usize,
+		memory_limit: usize,
+	) -> Self {
+		Self {
+			data,
+			code,
+			position: Ok(container.code_sections[0].start),
+			return_range: U256::zero()..U256::zero(),
+			// Relative jumps are checked by validation, and `JUMP` does not
+			// exist in EOF.
+			valids: Valids::new(&[]),
+			memory: Memory::new(memory_limit),
+			stack: Stack::new(stack_limit),
+			eof: Some(EofState {
+				container,
+				return_stack: Vec::new(),
+				deploy_code: None,
+			}),
 		}
 	}
 
+	/// Subcontainer referenced by the immediate of the `EOFCREATE` the machine
+	/// has just trapped on.
+	pub fn eofcreate_container(&self) -> Option<&[u8]> {
+		let position = *self.position.as_ref().ok()?;
+		let eof = self.eof.as_ref()?;
+		let index = *self.code.get(position.checked_sub(1)?)? as usize;
+		let range = eof.container.container_sections.get(index)?.clone();
+		Some(&self.code[range])
+	}
+
 	/// Explicit exit of the machine. Further step will return error.
 	pub fn exit(&mut self, reason: ExitReason) {
 		self.position = Err(reason);
@@ -102,8 +164,13 @@ impl Machine {
 		self.code.get(position).map(|v| (Opcode(*v), &self.stack))
 	}
 
-	/// Copy and get the return value of the machine, if any.
+	/// Copy and get the return value of the machine, if any. For EOF initcode,
+	/// this is the container deployed by `RETURNCONTRACT`.
 	pub fn return_value(&self) -> Vec<u8> {
+		if let Some(code) = self.eof.as_ref().and_then(|eof| eof.deploy_code.as_ref()) {
+			return code.clone();
+		}
+
 		if self.return_range.start > U256::from(usize::MAX) {
 			let mut ret = Vec::new();
 			ret.resize(
@@ -161,7 +228,12 @@ impl Machine {
 					Ok(())
 				}
 				Control::Trap(opcode) => {
-					self.position = Ok(position + 1);
+					// `EOFCREATE` is the only external opcode with an immediate.
+					let immediate_size = match (&self.eof, opcode) {
+						(Some(_), Opcode::EOFCREATE) => 1,
+						_ => 0,
+					};
+					self.position = Ok(position + 1 + immediate_size);
 					Err(Capture::Trap(opcode))
 				}
 			},
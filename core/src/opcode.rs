@@ -93,6 +93,8 @@ impl Opcode {
 	pub const MSIZE: Opcode = Opcode(0x59);
 	/// `JUMPDEST`
 	pub const JUMPDEST: Opcode = Opcode(0x5b);
+	/// `MCOPY`
+	pub const MCOPY: Opcode = Opcode(0x5e);
 
 	/// `PUSHn`
 	pub const PUSH0: Opcode = Opcode(0x5f);
@@ -173,6 +175,35 @@ impl Opcode {
 	/// `INVALID`
 	pub const INVALID: Opcode = Opcode(0xfe);
 
+	/// `DATALOAD`
+	pub const DATALOAD: Opcode = Opcode(0xd0);
+	/// `DATALOADN`
+	pub const DATALOADN: Opcode = Opcode(0xd1);
+	/// `DATASIZE`
+	pub const DATASIZE: Opcode = Opcode(0xd2);
+	/// `DATACOPY`
+	pub const DATACOPY: Opcode = Opcode(0xd3);
+	/// `RJUMP`
+	pub const RJUMP: Opcode = Opcode(0xe0);
+	/// `RJUMPI`
+	pub const RJUMPI: Opcode = Opcode(0xe1);
+	/// `RJUMPV`
+	pub const RJUMPV: Opcode = Opcode(0xe2);
+	/// `CALLF`
+	pub const CALLF: Opcode = Opcode(0xe3);
+	/// `RETF`
+	pub const RETF: Opcode = Opcode(0xe4);
+	/// `JUMPF`
+	pub const JUMPF: Opcode = Opcode(0xe5);
+	/// `DUPN`
+	pub const DUPN: Opcode = Opcode(0xe6);
+	/// `SWAPN`
+	pub const SWAPN: Opcode = Opcode(0xe7);
+	/// `EXCHANGE`
+	pub const EXCHANGE: Opcode = Opcode(0xe8);
+	/// `RETURNCONTRACT`
+	pub const RETURNCONTRACT: Opcode = Opcode(0xee);
+
 	/// See [EIP-3541](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3541.md)
 	pub const EOFMAGIC: Opcode = Opcode(0xef);
 }
@@ -189,6 +220,10 @@ impl Opcode {
 	pub const SELFBALANCE: Opcode = Opcode(0x47);
 	/// `BASEFEE`
 	pub const BASEFEE: Opcode = Opcode(0x48);
+	/// `BLOBHASH`
+	pub const BLOBHASH: Opcode = Opcode(0x49);
+	/// `BLOBBASEFEE`
+	pub const BLOBBASEFEE: Opcode = Opcode(0x4a);
 	/// `ORIGIN`
 	pub const ORIGIN: Opcode = Opcode(0x32);
 	/// `CALLER`
@@ -225,6 +260,10 @@ impl Opcode {
 	pub const SSTORE: Opcode = Opcode(0x55);
 	/// `GAS`
 	pub const GAS: Opcode = Opcode(0x5a);
+	/// `TLOAD`
+	pub const TLOAD: Opcode = Opcode(0x5c);
+	/// `TSTORE`
+	pub const TSTORE: Opcode = Opcode(0x5d);
 	/// `LOGn`
 	pub const LOG0: Opcode = Opcode(0xa0);
 	pub const LOG1: Opcode = Opcode(0xa1);
@@ -247,6 +286,16 @@ impl Opcode {
 	pub const SUICIDE: Opcode = Opcode(0xff);
 	/// `CHAINID`
 	pub const CHAINID: Opcode = Opcode(0x46);
+	/// `EOFCREATE`
+	pub const EOFCREATE: Opcode = Opcode(0xec);
+	/// `RETURNDATALOAD`
+	pub const RETURNDATALOAD: Opcode = Opcode(0xf7);
+	/// `EXTCALL`
+	pub const EXTCALL: Opcode = Opcode(0xf8);
+	/// `EXTDELEGATECALL`
+	pub const EXTDELEGATECALL: Opcode = Opcode(0xf9);
+	/// `EXTSTATICCALL`
+	pub const EXTSTATICCALL: Opcode = Opcode(0xfb);
 }
 
 impl Opcode {
@@ -292,6 +292,11 @@ pub fn address_access_cost(is_cold: bool, regular_value: u64, config: &Config) -
 	}
 }
 
+/// EIP-7702: cost of loading the code of a delegation target.
+pub fn delegation_access_cost(delegation_is_cold: Option<bool>, config: &Config) -> u64 {
+	delegation_is_cold.map_or(0, |is_cold| address_access_cost(is_cold, 0, config))
+}
+
 fn xfer_cost(is_call_or_callcode: bool, transfers_value: bool) -> u64 {
 	if is_call_or_callcode && transfers_value {
 		G_CALLVALUE
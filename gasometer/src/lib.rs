@@ -58,6 +58,7 @@ pub struct Snapshot {
 #[derive(Clone, Debug)]
 pub struct Gasometer<'config> {
 	gas_limit: u64,
+	floor_gas: u64,
 	config: &'config Config,
 	inner: Result<Inner<'config>, ExitError>,
 }
@@ -67,6 +68,7 @@ impl<'config> Gasometer<'config> {
 	pub fn new(gas_limit: u64, config: &'config Config) -> Self {
 		Self {
 			gas_limit,
+			floor_gas: 0,
 			config,
 			inner: Ok(Inner {
 				memory_gas: 0,
@@ -115,6 +117,12 @@ impl<'config> Gasometer<'config> {
 		}
 	}
 
+	#[inline]
+	/// Minimum gas the transaction uses, after refunds. See EIP-7623.
+	pub fn floor_gas(&self) -> u64 {
+		self.floor_gas
+	}
+
 	#[inline]
 	/// Refunded gas.
 	pub fn refunded_gas(&self) -> i64 {
@@ -160,6 +168,11 @@ impl<'config> Gasometer<'config> {
 		Ok(())
 	}
 
+	/// Whether there is enough gas left to deposit `len` bytes of code.
+	pub fn can_deposit(&self, len: usize) -> bool {
+		self.gas() >= len as u64 * consts::G_CODEDEPOSIT
+	}
+
 	#[inline]
 	/// Record `CREATE` code deposit.
 	pub fn record_deposit(&mut self, len: usize) -> Result<(), ExitError> {
@@ -220,18 +233,42 @@ impl<'config> Gasometer<'config> {
 
 	/// Record transaction cost.
 	pub fn record_transaction(&mut self, cost: TransactionCost) -> Result<(), ExitError> {
+		let (zero_data_len, non_zero_data_len) = match cost {
+			TransactionCost::Call {
+				zero_data_len,
+				non_zero_data_len,
+				..
+			}
+			| TransactionCost::Create {
+				zero_data_len,
+				non_zero_data_len,
+				..
+			} => (zero_data_len, non_zero_data_len),
+		};
+		// EIP-7623: the gas limit must cover the calldata floor, which is also
+		// the minimum gas used by the transaction.
+		let floor_gas = match self.config.total_cost_floor_per_token {
+			Some(per_token) => {
+				let tokens = zero_data_len as u64 + non_zero_data_len as u64 * 4;
+				self.config.gas_transaction_call + tokens * per_token
+			}
+			None => 0,
+		};
+
 		let gas_cost = match cost {
 			TransactionCost::Call {
 				zero_data_len,
 				non_zero_data_len,
 				access_list_address_len,
 				access_list_storage_len,
+				authorization_list_len,
 			} => {
 				self.config.gas_transaction_call
 					+ zero_data_len as u64 * self.config.gas_transaction_zero_data
 					+ non_zero_data_len as u64 * self.config.gas_transaction_non_zero_data
 					+ access_list_address_len as u64 * self.config.gas_access_list_address
 					+ access_list_storage_len as u64 * self.config.gas_access_list_storage_key
+					+ authorization_list_len as u64 * self.config.gas_per_empty_account_cost
 			}
 			TransactionCost::Create {
 				zero_data_len,
@@ -257,12 +294,13 @@ impl<'config> Gasometer<'config> {
 			snapshot: self.snapshot(),
 		});
 
-		if self.gas() < gas_cost {
+		if self.gas() < max(gas_cost, floor_gas) {
 			self.inner = Err(ExitError::OutOfGas);
 			return Err(ExitError::OutOfGas);
 		}
 
 		self.inner_mut()?.used_gas += gas_cost;
+		self.floor_gas = floor_gas;
 		Ok(())
 	}
 
@@ -277,9 +315,14 @@ impl<'config> Gasometer<'config> {
 	}
 }
 
-/// Calculate the call transaction cost.
+/// Calculate the call transaction cost. The data lengths also give the
+/// EIP-7623 calldata floor checked by `Gasometer::record_transaction`.
 #[allow(clippy::naive_bytecount)]
-pub fn call_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -> TransactionCost {
+pub fn call_transaction_cost(
+	data: &[u8],
+	access_list: &[(H160, Vec<H256>)],
+	authorization_list_len: usize,
+) -> TransactionCost {
 	let zero_data_len = data.iter().filter(|v| **v == 0).count();
 	let non_zero_data_len = data.len() - zero_data_len;
 	let (access_list_address_len, access_list_storage_len) = count_access_list(access_list);
@@ -289,6 +332,7 @@ pub fn call_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) ->
 		non_zero_data_len,
 		access_list_address_len,
 		access_list_storage_len,
+		authorization_list_len,
 	}
 }
 
@@ -447,6 +491,32 @@ pub fn static_opcode_cost(opcode: Opcode) -> Option<u64> {
 	TABLE[opcode.as_usize()]
 }
 
+/// EIP-7702: whether the delegation target of `target` is cold, if `target`
+/// holds a delegation designator.
+fn delegation_is_cold<H: Handler>(
+	target: H160,
+	config: &Config,
+	handler: &mut H,
+) -> Result<Option<bool>, ExitError> {
+	if !config.has_authorization_list {
+		return Ok(None);
+	}
+	match handler.delegated_address(target)? {
+		Some(delegate) => Ok(Some(handler.is_cold(delegate, None)?)),
+		None => Ok(None),
+	}
+}
+
+/// Target of an EOF call, which halts unless it is a 20-byte address. See
+/// [EIP-7069](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7069.md).
+fn ext_call_target(stack: &Stack) -> Result<H160, ExitError> {
+	let target = stack.peek(0)?;
+	if target[..12].iter().any(|byte| *byte != 0) {
+		return Err(ExitError::Other("call target is not an address".into()));
+	}
+	Ok(target.into())
+}
+
 /// Calculate the opcode cost.
 #[allow(clippy::nonminimal_bool)]
 pub fn dynamic_opcode_cost<H: Handler>(
@@ -478,6 +548,19 @@ pub fn dynamic_opcode_cost<H: Handler>(
 		Opcode::BASEFEE if config.has_base_fee => GasCost::Base,
 		Opcode::BASEFEE => GasCost::Invalid(opcode),
 
+		Opcode::BLOBHASH if config.has_blob_hash => GasCost::VeryLow,
+		Opcode::BLOBHASH => GasCost::Invalid(opcode),
+
+		Opcode::BLOBBASEFEE if config.has_blob_base_fee => GasCost::Base,
+		Opcode::BLOBBASEFEE => GasCost::Invalid(opcode),
+
+		Opcode::TLOAD if config.has_transient_storage => GasCost::WarmStorageRead,
+		Opcode::TSTORE if !is_static && config.has_transient_storage => GasCost::WarmStorageRead,
+
+		Opcode::MCOPY if config.has_mcopy => GasCost::VeryLowCopy {
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		},
+
 		Opcode::EXTCODESIZE => {
 			let target = stack.peek(0)?.into();
 			storage_target = StorageTarget::Address(target);
@@ -512,11 +595,12 @@ pub fn dynamic_opcode_cost<H: Handler>(
 				target_is_cold: handler.is_cold(target, None)?,
 				target_exists: {
 					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
-					handler.exists(target)
+					handler.exists(target)?
 				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
 			}
 		}
-		Opcode::STATICCALL => {
+		Opcode::STATICCALL if config.has_static_call => {
 			let target = stack.peek(1)?.into();
 			storage_target = StorageTarget::Address(target);
 			GasCost::StaticCall {
@@ -524,8 +608,9 @@ pub fn dynamic_opcode_cost<H: Handler>(
 				target_is_cold: handler.is_cold(target, None)?,
 				target_exists: {
 					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
-					handler.exists(target)
+					handler.exists(target)?
 				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
 			}
 		}
 		Opcode::SHA3 => GasCost::Sha3 {
@@ -561,8 +646,9 @@ pub fn dynamic_opcode_cost<H: Handler>(
 				target_is_cold: handler.is_cold(target, None)?,
 				target_exists: {
 					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
-					handler.exists(target)
+					handler.exists(target)?
 				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
 			}
 		}
 		Opcode::DELEGATECALL => GasCost::Invalid(opcode),
@@ -580,19 +666,11 @@ pub fn dynamic_opcode_cost<H: Handler>(
 
 			cfg_if::cfg_if! {
 				if #[cfg(feature="mvcc")] {
-					if let Ok(origin_value) = handler.original_storage(address, index) {
-						if let Ok(cur_value) =  handler.storage(address, index) {
-							GasCost::SStore {
-								original: origin_value,
-								current: cur_value,
-								new: value,
-								target_is_cold: handler.is_cold(address, Some(index))?,
-							}
-						} else {
-							return Err(ExitError::NotEstimatedYet);
-						}
-					} else {
-						return Err(ExitError::NotEstimatedYet);
+					GasCost::SStore {
+						original: handler.original_storage(address, index)?,
+						current: handler.storage(address, index)?,
+						new: value,
+						target_is_cold: handler.is_cold(address, Some(index))?,
 					}
 				}
 				else {
@@ -633,11 +711,11 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			let target = stack.peek(0)?.into();
 			storage_target = StorageTarget::Address(target);
 			GasCost::Suicide {
-				value: handler.balance(address),
+				value: handler.balance(address)?,
 				target_is_cold: handler.is_cold(target, None)?,
 				target_exists: {
 					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
-					handler.exists(target)
+					handler.exists(target)?
 				},
 				already_removed: handler.deleted(address),
 			}
@@ -654,13 +732,76 @@ pub fn dynamic_opcode_cost<H: Handler>(
 				target_is_cold: handler.is_cold(target, None)?,
 				target_exists: {
 					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
-					handler.exists(target)
+					handler.exists(target)?
 				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
 			}
 		}
 
 		Opcode::PUSH0 if config.has_push0 => GasCost::Base,
 
+		Opcode::RJUMP | Opcode::DATASIZE if config.has_eof => GasCost::Base,
+		Opcode::RJUMPI | Opcode::RJUMPV | Opcode::DATALOAD if config.has_eof => GasCost::Fixed(4),
+		Opcode::RETF
+		| Opcode::DUPN
+		| Opcode::SWAPN
+		| Opcode::EXCHANGE
+		| Opcode::DATALOADN
+		| Opcode::RETURNDATALOAD
+			if config.has_eof =>
+		{
+			GasCost::VeryLow
+		}
+		Opcode::CALLF | Opcode::JUMPF if config.has_eof => GasCost::Low,
+		Opcode::DATACOPY if config.has_eof => GasCost::VeryLowCopy {
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		},
+		Opcode::RETURNCONTRACT if config.has_eof => GasCost::Zero,
+		Opcode::EOFCREATE if !is_static && config.has_eof => GasCost::Create,
+		Opcode::EXTCALL
+			if config.has_eof
+				&& (!is_static || U256::from_big_endian(&stack.peek(3)?[..]) == U256::zero()) =>
+		{
+			let target = ext_call_target(stack)?;
+			storage_target = StorageTarget::Address(target);
+			GasCost::Call {
+				value: U256::from_big_endian(&stack.peek(3)?[..]),
+				gas: U256::zero(),
+				target_is_cold: handler.is_cold(target, None)?,
+				target_exists: {
+					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
+					handler.exists(target)?
+				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
+			}
+		}
+		Opcode::EXTDELEGATECALL if config.has_eof => {
+			let target = ext_call_target(stack)?;
+			storage_target = StorageTarget::Address(target);
+			GasCost::DelegateCall {
+				gas: U256::zero(),
+				target_is_cold: handler.is_cold(target, None)?,
+				target_exists: {
+					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
+					handler.exists(target)?
+				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
+			}
+		}
+		Opcode::EXTSTATICCALL if config.has_eof => {
+			let target = ext_call_target(stack)?;
+			storage_target = StorageTarget::Address(target);
+			GasCost::StaticCall {
+				gas: U256::zero(),
+				target_is_cold: handler.is_cold(target, None)?,
+				target_exists: {
+					handler.record_external_operation(evm_core::ExternalOperation::IsEmpty)?;
+					handler.exists(target)?
+				},
+				delegation_is_cold: delegation_is_cold(target, config, handler)?,
+			}
+		}
+
 		_ => GasCost::Invalid(opcode),
 	};
 
@@ -672,15 +813,18 @@ pub fn dynamic_opcode_cost<H: Handler>(
 		| Opcode::LOG1
 		| Opcode::LOG2
 		| Opcode::LOG3
-		| Opcode::LOG4 => Some(MemoryCost {
+		| Opcode::LOG4
+		| Opcode::RETURNCONTRACT => Some(MemoryCost {
 			offset: U256::from_big_endian(&stack.peek(0)?[..]),
 			len: U256::from_big_endian(&stack.peek(1)?[..]),
 		}),
 
-		Opcode::CODECOPY | Opcode::CALLDATACOPY | Opcode::RETURNDATACOPY => Some(MemoryCost {
-			offset: U256::from_big_endian(&stack.peek(0)?[..]),
-			len: U256::from_big_endian(&stack.peek(2)?[..]),
-		}),
+		Opcode::CODECOPY | Opcode::CALLDATACOPY | Opcode::RETURNDATACOPY | Opcode::DATACOPY => {
+			Some(MemoryCost {
+				offset: U256::from_big_endian(&stack.peek(0)?[..]),
+				len: U256::from_big_endian(&stack.peek(2)?[..]),
+			})
+		}
 
 		Opcode::EXTCODECOPY => Some(MemoryCost {
 			offset: U256::from_big_endian(&stack.peek(1)?[..]),
@@ -697,6 +841,20 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			len: U256::from(1),
 		}),
 
+		Opcode::MCOPY => {
+			let len = U256::from_big_endian(&stack.peek(2)?[..]);
+			Some(
+				MemoryCost {
+					offset: U256::from_big_endian(&stack.peek(0)?[..]),
+					len,
+				}
+				.join(MemoryCost {
+					offset: U256::from_big_endian(&stack.peek(1)?[..]),
+					len,
+				}),
+			)
+		}
+
 		Opcode::CREATE | Opcode::CREATE2 => Some(MemoryCost {
 			offset: U256::from_big_endian(&stack.peek(1)?[..]),
 			len: U256::from_big_endian(&stack.peek(2)?[..]),
@@ -713,6 +871,16 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			}),
 		),
 
+		Opcode::EOFCREATE => Some(MemoryCost {
+			offset: U256::from_big_endian(&stack.peek(2)?[..]),
+			len: U256::from_big_endian(&stack.peek(3)?[..]),
+		}),
+
+		Opcode::EXTCALL | Opcode::EXTDELEGATECALL | Opcode::EXTSTATICCALL => Some(MemoryCost {
+			offset: U256::from_big_endian(&stack.peek(1)?[..]),
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		}),
+
 		Opcode::DELEGATECALL | Opcode::STATICCALL => Some(
 			MemoryCost {
 				offset: U256::from_big_endian(&stack.peek(2)?[..]),
@@ -780,52 +948,64 @@ impl<'config> Inner<'config> {
 				value,
 				target_is_cold,
 				target_exists,
+				delegation_is_cold,
 				..
-			} => costs::call_cost(
-				value,
-				target_is_cold,
-				true,
-				true,
-				!target_exists,
-				self.config,
-			),
+			} => {
+				costs::call_cost(
+					value,
+					target_is_cold,
+					true,
+					true,
+					!target_exists,
+					self.config,
+				) + costs::delegation_access_cost(delegation_is_cold, self.config)
+			}
 			GasCost::CallCode {
 				value,
 				target_is_cold,
 				target_exists,
+				delegation_is_cold,
 				..
-			} => costs::call_cost(
-				value,
-				target_is_cold,
-				true,
-				false,
-				!target_exists,
-				self.config,
-			),
+			} => {
+				costs::call_cost(
+					value,
+					target_is_cold,
+					true,
+					false,
+					!target_exists,
+					self.config,
+				) + costs::delegation_access_cost(delegation_is_cold, self.config)
+			}
 			GasCost::DelegateCall {
 				target_is_cold,
 				target_exists,
+				delegation_is_cold,
 				..
-			} => costs::call_cost(
-				U256::zero(),
-				target_is_cold,
-				false,
-				false,
-				!target_exists,
-				self.config,
-			),
+			} => {
+				costs::call_cost(
+					U256::zero(),
+					target_is_cold,
+					false,
+					false,
+					!target_exists,
+					self.config,
+				) + costs::delegation_access_cost(delegation_is_cold, self.config)
+			}
 			GasCost::StaticCall {
 				target_is_cold,
 				target_exists,
+				delegation_is_cold,
 				..
-			} => costs::call_cost(
-				U256::zero(),
-				target_is_cold,
-				false,
-				true,
-				!target_exists,
-				self.config,
-			),
+			} => {
+				costs::call_cost(
+					U256::zero(),
+					target_is_cold,
+					false,
+					true,
+					!target_exists,
+					self.config,
+				) + costs::delegation_access_cost(delegation_is_cold, self.config)
+			}
 
 			GasCost::Suicide {
 				value,
@@ -847,11 +1027,13 @@ impl<'config> Inner<'config> {
 			GasCost::Create => consts::G_CREATE,
 			GasCost::Create2 { len } => costs::create2_cost(len)?,
 			GasCost::SLoad { target_is_cold } => costs::sload_cost(target_is_cold, self.config),
+			GasCost::WarmStorageRead => self.config.gas_storage_read_warm,
 
 			GasCost::Zero => consts::G_ZERO,
 			GasCost::Base => consts::G_BASE,
 			GasCost::VeryLow => consts::G_VERYLOW,
 			GasCost::Low => consts::G_LOW,
+			GasCost::Fixed(cost) => cost,
 			GasCost::Invalid(opcode) => return Err(ExitError::InvalidCode(opcode)),
 
 			GasCost::ExtCodeSize { target_is_cold } => {
@@ -933,6 +1115,8 @@ pub enum GasCost {
 		target_is_cold: bool,
 		/// Whether the target exists.
 		target_exists: bool,
+		/// Whether the EIP-7702 delegation target is cold, if the target delegates.
+		delegation_is_cold: Option<bool>,
 	},
 	/// Gas cost for `CALLCODE.
 	CallCode {
@@ -944,6 +1128,8 @@ pub enum GasCost {
 		target_is_cold: bool,
 		/// Whether the target exists.
 		target_exists: bool,
+		/// Whether the EIP-7702 delegation target is cold, if the target delegates.
+		delegation_is_cold: Option<bool>,
 	},
 	/// Gas cost for `DELEGATECALL`.
 	DelegateCall {
@@ -953,6 +1139,8 @@ pub enum GasCost {
 		target_is_cold: bool,
 		/// Whether the target exists.
 		target_exists: bool,
+		/// Whether the EIP-7702 delegation target is cold, if the target delegates.
+		delegation_is_cold: Option<bool>,
 	},
 	/// Gas cost for `STATICCALL`.
 	StaticCall {
@@ -962,6 +1150,8 @@ pub enum GasCost {
 		target_is_cold: bool,
 		/// Whether the target exists.
 		target_exists: bool,
+		/// Whether the EIP-7702 delegation target is cold, if the target delegates.
+		delegation_is_cold: Option<bool>,
 	},
 	/// Gas cost for `SUICIDE`.
 	Suicide {
@@ -1026,6 +1216,10 @@ pub enum GasCost {
 		/// True if target has not been previously accessed in this transaction
 		target_is_cold: bool,
 	},
+	/// Gas cost for `TLOAD` and `TSTORE`, priced as a warm storage read.
+	WarmStorageRead,
+	/// Fixed gas cost outside of the named tiers, such as `RJUMPI`.
+	Fixed(u64),
 }
 
 /// Storage opcode will access. Used for tracking accessed storage (EIP-2929).
@@ -1061,6 +1255,8 @@ pub enum TransactionCost {
 		access_list_address_len: usize,
 		/// Total number of storage keys in transaction access list (see EIP-2930)
 		access_list_storage_len: usize,
+		/// Number of authorizations in transaction authorization list (see EIP-7702)
+		authorization_list_len: usize,
 	},
 	/// Create transaction cost.
 	Create {
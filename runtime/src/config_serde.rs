@@ -0,0 +1,364 @@
+//! Serde support of `Config` and `ChainSpec`.
+//!
+//! A `Config` deserializes from either a complete set of fields, or a named
+//! `base` hard fork with some of its fields overridden, e.g.
+//! `{ "base": "cancun", "gas_sload": 900 }`. Unknown fields are rejected.
+//! Without a `base`, a missing optional field is `None`, as formats such as
+//! TOML have no `null` and leave `None` fields out.
+//!
+//! An unlimited `memory_limit` (`usize::MAX`) is written as `"unlimited"`,
+//! since formats such as TOML cannot represent integers above `i64::MAX`.
+
+use crate::{Config, ForkActivation, HardFork};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Error of a config without base fork missing one of its fields.
+pub struct MissingField(&'static str);
+
+impl fmt::Display for MissingField {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"missing field `{}` and no `base` fork to take it from",
+			self.0
+		)
+	}
+}
+
+/// Deserialize a present field, so that overriding an optional field with
+/// `null` sets it to `None` instead of keeping the base value.
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+	deserializer: D,
+) -> Result<Option<T>, D::Error> {
+	T::deserialize(deserializer).map(Some)
+}
+
+/// (De)serialize `Config::memory_limit`, with `usize::MAX` as `"unlimited"`.
+pub mod memory_limit {
+	use super::*;
+	use serde::de::{Unexpected, Visitor};
+
+	const UNLIMITED: &str = "unlimited";
+
+	pub fn serialize<S: Serializer>(limit: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+		if *limit == usize::MAX {
+			serializer.serialize_str(UNLIMITED)
+		} else {
+			serializer.serialize_u64(*limit as u64)
+		}
+	}
+
+	/// Deserialized form of `Config::memory_limit`.
+	pub struct MemoryLimit(usize);
+
+	impl From<MemoryLimit> for usize {
+		fn from(limit: MemoryLimit) -> usize {
+			limit.0
+		}
+	}
+
+	impl<'de> Deserialize<'de> for MemoryLimit {
+		fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			struct LimitVisitor;
+
+			impl<'de> Visitor<'de> for LimitVisitor {
+				type Value = MemoryLimit;
+
+				fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+					write!(f, "a memory size or \"{}\"", UNLIMITED)
+				}
+
+				fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<MemoryLimit, E> {
+					usize::try_from(value)
+						.map(MemoryLimit)
+						.map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+				}
+
+				fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<MemoryLimit, E> {
+					usize::try_from(value)
+						.map(MemoryLimit)
+						.map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+				}
+
+				fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<MemoryLimit, E> {
+					if value == UNLIMITED {
+						Ok(MemoryLimit(usize::MAX))
+					} else {
+						Err(E::invalid_value(Unexpected::Str(value), &self))
+					}
+				}
+			}
+
+			deserializer.deserialize_any(LimitVisitor)
+		}
+	}
+}
+
+macro_rules! config_spec {
+	(
+		required { $( $field:ident: $ty:ty, )* }
+		optional { $( $optional:ident: $optional_ty:ty, )* }
+	) => {
+		/// Deserialized form of `Config`.
+		#[derive(Deserialize)]
+		#[serde(deny_unknown_fields)]
+		pub struct ConfigSpec {
+			#[serde(default)]
+			base: Option<HardFork>,
+			$(
+				#[serde(default, deserialize_with = "present")]
+				$field: Option<$ty>,
+			)*
+			$(
+				#[serde(default, deserialize_with = "present")]
+				$optional: Option<Option<$optional_ty>>,
+			)*
+		}
+
+		impl TryFrom<ConfigSpec> for Config {
+			type Error = MissingField;
+
+			fn try_from(spec: ConfigSpec) -> Result<Self, Self::Error> {
+				let base = spec.base.map(HardFork::config);
+				Ok(Config {
+					$(
+						$field: match (spec.$field, &base) {
+							(Some(value), _) => value.into(),
+							(None, Some(base)) => base.$field,
+							(None, None) => return Err(MissingField(stringify!($field))),
+						},
+					)*
+					$(
+						$optional: match (spec.$optional, &base) {
+							(Some(value), _) => value,
+							(None, Some(base)) => base.$optional,
+							(None, None) => None,
+						},
+					)*
+				})
+			}
+		}
+	};
+}
+
+config_spec! {
+	required {
+		gas_ext_code: u64,
+		gas_ext_code_hash: u64,
+		gas_sstore_set: u64,
+		gas_sstore_reset: u64,
+		refund_sstore_clears: i64,
+		max_refund_quotient: u64,
+		gas_balance: u64,
+		gas_sload: u64,
+		gas_sload_cold: u64,
+		gas_suicide: u64,
+		gas_suicide_new_account: u64,
+		gas_call: u64,
+		gas_expbyte: u64,
+		gas_transaction_create: u64,
+		gas_transaction_call: u64,
+		gas_transaction_zero_data: u64,
+		gas_transaction_non_zero_data: u64,
+		gas_access_list_address: u64,
+		gas_access_list_storage_key: u64,
+		gas_account_access_cold: u64,
+		gas_storage_read_warm: u64,
+		gas_per_empty_account_cost: u64,
+		gas_per_auth_base_cost: u64,
+		sstore_gas_metering: bool,
+		sstore_revert_under_stipend: bool,
+		increase_state_access_gas: bool,
+		decrease_clears_refund: bool,
+		bn128_repricing: bool,
+		modexp_repricing: bool,
+		disallow_executable_format: bool,
+		warm_coinbase_address: bool,
+		err_on_call_with_more_gas: bool,
+		call_l64_after_gas: bool,
+		empty_considered_exists: bool,
+		create_increase_nonce: bool,
+		err_on_code_deposit_out_of_gas: bool,
+		stack_limit: usize,
+		memory_limit: memory_limit::MemoryLimit,
+		call_stack_limit: usize,
+		max_blobs_per_block: usize,
+		block_reward: u64,
+		call_stipend: u64,
+		has_delegate_call: bool,
+		has_static_call: bool,
+		has_create2: bool,
+		has_revert: bool,
+		has_return_data: bool,
+		has_bitwise_shifting: bool,
+		has_chain_id: bool,
+		has_self_balance: bool,
+		has_ext_code_hash: bool,
+		has_base_fee: bool,
+		has_push0: bool,
+		has_kzg_point_evaluation: bool,
+		has_transient_storage: bool,
+		has_mcopy: bool,
+		has_blob_hash: bool,
+		has_blob_base_fee: bool,
+		has_restricted_selfdestruct: bool,
+		has_authorization_list: bool,
+		has_block_hash_history: bool,
+		has_withdrawals: bool,
+		has_beacon_block_root: bool,
+		has_bls12_381_precompiles: bool,
+		has_p256_verify: bool,
+		has_eof: bool,
+		estimate: bool,
+	}
+	optional {
+		total_cost_floor_per_token: u64,
+		create_contract_limit: usize,
+		max_initcode_size: usize,
+	}
+}
+
+/// Scheduled fork of a serialized `ChainSpec`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fork {
+	activation: ForkActivation,
+	config: Config,
+}
+
+#[derive(Serialize)]
+struct ForkRef<'a> {
+	activation: &'a ForkActivation,
+	config: &'a Config,
+}
+
+/// (De)serialize `ChainSpec::forks` as a list of `{ activation, config }`.
+pub mod forks {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(
+		forks: &[(ForkActivation, Config)],
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(
+			forks
+				.iter()
+				.map(|(activation, config)| ForkRef { activation, config }),
+		)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Vec<(ForkActivation, Config)>, D::Error> {
+		let forks = Vec::<Fork>::deserialize(deserializer)?;
+		if forks.is_empty() {
+			return Err(D::Error::invalid_length(0, &"at least the genesis fork"));
+		}
+		Ok(forks
+			.into_iter()
+			.map(|fork| (fork.activation, fork.config))
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{ChainSpec, Config, ForkActivation};
+
+	#[test]
+	fn config_round_trip() {
+		let config = Config::cancun();
+		let json = serde_json::to_string(&config).unwrap();
+		assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+	}
+
+	#[test]
+	fn config_toml_round_trip() {
+		for config in [Config::frontier(), Config::cancun()] {
+			let toml = toml::to_string(&config).unwrap();
+			assert!(toml.contains("memory_limit = \"unlimited\""));
+			assert_eq!(toml::from_str::<Config>(&toml).unwrap(), config);
+		}
+
+		let config: Config = toml::from_str(
+			r#"
+			base = "cancun"
+			gas_sload = 900
+			memory_limit = 1048576
+			"#,
+		)
+		.unwrap();
+
+		let mut expected = Config::cancun();
+		expected.gas_sload = 900;
+		expected.memory_limit = 1 << 20;
+		assert_eq!(config, expected);
+		assert_eq!(
+			toml::from_str::<Config>(&toml::to_string(&expected).unwrap()).unwrap(),
+			expected
+		);
+	}
+
+	#[test]
+	fn chain_spec_toml_round_trip() {
+		let mainnet = ChainSpec::mainnet();
+		let toml = toml::to_string(&mainnet).unwrap();
+		assert_eq!(toml::from_str::<ChainSpec>(&toml).unwrap(), mainnet);
+	}
+
+	#[test]
+	fn config_overrides_base_fork() {
+		let config: Config = serde_json::from_str(
+			r#"{ "base": "cancun", "gas_sload": 900, "create_contract_limit": null }"#,
+		)
+		.unwrap();
+
+		let mut expected = Config::cancun();
+		expected.gas_sload = 900;
+		expected.create_contract_limit = None;
+		assert_eq!(config, expected);
+	}
+
+	#[test]
+	fn config_rejects_unknown_and_missing_fields() {
+		let err = serde_json::from_str::<Config>(r#"{ "base": "cancun", "gas_sloads": 900 }"#)
+			.unwrap_err();
+		assert!(err.to_string().contains("unknown field `gas_sloads`"));
+
+		let err = serde_json::from_str::<Config>(r#"{ "gas_sload": 900 }"#).unwrap_err();
+		assert!(err.to_string().contains("missing field `gas_ext_code`"));
+	}
+
+	#[test]
+	fn chain_spec() {
+		let mainnet = ChainSpec::mainnet();
+		let json = serde_json::to_string(&mainnet).unwrap();
+		assert_eq!(serde_json::from_str::<ChainSpec>(&json).unwrap(), mainnet);
+
+		let spec: ChainSpec = serde_json::from_str(
+			r#"{
+				"chain_id": 1337,
+				"forks": [
+					{ "activation": { "block": 0 }, "config": { "base": "paris" } },
+					{
+						"activation": { "timestamp": 1000 },
+						"config": { "base": "cancun", "gas_transaction_call": 30000 }
+					}
+				]
+			}"#,
+		)
+		.unwrap();
+
+		let mut cancun = Config::cancun();
+		cancun.gas_transaction_call = 30000;
+		let expected = ChainSpec::new(1337, Config::merge())
+			.with_fork(ForkActivation::Timestamp(1000), cancun);
+		assert_eq!(spec, expected);
+
+		assert!(serde_json::from_str::<ChainSpec>(r#"{ "chain_id": 1, "forks": [] }"#).is_err());
+	}
+}
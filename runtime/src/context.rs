@@ -17,6 +17,14 @@ pub enum CreateScheme {
 		/// Salt.
 		salt: H256,
 	},
+	/// Create scheme of `EOFCREATE`, deriving the address from the caller and
+	/// salt only. See [EIP-7620](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7620.md).
+	Eof {
+		/// Caller of the create.
+		caller: H160,
+		/// Salt.
+		salt: H256,
+	},
 	/// Create at a fixed location.
 	Fixed(H160),
 }
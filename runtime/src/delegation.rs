@@ -0,0 +1,29 @@
+use alloc::vec::Vec;
+use primitive_types::H160;
+
+/// Prefix of an EIP-7702 delegation designator, followed by the 20-byte
+/// address the account delegates to.
+pub const DELEGATION_DESIGNATOR_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Length of an EIP-7702 delegation designator.
+pub const DELEGATION_DESIGNATOR_LEN: usize = 23;
+
+/// Build the delegation designator `0xef0100 || address`.
+pub fn delegation_designator(address: H160) -> Vec<u8> {
+	let mut code = Vec::with_capacity(DELEGATION_DESIGNATOR_LEN);
+	code.extend_from_slice(&DELEGATION_DESIGNATOR_PREFIX);
+	code.extend_from_slice(address.as_bytes());
+	code
+}
+
+/// Get the address a delegation designator points to, or `None` if `code` is
+/// not a delegation designator.
+pub fn delegated_address(code: &[u8]) -> Option<H160> {
+	if code.len() == DELEGATION_DESIGNATOR_LEN && code.starts_with(&DELEGATION_DESIGNATOR_PREFIX) {
+		Some(H160::from_slice(
+			&code[DELEGATION_DESIGNATOR_PREFIX.len()..],
+		))
+	} else {
+		None
+	}
+}
@@ -2,7 +2,7 @@
 mod macros;
 mod system;
 
-use crate::{CallScheme, ExitReason, Handler, Opcode, Runtime};
+use crate::{CallScheme, ExitError, ExitReason, Handler, Opcode, Runtime};
 use alloc::vec::Vec;
 use core::cmp::min;
 use primitive_types::{H160, H256, U256};
@@ -44,6 +44,8 @@ pub fn eval<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) ->
 		Opcode::GASLIMIT => system::gaslimit(state, handler),
 		Opcode::SLOAD => system::sload(state, handler),
 		Opcode::SSTORE => system::sstore(state, handler),
+		Opcode::TLOAD => system::tload(state, handler),
+		Opcode::TSTORE => system::tstore(state, handler),
 		Opcode::GAS => system::gas(state, handler),
 		Opcode::LOG0 => system::log(state, 0, handler),
 		Opcode::LOG1 => system::log(state, 1, handler),
@@ -59,6 +61,19 @@ pub fn eval<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) ->
 		Opcode::STATICCALL => system::call(state, CallScheme::StaticCall, handler),
 		Opcode::CHAINID => system::chainid(state, handler),
 		Opcode::BASEFEE => system::base_fee(state, handler),
+		Opcode::BLOBHASH => system::blob_hash(state, handler),
+		Opcode::BLOBBASEFEE => system::blob_base_fee(state, handler),
+		Opcode::RETURNDATALOAD if state.machine.eof().is_some() => system::returndataload(state),
+		Opcode::EOFCREATE if state.machine.eof().is_some() => system::eofcreate(state, handler),
+		Opcode::EXTCALL if state.machine.eof().is_some() => {
+			system::ext_call(state, CallScheme::Call, handler)
+		}
+		Opcode::EXTDELEGATECALL if state.machine.eof().is_some() => {
+			system::ext_call(state, CallScheme::DelegateCall, handler)
+		}
+		Opcode::EXTSTATICCALL if state.machine.eof().is_some() => {
+			system::ext_call(state, CallScheme::StaticCall, handler)
+		}
 		_ => handle_other(state, opcode, handler),
 	}
 }
@@ -81,6 +96,12 @@ pub fn finish_create(
 			runtime.machine.stack_mut().push(H256::default())?;
 			Ok(())
 		}
+		// A read of a pending write of a lower transaction invalidates the
+		// whole transaction, not only the sub-call.
+		ExitReason::Error(ExitError::NotEstimatedYet { .. }) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+			Err(reason)
+		}
 		ExitReason::Error(_) => {
 			runtime.machine.stack_mut().push(H256::default())?;
 			Ok(())
@@ -99,6 +120,10 @@ pub fn finish_call(
 	reason: ExitReason,
 	return_data: Vec<u8>,
 ) -> Result<(), ExitReason> {
+	if runtime.machine.eof().is_some() {
+		return finish_ext_call(runtime, reason, return_data);
+	}
+
 	runtime.return_data_buffer = return_data;
 	let target_len = min(out_len, U256::from(runtime.return_data_buffer.len()));
 
@@ -134,6 +159,12 @@ pub fn finish_call(
 
 			Ok(())
 		}
+		// See `finish_create`.
+		ExitReason::Error(ExitError::NotEstimatedYet { .. }) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+
+			Err(reason)
+		}
 		ExitReason::Error(_) => {
 			runtime.machine.stack_mut().push(H256::default())?;
 
@@ -146,3 +177,32 @@ pub fn finish_call(
 		}
 	}
 }
+
+/// Finish an EOF call, pushing `0` on success, `1` on revert or when the
+/// callee did not run, and `2` on failure. See
+/// [EIP-7069](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7069.md).
+fn finish_ext_call(
+	runtime: &mut Runtime,
+	reason: ExitReason,
+	return_data: Vec<u8>,
+) -> Result<(), ExitReason> {
+	runtime.return_data_buffer = return_data;
+
+	let status = match reason {
+		ExitReason::Succeed(_) => 0,
+		ExitReason::Revert(_)
+		| ExitReason::Error(ExitError::CallTooDeep)
+		| ExitReason::Error(ExitError::OutOfFund) => 1,
+		ExitReason::Error(_) | ExitReason::Fatal(_) => 2,
+	};
+	let mut value = H256::default();
+	U256::from(status).to_big_endian(&mut value[..]);
+	runtime.machine.stack_mut().push(value)?;
+
+	match reason {
+		// See `finish_create`.
+		ExitReason::Error(ExitError::NotEstimatedYet { .. }) => Err(reason),
+		ExitReason::Fatal(e) => Err(e.into()),
+		_ => Ok(()),
+	}
+}
@@ -1,10 +1,11 @@
 use super::Control;
 use crate::{
-	CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitSucceed, Handler,
-	Runtime, Transfer,
+	is_eof, CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitSucceed, Handler,
+	Opcode, Runtime, Transfer,
 };
 use alloc::vec::Vec;
-use primitive_types::{H256, U256};
+use core::cmp::max;
+use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
 pub fn sha3<H: Handler>(runtime: &mut Runtime) -> Control<H> {
@@ -41,13 +42,15 @@ pub fn address<H: Handler>(runtime: &mut Runtime) -> Control<H> {
 
 pub fn balance<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	pop!(runtime, address);
-	push_u256!(runtime, handler.balance(address.into()));
+	let balance = try_or_fail!(handler.balance(address.into()));
+	push_u256!(runtime, balance);
 
 	Control::Continue
 }
 
 pub fn selfbalance<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-	push_u256!(runtime, handler.balance(runtime.context.address));
+	let balance = try_or_fail!(handler.balance(runtime.context.address));
+	push_u256!(runtime, balance);
 
 	Control::Continue
 }
@@ -90,6 +93,20 @@ pub fn base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	Control::Continue
 }
 
+pub fn blob_hash<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	pop_u256!(runtime, index);
+	let ret = handler.blob_hash(index).unwrap_or_default();
+	push!(runtime, ret);
+
+	Control::Continue
+}
+
+pub fn blob_base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	push_u256!(runtime, handler.block_blob_base_fee());
+
+	Control::Continue
+}
+
 pub fn extcodesize<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
 	pop!(runtime, address);
 	if let Err(e) =
@@ -97,7 +114,7 @@ pub fn extcodesize<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Contro
 	{
 		return Control::Exit(e.into());
 	}
-	let code_size = handler.code_size(address.into());
+	let code_size = try_or_fail!(handler.code_size(address.into()));
 	push_u256!(runtime, code_size);
 
 	Control::Continue
@@ -110,7 +127,7 @@ pub fn extcodehash<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Contro
 	{
 		return Control::Exit(e.into());
 	}
-	let code_hash = handler.code_hash(address.into());
+	let code_hash = try_or_fail!(handler.code_hash(address.into()));
 	push!(runtime, code_hash);
 
 	Control::Continue
@@ -130,7 +147,7 @@ pub fn extcodecopy<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Contro
 	{
 		return Control::Exit(e.into());
 	}
-	let code = handler.code(address.into());
+	let code = try_or_fail!(handler.code(address.into()));
 	match runtime
 		.machine
 		.memory_mut()
@@ -157,10 +174,12 @@ pub fn returndatacopy<H: Handler>(runtime: &mut Runtime) -> Control<H> {
 		.machine
 		.memory_mut()
 		.resize_offset(memory_offset, len));
-	if data_offset
-		.checked_add(len)
-		.map(|l| l > U256::from(runtime.return_data_buffer.len()))
-		.unwrap_or(true)
+	// EOF pads out of bounds return data with zeros instead.
+	if runtime.machine.eof().is_none()
+		&& data_offset
+			.checked_add(len)
+			.map(|l| l > U256::from(runtime.return_data_buffer.len()))
+			.unwrap_or(true)
 	{
 		return Control::Exit(ExitError::OutOfOffset.into());
 	}
@@ -176,9 +195,27 @@ pub fn returndatacopy<H: Handler>(runtime: &mut Runtime) -> Control<H> {
 	}
 }
 
+pub fn returndataload<H: Handler>(runtime: &mut Runtime) -> Control<H> {
+	pop_u256!(runtime, offset);
+
+	let mut load = [0u8; 32];
+	#[allow(clippy::needless_range_loop)]
+	for i in 0..32 {
+		if let Some(p) = offset.checked_add(U256::from(i)) {
+			if p < U256::from(runtime.return_data_buffer.len()) {
+				load[i] = runtime.return_data_buffer[p.as_usize()];
+			}
+		}
+	}
+	push!(runtime, H256::from(load));
+
+	Control::Continue
+}
+
 pub fn blockhash<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	pop_u256!(runtime, number);
-	push!(runtime, handler.block_hash(number));
+	let hash = try_or_fail!(handler.block_hash(number));
+	push!(runtime, hash);
 
 	Control::Continue
 }
@@ -229,7 +266,7 @@ pub fn sload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
 						index,
 						value
 					});
-					
+
 					Control::Continue
 				},
 				Err(e) => Control::Exit(e.into())
@@ -244,7 +281,7 @@ pub fn sload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
 				index,
 				value
 			});
-		
+
 			Control::Continue
 		}
 	}
@@ -265,6 +302,23 @@ pub fn sstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H>
 	}
 }
 
+pub fn tload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
+	pop!(runtime, index);
+	let value = handler.transient_storage(runtime.context.address, index);
+	push!(runtime, value);
+
+	Control::Continue
+}
+
+pub fn tstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
+	pop!(runtime, index, value);
+
+	match handler.set_transient_storage(runtime.context.address, index, value) {
+		Ok(()) => Control::Continue,
+		Err(e) => Control::Exit(e.into()),
+	}
+}
+
 pub fn gas<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	push_u256!(runtime, handler.gas_left());
 
@@ -351,6 +405,46 @@ pub fn create<H: Handler>(runtime: &mut Runtime, is_create2: bool, handler: &mut
 	}
 }
 
+pub fn eofcreate<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
+	runtime.return_data_buffer = Vec::new();
+
+	let mut init_code = match runtime.machine.eofcreate_container() {
+		Some(container) => container.to_vec(),
+		None => return Control::Exit(ExitError::InvalidCode(Opcode::EOFCREATE).into()),
+	};
+	pop_u256!(runtime, value);
+	pop!(runtime, salt);
+	pop_u256!(runtime, input_offset, input_len);
+
+	try_or_fail!(runtime
+		.machine
+		.memory_mut()
+		.resize_offset(input_offset, input_len));
+	if input_len != U256::zero() {
+		let input_offset = as_usize_or_fail!(input_offset);
+		let input_len = as_usize_or_fail!(input_len);
+
+		init_code.extend_from_slice(&runtime.machine.memory().get(input_offset, input_len));
+	}
+
+	// The initcontainer is followed by its calldata, as in creation
+	// transactions.
+	let scheme = CreateScheme::Eof {
+		caller: runtime.context.address,
+		salt,
+	};
+
+	match handler.create(runtime.context.address, scheme, value, init_code, None) {
+		Capture::Exit((reason, address, return_data)) => {
+			match super::finish_create(runtime, reason, address, return_data) {
+				Ok(()) => Control::Continue,
+				Err(e) => Control::Exit(e),
+			}
+		}
+		Capture::Trap(interrupt) => Control::CreateInterrupt(interrupt),
+	}
+}
+
 pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut H) -> Control<H> {
 	runtime.return_data_buffer = Vec::new();
 
@@ -445,3 +539,98 @@ pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut
 		}
 	}
 }
+
+/// Gas an EOF call leaves to the caller at least. See
+/// [EIP-7069](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7069.md).
+const MIN_RETAINED_GAS: u64 = 5000;
+/// Gas below which an EOF call does not run the callee.
+const MIN_CALLEE_GAS: u64 = 2300;
+
+pub fn ext_call<H: Handler>(
+	runtime: &mut Runtime,
+	scheme: CallScheme,
+	handler: &mut H,
+) -> Control<H> {
+	runtime.return_data_buffer = Vec::new();
+
+	pop!(runtime, to);
+	pop_u256!(runtime, in_offset, in_len);
+	let value = match scheme {
+		CallScheme::Call => {
+			pop_u256!(runtime, value);
+			value
+		}
+		_ => U256::zero(),
+	};
+	if to[..12].iter().any(|byte| *byte != 0) {
+		return Control::Exit(ExitError::Other("call target is not an address".into()).into());
+	}
+	let to = H160::from(to);
+
+	try_or_fail!(runtime
+		.machine
+		.memory_mut()
+		.resize_offset(in_offset, in_len));
+
+	let input = if in_len == U256::zero() {
+		Vec::new()
+	} else {
+		let in_offset = as_usize_or_fail!(in_offset);
+		let in_len = as_usize_or_fail!(in_len);
+
+		runtime.machine.memory().get(in_offset, in_len)
+	};
+
+	let gas_left = handler.gas_left();
+	let gas = gas_left.saturating_sub(max(gas_left / 64, U256::from(MIN_RETAINED_GAS)));
+	let runs_callee = gas >= U256::from(MIN_CALLEE_GAS)
+		&& (scheme != CallScheme::DelegateCall || is_eof(&try_or_fail!(handler.code(to))));
+	if !runs_callee {
+		push_u256!(runtime, U256::one());
+		return Control::Continue;
+	}
+
+	let context = match scheme {
+		CallScheme::DelegateCall => Context {
+			address: runtime.context.address,
+			caller: runtime.context.caller,
+			apparent_value: runtime.context.apparent_value,
+		},
+		_ => Context {
+			address: to,
+			caller: runtime.context.address,
+			apparent_value: value,
+		},
+	};
+
+	let transfer = if scheme == CallScheme::Call {
+		Some(Transfer {
+			source: runtime.context.address,
+			target: to,
+			value,
+		})
+	} else {
+		None
+	};
+
+	match handler.ext_call(
+		to,
+		transfer,
+		input,
+		Some(gas.as_u64()),
+		scheme == CallScheme::StaticCall,
+		context,
+	) {
+		Capture::Exit((reason, return_data)) => {
+			match super::finish_call(runtime, U256::zero(), U256::zero(), reason, return_data) {
+				Ok(()) => Control::Continue,
+				Err(e) => Control::Exit(e),
+			}
+		}
+		Capture::Trap(interrupt) => {
+			runtime.return_data_len = U256::zero();
+			runtime.return_data_offset = U256::zero();
+			Control::CallInterrupt(interrupt)
+		}
+	}
+}
@@ -0,0 +1,231 @@
+use crate::Config;
+use alloc::vec::Vec;
+use primitive_types::U256;
+
+/// Named hard fork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "with-serde", serde(rename_all = "snake_case"))]
+pub enum HardFork {
+	/// Frontier.
+	Frontier,
+	/// Homestead.
+	Homestead,
+	/// Tangerine Whistle.
+	TangerineWhistle,
+	/// Spurious Dragon.
+	SpuriousDragon,
+	/// Byzantium.
+	Byzantium,
+	/// Constantinople.
+	Constantinople,
+	/// Petersburg.
+	Petersburg,
+	/// Istanbul.
+	Istanbul,
+	/// Berlin.
+	Berlin,
+	/// London.
+	London,
+	/// The merge, also known as Paris.
+	#[cfg_attr(feature = "with-serde", serde(alias = "paris"))]
+	Merge,
+	/// Shanghai.
+	Shanghai,
+	/// Cancun.
+	Cancun,
+	/// Prague.
+	Prague,
+}
+
+impl HardFork {
+	/// Configuration of the hard fork.
+	pub const fn config(self) -> Config {
+		match self {
+			HardFork::Frontier => Config::frontier(),
+			HardFork::Homestead => Config::homestead(),
+			HardFork::TangerineWhistle => Config::tangerine_whistle(),
+			HardFork::SpuriousDragon => Config::spurious_dragon(),
+			HardFork::Byzantium => Config::byzantium(),
+			HardFork::Constantinople => Config::constantinople(),
+			HardFork::Petersburg => Config::petersburg(),
+			HardFork::Istanbul => Config::istanbul(),
+			HardFork::Berlin => Config::berlin(),
+			HardFork::London => Config::london(),
+			HardFork::Merge => Config::merge(),
+			HardFork::Shanghai => Config::shanghai(),
+			HardFork::Cancun => Config::cancun(),
+			HardFork::Prague => Config::prague(),
+		}
+	}
+}
+
+/// Activation point of a hard fork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "with-serde", serde(rename_all = "snake_case"))]
+pub enum ForkActivation {
+	/// Active from the given block number. Used before the merge.
+	Block(u64),
+	/// Active from the given block timestamp. Used after the merge.
+	Timestamp(u64),
+}
+
+impl ForkActivation {
+	/// Whether the fork is active for a block of the given number and timestamp.
+	pub fn is_active(&self, block_number: U256, block_timestamp: U256) -> bool {
+		match *self {
+			ForkActivation::Block(number) => block_number >= U256::from(number),
+			ForkActivation::Timestamp(timestamp) => block_timestamp >= U256::from(timestamp),
+		}
+	}
+}
+
+/// Chain specification, scheduling the hard fork configurations of a chain by
+/// block number and timestamp.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "with-serde", serde(deny_unknown_fields))]
+pub struct ChainSpec {
+	/// Chain ID.
+	pub chain_id: u64,
+	/// Hard forks in activation order. The first one is active from genesis.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::config_serde::forks"))]
+	pub forks: Vec<(ForkActivation, Config)>,
+}
+
+impl ChainSpec {
+	/// Create a chain specification running `genesis` from the genesis block.
+	pub fn new(chain_id: u64, genesis: Config) -> Self {
+		Self {
+			chain_id,
+			forks: alloc::vec![(ForkActivation::Block(0), genesis)],
+		}
+	}
+
+	/// Schedule a hard fork after the ones already scheduled.
+	pub fn with_fork(mut self, activation: ForkActivation, config: Config) -> Self {
+		self.forks.push((activation, config));
+		self
+	}
+
+	/// Configuration active for a block of the given number and timestamp. This
+	/// is the last scheduled fork that is active, or the genesis configuration.
+	pub fn config_at(&self, block_number: U256, block_timestamp: U256) -> &Config {
+		self.forks
+			.iter()
+			.rev()
+			.find(|(activation, _)| activation.is_active(block_number, block_timestamp))
+			.or_else(|| self.forks.first())
+			.map(|(_, config)| config)
+			.expect("chain spec has a genesis configuration")
+	}
+
+	/// Ethereum mainnet.
+	pub fn mainnet() -> Self {
+		Self::from_forks(
+			1,
+			&[
+				(ForkActivation::Block(0), HardFork::Frontier),
+				(ForkActivation::Block(1_150_000), HardFork::Homestead),
+				(ForkActivation::Block(2_463_000), HardFork::TangerineWhistle),
+				(ForkActivation::Block(2_675_000), HardFork::SpuriousDragon),
+				(ForkActivation::Block(4_370_000), HardFork::Byzantium),
+				(ForkActivation::Block(7_280_000), HardFork::Petersburg),
+				(ForkActivation::Block(9_069_000), HardFork::Istanbul),
+				(ForkActivation::Block(12_244_000), HardFork::Berlin),
+				(ForkActivation::Block(12_965_000), HardFork::London),
+				(ForkActivation::Block(15_537_394), HardFork::Merge),
+				(ForkActivation::Timestamp(1_681_338_455), HardFork::Shanghai),
+				(ForkActivation::Timestamp(1_710_338_135), HardFork::Cancun),
+				(ForkActivation::Timestamp(1_746_612_311), HardFork::Prague),
+			],
+		)
+	}
+
+	/// Sepolia testnet.
+	pub fn sepolia() -> Self {
+		Self::from_forks(
+			11_155_111,
+			&[
+				(ForkActivation::Block(0), HardFork::London),
+				(ForkActivation::Block(1_450_409), HardFork::Merge),
+				(ForkActivation::Timestamp(1_677_557_088), HardFork::Shanghai),
+				(ForkActivation::Timestamp(1_706_655_072), HardFork::Cancun),
+				(ForkActivation::Timestamp(1_741_159_776), HardFork::Prague),
+			],
+		)
+	}
+
+	/// Holesky testnet.
+	pub fn holesky() -> Self {
+		Self::from_forks(
+			17_000,
+			&[
+				(ForkActivation::Block(0), HardFork::Merge),
+				(ForkActivation::Timestamp(1_696_000_704), HardFork::Shanghai),
+				(ForkActivation::Timestamp(1_707_305_664), HardFork::Cancun),
+				(ForkActivation::Timestamp(1_740_434_112), HardFork::Prague),
+			],
+		)
+	}
+
+	fn from_forks(chain_id: u64, forks: &[(ForkActivation, HardFork)]) -> Self {
+		Self {
+			chain_id,
+			forks: forks
+				.iter()
+				.map(|(activation, fork)| (*activation, fork.config()))
+				.collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config_at(spec: &ChainSpec, block_number: u64, block_timestamp: u64) -> &Config {
+		spec.config_at(U256::from(block_number), U256::from(block_timestamp))
+	}
+
+	#[test]
+	fn mainnet_forks_by_block_number() {
+		let mainnet = ChainSpec::mainnet();
+		assert_eq!(config_at(&mainnet, 0, 0), &Config::frontier());
+		assert_eq!(config_at(&mainnet, 1_149_999, 0), &Config::frontier());
+		assert_eq!(config_at(&mainnet, 1_150_000, 0), &Config::homestead());
+		assert_eq!(config_at(&mainnet, 7_280_000, 0), &Config::petersburg());
+		assert_eq!(config_at(&mainnet, 15_537_393, 0), &Config::london());
+		assert_eq!(config_at(&mainnet, 15_537_394, 0), &Config::merge());
+	}
+
+	#[test]
+	fn mainnet_forks_by_timestamp() {
+		let mainnet = ChainSpec::mainnet();
+		let number = 20_000_000;
+		assert_eq!(config_at(&mainnet, number, 1_681_338_454), &Config::merge());
+		assert_eq!(
+			config_at(&mainnet, number, 1_681_338_455),
+			&Config::shanghai()
+		);
+		assert_eq!(
+			config_at(&mainnet, number, 1_710_338_135),
+			&Config::cancun()
+		);
+		assert_eq!(
+			config_at(&mainnet, number, 1_746_612_311),
+			&Config::prague()
+		);
+	}
+
+	#[test]
+	fn custom_schedule() {
+		let spec = ChainSpec::new(1337, Config::istanbul())
+			.with_fork(ForkActivation::Block(10), Config::london())
+			.with_fork(ForkActivation::Timestamp(1000), Config::cancun());
+		assert_eq!(config_at(&spec, 9, 500), &Config::istanbul());
+		assert_eq!(config_at(&spec, 10, 999), &Config::london());
+		assert_eq!(config_at(&spec, 10, 1000), &Config::cancun());
+	}
+}
@@ -26,18 +26,24 @@ pub trait Handler {
 	type CallFeedback;
 
 	/// Get balance of address.
-	fn balance(&self, address: H160) -> U256;
+	fn balance(&self, address: H160) -> Result<U256, ExitError>;
 	/// Get code size of address.
-	fn code_size(&self, address: H160) -> U256;
+	fn code_size(&self, address: H160) -> Result<U256, ExitError>;
 	/// Get code hash of address.
-	fn code_hash(&self, address: H160) -> H256;
+	fn code_hash(&self, address: H160) -> Result<H256, ExitError>;
 	/// Get code of address.
-	fn code(&self, address: H160) -> Vec<u8>;
+	fn code(&self, address: H160) -> Result<Vec<u8>, ExitError>;
+	/// Get the address the code of address delegates to, if it holds an
+	/// EIP-7702 delegation designator.
+	fn delegated_address(&self, address: H160) -> Result<Option<H160>, ExitError> {
+		Ok(crate::delegated_address(&self.code(address)?))
+	}
 	/// Get storage value of address at index.
 	fn storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError>;
 	/// Get original storage value of address at index.
 	fn original_storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError>;
-
+	/// Get transient storage value of address at index.
+	fn transient_storage(&self, address: H160, index: H256) -> H256;
 
 	/// Get the gas left value.
 	fn gas_left(&self) -> U256;
@@ -46,7 +52,7 @@ pub trait Handler {
 	/// Get execution origin.
 	fn origin(&self) -> H160;
 	/// Get environmental block hash.
-	fn block_hash(&self, number: U256) -> H256;
+	fn block_hash(&self, number: U256) -> Result<H256, ExitError>;
 	/// Get environmental block number.
 	fn block_number(&self) -> U256;
 	/// Get environmental coinbase.
@@ -61,11 +67,15 @@ pub trait Handler {
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Environmental block blob base fee.
+	fn block_blob_base_fee(&self) -> U256;
+	/// Get the versioned hash of the transaction blob at index, if any.
+	fn blob_hash(&self, index: U256) -> Option<H256>;
 	/// Get environmental chain ID.
 	fn chain_id(&self) -> U256;
 
 	/// Check whether an address exists.
-	fn exists(&self, address: H160) -> bool;
+	fn exists(&self, address: H160) -> Result<bool, ExitError>;
 	/// Check whether an address has already been deleted.
 	fn deleted(&self, address: H160) -> bool;
 	/// Checks if the address or (address, index) pair has been previously accessed
@@ -78,6 +88,13 @@ pub trait Handler {
 
 	/// Set storage value of address at index.
 	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError>;
+	/// Set transient storage value of address at index.
+	fn set_transient_storage(
+		&mut self,
+		address: H160,
+		index: H256,
+		value: H256,
+	) -> Result<(), ExitError>;
 	/// Create a log owned by address with given topics and data.
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
 	/// Mark an address to be deleted, with funds transferred to target.
@@ -105,6 +122,26 @@ pub trait Handler {
 		is_static: bool,
 		context: Context,
 	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt>;
+	/// Invoke an EOF call operation (`EXTCALL`, `EXTDELEGATECALL` or
+	/// `EXTSTATICCALL`). Unlike `call`, value transfers get no stipend.
+	fn ext_call(
+		&mut self,
+		code_address: H160,
+		transfer: Option<Transfer>,
+		input: Vec<u8>,
+		target_gas: Option<u64>,
+		is_static: bool,
+		context: Context,
+	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
+		self.call(
+			code_address,
+			transfer,
+			input,
+			target_gas,
+			is_static,
+			context,
+		)
+	}
 	/// Feed in call feedback.
 	fn call_feedback(&mut self, _feedback: Self::CallFeedback) -> Result<(), ExitError> {
 		Ok(())
@@ -22,14 +22,23 @@ macro_rules! event {
 	($x:expr) => {};
 }
 
+#[cfg(feature = "with-serde")]
+mod config_serde;
 mod context;
+mod delegation;
 mod eval;
+mod fork;
 mod handler;
 mod interrupt;
 
 pub use evm_core::*;
 
 pub use crate::context::{CallScheme, Context, CreateScheme};
+pub use crate::delegation::{
+	delegated_address, delegation_designator, DELEGATION_DESIGNATOR_LEN,
+	DELEGATION_DESIGNATOR_PREFIX,
+};
+pub use crate::fork::{ChainSpec, ForkActivation, HardFork};
 pub use crate::handler::{Handler, Transfer};
 pub use crate::interrupt::{Resolve, ResolveCall, ResolveCreate};
 
@@ -136,6 +145,24 @@ impl Runtime {
 	}
 
 	/// Get a reference to the machine.
+	pub fn new_eof(
+		code: Rc<Vec<u8>>,
+		container: EofContainer,
+		data: Rc<Vec<u8>>,
+		context: Context,
+		stack_limit: usize,
+		memory_limit: usize,
+	) -> Self {
+		Self {
+			machine: Machine::new_eof(code, container, data, stack_limit, memory_limit),
+			status: Ok(()),
+			return_data_buffer: Vec::new(),
+			return_data_len: U256::zero(),
+			return_data_offset: U256::zero(),
+			context,
+		}
+	}
+
 	pub fn machine(&self) -> &Machine {
 		&self.machine
 	}
@@ -187,8 +214,36 @@ impl Runtime {
 	}
 }
 
+/// Address of the EIP-2935 history storage contract.
+pub const HISTORY_STORAGE_ADDRESS: H160 = H160([
+	0x00, 0x00, 0xf9, 0x08, 0x27, 0xf1, 0xc5, 0x3a, 0x10, 0xcb, 0x7a, 0x02, 0x33, 0x5b, 0x17, 0x53,
+	0x20, 0x00, 0x29, 0x35,
+]);
+
+/// Number of block hashes kept by the EIP-2935 history storage contract, which
+/// stores the hash of block `n` at slot `n % HISTORY_SERVE_WINDOW`.
+pub const HISTORY_SERVE_WINDOW: u64 = 8191;
+
+/// Address of the EIP-4788 beacon roots contract.
+pub const BEACON_ROOTS_ADDRESS: H160 = H160([
+	0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+	0xd0, 0xbe, 0xac, 0x02,
+]);
+
+/// Caller of the system calls made at the start of a block, such as the
+/// EIP-4788 and EIP-2935 ones.
+pub const SYSTEM_ADDRESS: H160 = H160([
+	0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0xff, 0xff, 0xff, 0xfe,
+]);
+
 /// Runtime configuration.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+	feature = "with-serde",
+	serde(try_from = "crate::config_serde::ConfigSpec")
+)]
 pub struct Config {
 	/// Gas paid for extcode.
 	pub gas_ext_code: u64,
@@ -232,6 +287,13 @@ pub struct Config {
 	pub gas_account_access_cold: u64,
 	/// Gas paid for accessing ready storage.
 	pub gas_storage_read_warm: u64,
+	/// EIP-7702: Gas paid for each authorization in the transaction's authorization list.
+	pub gas_per_empty_account_cost: u64,
+	/// EIP-7702: Gas paid for an authorization whose authority already exists.
+	pub gas_per_auth_base_cost: u64,
+	/// EIP-7623: Floor gas paid per calldata token, if the transaction's gas
+	/// usage is floored by its calldata.
+	pub total_cost_floor_per_token: Option<u64>,
 	/// EIP-1283.
 	pub sstore_gas_metering: bool,
 	/// EIP-1706.
@@ -240,6 +302,10 @@ pub struct Config {
 	pub increase_state_access_gas: bool,
 	/// EIP-3529
 	pub decrease_clears_refund: bool,
+	/// EIP-1108, cheaper alt_bn128 precompiles.
+	pub bn128_repricing: bool,
+	/// EIP-2565, cheaper modexp precompile.
+	pub modexp_repricing: bool,
 	/// EIP-3541
 	pub disallow_executable_format: bool,
 	/// EIP-3651
@@ -254,9 +320,16 @@ pub struct Config {
 	pub empty_considered_exists: bool,
 	/// Whether create transactions and create opcode increases nonce by one.
 	pub create_increase_nonce: bool,
+	/// EIP-2, whether running out of gas for the code deposit fails the
+	/// creation. Otherwise the contract is created with empty code.
+	pub err_on_code_deposit_out_of_gas: bool,
 	/// Stack limit.
 	pub stack_limit: usize,
 	/// Memory limit.
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(serialize_with = "crate::config_serde::memory_limit::serialize")
+	)]
 	pub memory_limit: usize,
 	/// Call limit.
 	pub call_stack_limit: usize,
@@ -264,10 +337,18 @@ pub struct Config {
 	pub create_contract_limit: Option<usize>,
 	/// EIP-3860, maximum size limit of init_code.
 	pub max_initcode_size: Option<usize>,
+	/// EIP-4844, maximum number of blobs in a block, which also bounds the
+	/// blobs of a single transaction. Zero before blob transactions.
+	pub max_blobs_per_block: usize,
+	/// Reward of the miner of a block, in wei, on which ommer rewards are also
+	/// based. Zero since the Merge.
+	pub block_reward: u64,
 	/// Call stipend.
 	pub call_stipend: u64,
 	/// Has delegate call.
 	pub has_delegate_call: bool,
+	/// Has static call.
+	pub has_static_call: bool,
 	/// Has create2.
 	pub has_create2: bool,
 	/// Has revert.
@@ -286,6 +367,32 @@ pub struct Config {
 	pub has_base_fee: bool,
 	/// Has PUSH0 opcode. See [EIP-3855](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3855.md)
 	pub has_push0: bool,
+	/// Has the KZG point evaluation precompile. See [EIP-4844](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4844.md)
+	pub has_kzg_point_evaluation: bool,
+	/// Has transient storage. See [EIP-1153](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1153.md)
+	pub has_transient_storage: bool,
+	/// Has MCOPY opcode. See [EIP-5656](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-5656.md)
+	pub has_mcopy: bool,
+	/// Has BLOBHASH opcode. See [EIP-4844](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4844.md)
+	pub has_blob_hash: bool,
+	/// Has BLOBBASEFEE opcode. See [EIP-7516](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7516.md)
+	pub has_blob_base_fee: bool,
+	/// SELFDESTRUCT only deletes accounts created in the same transaction. See [EIP-6780](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-6780.md)
+	pub has_restricted_selfdestruct: bool,
+	/// Has set-code transactions and delegation designators. See [EIP-7702](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7702.md)
+	pub has_authorization_list: bool,
+	/// BLOCKHASH reads the history storage contract. See [EIP-2935](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-2935.md)
+	pub has_block_hash_history: bool,
+	/// Credits the withdrawals of a block to their recipients. See [EIP-4895](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4895.md)
+	pub has_withdrawals: bool,
+	/// Stores the parent beacon block root in the beacon roots contract. See [EIP-4788](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4788.md)
+	pub has_beacon_block_root: bool,
+	/// Has the BLS12-381 curve operation precompiles. See [EIP-2537](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-2537.md)
+	pub has_bls12_381_precompiles: bool,
+	/// Has the secp256r1 signature verification precompile at `0x100`, deployed by some L2 chains. See [RIP-7212](https://github.com/ethereum/RIPs/blob/master/RIPS/rip-7212.md)
+	pub has_p256_verify: bool,
+	/// Validates and executes EVM Object Format (EOF) v1 containers. See [EIP-3540](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3540.md)
+	pub has_eof: bool,
 	/// Whether the gasometer is running in estimate mode.
 	pub estimate: bool,
 }
@@ -315,23 +422,32 @@ impl Config {
 			gas_access_list_storage_key: 0,
 			gas_account_access_cold: 0,
 			gas_storage_read_warm: 0,
+			gas_per_empty_account_cost: 0,
+			gas_per_auth_base_cost: 0,
+			total_cost_floor_per_token: None,
 			sstore_gas_metering: false,
 			sstore_revert_under_stipend: false,
 			increase_state_access_gas: false,
 			decrease_clears_refund: false,
+			bn128_repricing: false,
+			modexp_repricing: false,
 			disallow_executable_format: false,
 			warm_coinbase_address: false,
 			err_on_call_with_more_gas: true,
 			empty_considered_exists: true,
 			create_increase_nonce: false,
+			err_on_code_deposit_out_of_gas: false,
 			call_l64_after_gas: false,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: None,
 			max_initcode_size: None,
+			max_blobs_per_block: 0,
+			block_reward: 5_000_000_000_000_000_000,
 			call_stipend: 2300,
 			has_delegate_call: false,
+			has_static_call: false,
 			has_create2: false,
 			has_revert: false,
 			has_return_data: false,
@@ -341,10 +457,94 @@ impl Config {
 			has_ext_code_hash: false,
 			has_base_fee: false,
 			has_push0: false,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: false,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			has_eof: false,
 			estimate: false,
 		}
 	}
 
+	/// Homestead hard fork configuration.
+	pub const fn homestead() -> Config {
+		Config {
+			gas_transaction_create: 53000,
+			err_on_code_deposit_out_of_gas: true,
+			has_delegate_call: true,
+			..Self::frontier()
+		}
+	}
+
+	/// Tangerine Whistle hard fork configuration (EIP-150).
+	pub const fn tangerine_whistle() -> Config {
+		Config {
+			gas_ext_code: 700,
+			gas_balance: 400,
+			gas_sload: 200,
+			gas_suicide: 5000,
+			gas_suicide_new_account: 25000,
+			gas_call: 700,
+			err_on_call_with_more_gas: false,
+			call_l64_after_gas: true,
+			..Self::homestead()
+		}
+	}
+
+	/// Spurious Dragon hard fork configuration (EIP-155, EIP-158, EIP-160,
+	/// EIP-161 and EIP-170).
+	pub const fn spurious_dragon() -> Config {
+		Config {
+			gas_expbyte: 50,
+			empty_considered_exists: false,
+			create_increase_nonce: true,
+			create_contract_limit: Some(0x6000),
+			..Self::tangerine_whistle()
+		}
+	}
+
+	/// Byzantium hard fork configuration.
+	pub const fn byzantium() -> Config {
+		Config {
+			has_static_call: true,
+			has_revert: true,
+			has_return_data: true,
+			block_reward: 3_000_000_000_000_000_000,
+			..Self::spurious_dragon()
+		}
+	}
+
+	/// Constantinople hard fork configuration, with the EIP-1283 net gas
+	/// metering for `SSTORE`.
+	pub const fn constantinople() -> Config {
+		Config {
+			gas_ext_code_hash: 400,
+			sstore_gas_metering: true,
+			has_create2: true,
+			has_bitwise_shifting: true,
+			has_ext_code_hash: true,
+			block_reward: 2_000_000_000_000_000_000,
+			..Self::byzantium()
+		}
+	}
+
+	/// Petersburg hard fork configuration. Constantinople without EIP-1283.
+	pub const fn petersburg() -> Config {
+		Config {
+			sstore_gas_metering: false,
+			block_reward: 2_000_000_000_000_000_000,
+			..Self::constantinople()
+		}
+	}
+
 	/// Istanbul hard fork configuration.
 	pub const fn istanbul() -> Config {
 		Config {
@@ -369,23 +569,32 @@ impl Config {
 			gas_access_list_storage_key: 0,
 			gas_account_access_cold: 0,
 			gas_storage_read_warm: 0,
+			gas_per_empty_account_cost: 0,
+			gas_per_auth_base_cost: 0,
+			total_cost_floor_per_token: None,
 			sstore_gas_metering: true,
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: false,
 			decrease_clears_refund: false,
+			bn128_repricing: true,
+			modexp_repricing: false,
 			disallow_executable_format: false,
 			warm_coinbase_address: false,
 			err_on_call_with_more_gas: false,
 			empty_considered_exists: false,
 			create_increase_nonce: true,
+			err_on_code_deposit_out_of_gas: true,
 			call_l64_after_gas: true,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: Some(0x6000),
 			max_initcode_size: None,
+			max_blobs_per_block: 0,
+			block_reward: 2_000_000_000_000_000_000,
 			call_stipend: 2300,
 			has_delegate_call: true,
+			has_static_call: true,
 			has_create2: true,
 			has_revert: true,
 			has_return_data: true,
@@ -395,6 +604,19 @@ impl Config {
 			has_ext_code_hash: true,
 			has_base_fee: false,
 			has_push0: false,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: false,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			has_eof: false,
 			estimate: false,
 		}
 	}
@@ -419,6 +641,16 @@ impl Config {
 		Self::config_with_derived_values(DerivedConfigInputs::shanghai())
 	}
 
+	/// Cancun hard fork configuration.
+	pub const fn cancun() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::cancun())
+	}
+
+	/// Prague hard fork configuration.
+	pub const fn prague() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::prague())
+	}
+
 	const fn config_with_derived_values(inputs: DerivedConfigInputs) -> Config {
 		let DerivedConfigInputs {
 			gas_storage_read_warm,
@@ -427,9 +659,24 @@ impl Config {
 			decrease_clears_refund,
 			has_base_fee,
 			has_push0,
+			has_kzg_point_evaluation,
+			has_transient_storage,
+			has_mcopy,
+			has_blob_hash,
+			has_blob_base_fee,
+			has_restricted_selfdestruct,
+			has_authorization_list,
+			has_block_hash_history,
+			has_withdrawals,
+			has_beacon_block_root,
+			has_bls12_381_precompiles,
+			has_p256_verify,
+			total_cost_floor_per_token,
 			disallow_executable_format,
 			warm_coinbase_address,
 			max_initcode_size,
+			max_blobs_per_block,
+			block_reward,
 		} = inputs;
 
 		// See https://eips.ethereum.org/EIPS/eip-2929
@@ -466,23 +713,32 @@ impl Config {
 			gas_access_list_storage_key,
 			gas_account_access_cold: 2600,
 			gas_storage_read_warm,
+			gas_per_empty_account_cost: 25000,
+			gas_per_auth_base_cost: 12500,
+			total_cost_floor_per_token,
 			sstore_gas_metering: true,
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: true,
 			decrease_clears_refund,
+			bn128_repricing: true,
+			modexp_repricing: true,
 			disallow_executable_format,
 			warm_coinbase_address,
 			err_on_call_with_more_gas: false,
 			empty_considered_exists: false,
 			create_increase_nonce: true,
+			err_on_code_deposit_out_of_gas: true,
 			call_l64_after_gas: true,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: Some(0x6000),
 			max_initcode_size,
+			max_blobs_per_block,
+			block_reward,
 			call_stipend: 2300,
 			has_delegate_call: true,
+			has_static_call: true,
 			has_create2: true,
 			has_revert: true,
 			has_return_data: true,
@@ -492,6 +748,19 @@ impl Config {
 			has_ext_code_hash: true,
 			has_base_fee,
 			has_push0,
+			has_kzg_point_evaluation,
+			has_transient_storage,
+			has_mcopy,
+			has_blob_hash,
+			has_blob_base_fee,
+			has_restricted_selfdestruct,
+			has_authorization_list,
+			has_block_hash_history,
+			has_withdrawals,
+			has_beacon_block_root,
+			has_bls12_381_precompiles,
+			has_p256_verify,
+			has_eof: false,
 			estimate: false,
 		}
 	}
@@ -506,9 +775,24 @@ struct DerivedConfigInputs {
 	decrease_clears_refund: bool,
 	has_base_fee: bool,
 	has_push0: bool,
+	has_kzg_point_evaluation: bool,
+	has_transient_storage: bool,
+	has_mcopy: bool,
+	has_blob_hash: bool,
+	has_blob_base_fee: bool,
+	has_restricted_selfdestruct: bool,
+	has_authorization_list: bool,
+	has_block_hash_history: bool,
+	has_withdrawals: bool,
+	has_beacon_block_root: bool,
+	has_bls12_381_precompiles: bool,
+	has_p256_verify: bool,
+	total_cost_floor_per_token: Option<u64>,
 	disallow_executable_format: bool,
 	warm_coinbase_address: bool,
 	max_initcode_size: Option<usize>,
+	max_blobs_per_block: usize,
+	block_reward: u64,
 }
 
 impl DerivedConfigInputs {
@@ -520,9 +804,24 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: false,
 			has_base_fee: false,
 			has_push0: false,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: false,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			total_cost_floor_per_token: None,
 			disallow_executable_format: false,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
+			max_blobs_per_block: 0,
+			block_reward: 2_000_000_000_000_000_000,
 		}
 	}
 
@@ -534,9 +833,24 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: false,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: false,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			total_cost_floor_per_token: None,
 			disallow_executable_format: true,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
+			max_blobs_per_block: 0,
+			block_reward: 2_000_000_000_000_000_000,
 		}
 	}
 
@@ -548,9 +862,24 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: false,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: false,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			total_cost_floor_per_token: None,
 			disallow_executable_format: true,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
+			max_blobs_per_block: 0,
+			block_reward: 0,
 		}
 	}
 
@@ -562,10 +891,85 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: true,
+			has_kzg_point_evaluation: false,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			has_restricted_selfdestruct: false,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: true,
+			has_beacon_block_root: false,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			total_cost_floor_per_token: None,
+			disallow_executable_format: true,
+			warm_coinbase_address: true,
+			// 2 * 24576 as per EIP-3860
+			max_initcode_size: Some(0xC000),
+			max_blobs_per_block: 0,
+			block_reward: 0,
+		}
+	}
+
+	const fn cancun() -> Self {
+		Self {
+			gas_storage_read_warm: 100,
+			gas_sload_cold: 2100,
+			gas_access_list_storage_key: 1900,
+			decrease_clears_refund: true,
+			has_base_fee: true,
+			has_push0: true,
+			has_kzg_point_evaluation: true,
+			has_transient_storage: true,
+			has_mcopy: true,
+			has_blob_hash: true,
+			has_blob_base_fee: true,
+			has_restricted_selfdestruct: true,
+			has_authorization_list: false,
+			has_block_hash_history: false,
+			has_withdrawals: true,
+			has_beacon_block_root: true,
+			has_bls12_381_precompiles: false,
+			has_p256_verify: false,
+			total_cost_floor_per_token: None,
+			disallow_executable_format: true,
+			warm_coinbase_address: true,
+			// 2 * 24576 as per EIP-3860
+			max_initcode_size: Some(0xC000),
+			max_blobs_per_block: 6,
+			block_reward: 0,
+		}
+	}
+
+	const fn prague() -> Self {
+		Self {
+			gas_storage_read_warm: 100,
+			gas_sload_cold: 2100,
+			gas_access_list_storage_key: 1900,
+			decrease_clears_refund: true,
+			has_base_fee: true,
+			has_push0: true,
+			has_kzg_point_evaluation: true,
+			has_transient_storage: true,
+			has_mcopy: true,
+			has_blob_hash: true,
+			has_blob_base_fee: true,
+			has_restricted_selfdestruct: true,
+			has_authorization_list: true,
+			has_block_hash_history: true,
+			has_withdrawals: true,
+			has_beacon_block_root: true,
+			has_bls12_381_precompiles: true,
+			has_p256_verify: false,
+			total_cost_floor_per_token: Some(10),
 			disallow_executable_format: true,
 			warm_coinbase_address: true,
 			// 2 * 24576 as per EIP-3860
 			max_initcode_size: Some(0xC000),
+			max_blobs_per_block: 9,
+			block_reward: 0,
 		}
 	}
 }
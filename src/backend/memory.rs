@@ -1,4 +1,6 @@
-use super::{Apply, ApplyBackend, Backend, Basic, Log};
+use super::{Apply, ApplyBackend, Backend, Basic, Log, StateTrie};
+#[cfg(feature = "with-codec")]
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use primitive_types::{H160, H256, U256};
 
@@ -30,6 +32,10 @@ pub struct MemoryVicinity {
 	pub block_gas_limit: U256,
 	/// Environmental base fee per gas.
 	pub block_base_fee_per_gas: U256,
+	/// Environmental blob base fee.
+	pub block_blob_base_fee: U256,
+	/// Versioned hashes of the transaction blobs.
+	pub blob_hashes: Vec<H256>,
 	/// Environmental randomness.
 	///
 	/// In Ethereum, this is the randomness beacon provided by the beacon
@@ -39,10 +45,6 @@ pub struct MemoryVicinity {
 
 /// Account information of a memory backend.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-	feature = "with-codec",
-	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
-)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryAccount {
 	/// Account nonce.
@@ -55,12 +57,62 @@ pub struct MemoryAccount {
 	pub code: Vec<u8>,
 }
 
+// `hashbrown` maps have no SCALE codec, so the storage is encoded as an
+// ordered map, which also keeps the encoding deterministic.
+#[cfg(feature = "with-codec")]
+impl scale_codec::Encode for MemoryAccount {
+	fn encode_to<T: scale_codec::Output + ?Sized>(&self, dest: &mut T) {
+		self.nonce.encode_to(dest);
+		self.balance.encode_to(dest);
+		self.storage
+			.iter()
+			.collect::<BTreeMap<_, _>>()
+			.encode_to(dest);
+		self.code.encode_to(dest);
+	}
+}
+
+#[cfg(feature = "with-codec")]
+impl scale_codec::Decode for MemoryAccount {
+	fn decode<I: scale_codec::Input>(input: &mut I) -> Result<Self, scale_codec::Error> {
+		Ok(Self {
+			nonce: U256::decode(input)?,
+			balance: U256::decode(input)?,
+			storage: BTreeMap::<H256, H256>::decode(input)?.into_iter().collect(),
+			code: Vec::decode(input)?,
+		})
+	}
+}
+
+#[cfg(feature = "with-codec")]
+impl scale_info::TypeInfo for MemoryAccount {
+	type Identity = Self;
+
+	fn type_info() -> scale_info::Type {
+		scale_info::Type::builder()
+			.path(scale_info::Path::new("MemoryAccount", module_path!()))
+			.composite(
+				scale_info::build::Fields::named()
+					.field(|f| f.ty::<U256>().name("nonce").type_name("U256"))
+					.field(|f| f.ty::<U256>().name("balance").type_name("U256"))
+					.field(|f| {
+						f.ty::<BTreeMap<H256, H256>>()
+							.name("storage")
+							.type_name("BTreeMap<H256, H256>")
+					})
+					.field(|f| f.ty::<Vec<u8>>().name("code").type_name("Vec<u8>")),
+			)
+	}
+}
+
 /// Memory backend, storing all state values in a `BTreeMap` in memory.
 #[derive(Clone, Debug)]
 pub struct MemoryBackend {
 	vicinity: MemoryVicinity,
 	state: hashbrown::HashMap<H160, MemoryAccount>,
 	logs: Vec<Log>,
+	/// Tries of the state, built on the first root and updated on apply.
+	trie: Option<StateTrie>,
 }
 
 impl MemoryBackend {
@@ -70,6 +122,7 @@ impl MemoryBackend {
 			vicinity,
 			state,
 			logs: Vec::new(),
+			trie: None,
 		}
 	}
 
@@ -79,9 +132,28 @@ impl MemoryBackend {
 	}
 
 	/// Get a mutable reference to the underlying `BTreeMap` storing the state.
+	/// The tries of the state are rebuilt on the next root.
 	pub fn state_mut(&mut self) -> &mut hashbrown::HashMap<H160, MemoryAccount> {
+		self.trie = None;
 		&mut self.state
 	}
+
+	/// Root of the state trie of the accounts. After the first one, only
+	/// the accounts changed by `ApplyBackend::apply` are rehashed.
+	pub fn state_root(&mut self) -> H256 {
+		self.trie().root()
+	}
+
+	/// Root of the storage trie of an account.
+	pub fn storage_root(&mut self, address: H160) -> H256 {
+		self.trie().storage_root(address)
+	}
+
+	fn trie(&mut self) -> &mut StateTrie {
+		let state = &self.state;
+		self.trie
+			.get_or_insert_with(|| StateTrie::from_memory(state))
+	}
 }
 
 impl Backend for MemoryBackend {
@@ -123,6 +195,16 @@ impl Backend for MemoryBackend {
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.vicinity.block_base_fee_per_gas
 	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.vicinity.block_blob_base_fee
+	}
+	fn blob_hash(&self, index: U256) -> Option<H256> {
+		if index >= U256::from(self.vicinity.blob_hashes.len()) {
+			None
+		} else {
+			Some(self.vicinity.blob_hashes[index.as_usize()])
+		}
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.vicinity.chain_id
@@ -163,6 +245,10 @@ impl Backend for MemoryBackend {
 
 impl ApplyBackend for MemoryBackend {
 	fn apply(&mut self, values: Vec<Apply>, logs: Vec<Log>, delete_empty: bool) {
+		if let Some(trie) = &mut self.trie {
+			trie.apply(&values, delete_empty);
+		}
+
 		for apply in values {
 			match apply {
 				Apply::Modify {
@@ -3,9 +3,14 @@
 //! Backends store state information of the VM, and exposes it to runtime.
 
 mod memory;
+mod state_trie;
+mod trie;
 
 pub use self::memory::{MemoryAccount, MemoryBackend, MemoryVicinity};
-use alloc::{vec::Vec, collections::BTreeMap};
+pub use self::state_trie::{StateTrie, EMPTY_CODE_HASH};
+pub use self::trie::{SecureTrie, Trie, EMPTY_TRIE_ROOT};
+use crate::{ChainSpec, Config};
+use alloc::{collections::BTreeMap, vec::Vec};
 use primitive_types::{H160, H256, U256};
 /// Basic account information.
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
@@ -70,8 +75,22 @@ pub trait Backend {
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Environmental block blob base fee. Zero by default, as before Cancun.
+	fn block_blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+	/// Versioned hash of the transaction blob at index, if any. None by
+	/// default, as for transactions without blobs.
+	fn blob_hash(&self, _index: U256) -> Option<H256> {
+		None
+	}
 	/// Environmental chain ID.
 	fn chain_id(&self) -> U256;
+	/// Hard fork configuration of the environmental block under the given
+	/// chain specification.
+	fn config<'spec>(&self, chain_spec: &'spec ChainSpec) -> &'spec Config {
+		chain_spec.config_at(self.block_number(), self.block_timestamp())
+	}
 
 	/// Whether account at address exists.
 	fn exists(&self, address: H160) -> bool;
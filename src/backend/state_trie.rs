@@ -0,0 +1,151 @@
+//! State and storage roots of accounts, computed with secure tries.
+
+use super::trie::{keccak, SecureTrie, EMPTY_TRIE_ROOT};
+use super::{Apply, Basic, MemoryAccount};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use rlp::RlpStream;
+
+/// Hash of empty code.
+pub const EMPTY_CODE_HASH: H256 = H256([
+	0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+	0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// State trie of a set of accounts, with their storage tries. It is updated
+/// with state changes, after which the root only rehashes the tries of the
+/// changed accounts.
+#[derive(Clone, Debug, Default)]
+pub struct StateTrie {
+	trie: SecureTrie,
+	accounts: BTreeMap<H160, Account>,
+	/// Accounts changed since the last root.
+	dirty: BTreeSet<H160>,
+}
+
+#[derive(Clone, Debug)]
+struct Account {
+	basic: Basic,
+	code_hash: H256,
+	storage: SecureTrie,
+}
+
+impl Account {
+	fn new(basic: Basic) -> Self {
+		Self {
+			basic,
+			code_hash: EMPTY_CODE_HASH,
+			storage: SecureTrie::new(),
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.basic.balance == U256::zero()
+			&& self.basic.nonce == U256::zero()
+			&& self.code_hash == EMPTY_CODE_HASH
+	}
+
+	fn set_storage(&mut self, index: H256, value: H256) {
+		if value == H256::zero() {
+			self.storage.remove(index.as_bytes());
+		} else {
+			let value = U256::from_big_endian(value.as_bytes());
+			self.storage
+				.insert(index.as_bytes(), rlp::encode(&value).to_vec());
+		}
+	}
+
+	fn encode(&mut self) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(4);
+		stream.append(&self.basic.nonce);
+		stream.append(&self.basic.balance);
+		stream.append(&self.storage.root());
+		stream.append(&self.code_hash);
+		stream.out().to_vec()
+	}
+}
+
+impl StateTrie {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// State trie of the accounts of a `MemoryBackend`.
+	pub fn from_memory(state: &hashbrown::HashMap<H160, MemoryAccount>) -> Self {
+		let mut trie = Self::new();
+		for (address, memory) in state {
+			let mut account = Account::new(Basic {
+				balance: memory.balance,
+				nonce: memory.nonce,
+			});
+			account.code_hash = keccak(&memory.code);
+			for (index, value) in &memory.storage {
+				account.set_storage(*index, *value);
+			}
+			trie.accounts.insert(*address, account);
+			trie.dirty.insert(*address);
+		}
+		trie
+	}
+
+	/// Update the tries with state changes, deleting empty accounts like
+	/// `ApplyBackend::apply` with the same `delete_empty`.
+	pub fn apply<'a, I: IntoIterator<Item = &'a Apply>>(&mut self, values: I, delete_empty: bool) {
+		for apply in values {
+			match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let account = self
+						.accounts
+						.entry(*address)
+						.or_insert_with(|| Account::new(Basic::default()));
+					account.basic = basic.clone();
+					if let Some(code) = code {
+						account.code_hash = keccak(code);
+					}
+					if *reset_storage {
+						account.storage = SecureTrie::new();
+					}
+					for (index, value) in storage {
+						account.set_storage(*index, *value);
+					}
+
+					if delete_empty && account.is_empty() {
+						self.accounts.remove(address);
+					}
+					self.dirty.insert(*address);
+				}
+				Apply::Delete { address } => {
+					self.accounts.remove(address);
+					self.dirty.insert(*address);
+				}
+			}
+		}
+	}
+
+	/// Root of the state trie.
+	pub fn root(&mut self) -> H256 {
+		for address in core::mem::take(&mut self.dirty) {
+			match self.accounts.get_mut(&address) {
+				Some(account) => self.trie.insert(address.as_bytes(), account.encode()),
+				None => self.trie.remove(address.as_bytes()),
+			}
+		}
+		self.trie.root()
+	}
+
+	/// Root of the storage trie of an account, the root of the empty trie for
+	/// missing accounts.
+	pub fn storage_root(&mut self, address: H160) -> H256 {
+		match self.accounts.get_mut(&address) {
+			Some(account) => account.storage.root(),
+			None => EMPTY_TRIE_ROOT,
+		}
+	}
+}
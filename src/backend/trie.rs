@@ -0,0 +1,433 @@
+//! Merkle-Patricia tries, as committed to by the roots of block headers.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::mem;
+use primitive_types::H256;
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+/// Root of the empty trie, the hash of the RLP of the empty string.
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+	0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+	0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+pub(crate) fn keccak(data: &[u8]) -> H256 {
+	H256::from_slice(Keccak256::digest(data).as_slice())
+}
+
+/// Merkle-Patricia trie. The references of the nodes are kept until their
+/// paths are modified, so the root after a few updates only rehashes the
+/// modified paths.
+#[derive(Clone, Debug, Default)]
+pub struct Trie {
+	root: Node,
+}
+
+impl Trie {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the value at `key`. Tries do not store empty values, which remove
+	/// the key instead.
+	pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+		if value.is_empty() {
+			return self.remove(key);
+		}
+		let root = mem::take(&mut self.root);
+		self.root = root.insert(&nibbles(key), value);
+	}
+
+	/// Remove the value at `key`, if any.
+	pub fn remove(&mut self, key: &[u8]) {
+		let root = mem::take(&mut self.root);
+		self.root = root.remove(&nibbles(key));
+	}
+
+	/// Root hash of the trie.
+	pub fn root(&mut self) -> H256 {
+		keccak(&self.root.encode())
+	}
+}
+
+/// Trie of keys hashed with Keccak-256, as the state and storage tries.
+#[derive(Clone, Debug, Default)]
+pub struct SecureTrie {
+	trie: Trie,
+}
+
+impl SecureTrie {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the value at the hash of `key`. Empty values remove the key.
+	pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+		self.trie.insert(keccak(key).as_bytes(), value)
+	}
+
+	/// Remove the value at the hash of `key`, if any.
+	pub fn remove(&mut self, key: &[u8]) {
+		self.trie.remove(keccak(key).as_bytes())
+	}
+
+	/// Root hash of the trie.
+	pub fn root(&mut self) -> H256 {
+		self.trie.root()
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+	kind: Kind,
+	/// Reference of the node in its parent, unless modified since computed.
+	reference: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, Default)]
+enum Kind {
+	#[default]
+	Empty,
+	Leaf {
+		path: Vec<u8>,
+		value: Vec<u8>,
+	},
+	Extension {
+		path: Vec<u8>,
+		child: Box<Node>,
+	},
+	Branch {
+		children: Box<[Node; 16]>,
+		value: Option<Vec<u8>>,
+	},
+}
+
+impl Node {
+	fn new(kind: Kind) -> Self {
+		Self {
+			kind,
+			reference: None,
+		}
+	}
+
+	fn leaf(path: Vec<u8>, value: Vec<u8>) -> Self {
+		Self::new(Kind::Leaf { path, value })
+	}
+
+	/// `child` under `path`, which may be empty.
+	fn extension(path: Vec<u8>, child: Node) -> Self {
+		if path.is_empty() {
+			return child;
+		}
+		Self::new(Kind::Extension {
+			path,
+			child: Box::new(child),
+		})
+	}
+
+	fn branch(children: Box<[Node; 16]>, value: Option<Vec<u8>>) -> Self {
+		Self::new(Kind::Branch { children, value })
+	}
+
+	fn is_empty(&self) -> bool {
+		matches!(self.kind, Kind::Empty)
+	}
+
+	fn insert(self, path: &[u8], value: Vec<u8>) -> Node {
+		match self.kind {
+			Kind::Empty => Node::leaf(path.to_vec(), value),
+			Kind::Leaf {
+				path: leaf_path,
+				value: leaf_value,
+			} => {
+				if leaf_path == path {
+					return Node::leaf(leaf_path, value);
+				}
+				let common = common_prefix(&leaf_path, path);
+				let mut children = Box::<[Node; 16]>::default();
+				let mut branch_value = None;
+				put(
+					&mut children,
+					&mut branch_value,
+					&leaf_path[common..],
+					leaf_value,
+				);
+				put(&mut children, &mut branch_value, &path[common..], value);
+				Node::extension(
+					path[..common].to_vec(),
+					Node::branch(children, branch_value),
+				)
+			}
+			Kind::Extension {
+				path: extension_path,
+				child,
+			} => {
+				let common = common_prefix(&extension_path, path);
+				if common == extension_path.len() {
+					let child = child.insert(&path[common..], value);
+					return Node::extension(extension_path, child);
+				}
+				let mut children = Box::<[Node; 16]>::default();
+				let mut branch_value = None;
+				children[extension_path[common] as usize] =
+					Node::extension(extension_path[common + 1..].to_vec(), *child);
+				put(&mut children, &mut branch_value, &path[common..], value);
+				Node::extension(
+					path[..common].to_vec(),
+					Node::branch(children, branch_value),
+				)
+			}
+			Kind::Branch {
+				mut children,
+				value: branch_value,
+			} => match path.split_first() {
+				None => Node::branch(children, Some(value)),
+				Some((&nibble, rest)) => {
+					let child = mem::take(&mut children[nibble as usize]);
+					children[nibble as usize] = child.insert(rest, value);
+					Node::branch(children, branch_value)
+				}
+			},
+		}
+	}
+
+	fn remove(self, path: &[u8]) -> Node {
+		match self.kind {
+			Kind::Empty => Node::default(),
+			Kind::Leaf {
+				path: leaf_path,
+				value,
+			} => {
+				if leaf_path == path {
+					return Node::default();
+				}
+				Node {
+					kind: Kind::Leaf {
+						path: leaf_path,
+						value,
+					},
+					reference: self.reference,
+				}
+			}
+			Kind::Extension {
+				path: extension_path,
+				child,
+			} => {
+				if !path.starts_with(&extension_path) {
+					return Node {
+						kind: Kind::Extension {
+							path: extension_path,
+							child,
+						},
+						reference: self.reference,
+					};
+				}
+				let child = child.remove(&path[extension_path.len()..]);
+				Node::join(extension_path, child)
+			}
+			Kind::Branch {
+				mut children,
+				value,
+			} => {
+				let value = match path.split_first() {
+					None => None,
+					Some((&nibble, rest)) => {
+						let child = mem::take(&mut children[nibble as usize]);
+						children[nibble as usize] = child.remove(rest);
+						value
+					}
+				};
+
+				// Branches with a single entry left collapse into it.
+				let mut occupied = (0..16).filter(|&i| !children[i].is_empty());
+				match (occupied.next(), occupied.next(), value) {
+					(None, _, None) => Node::default(),
+					(None, _, Some(value)) => Node::leaf(Vec::new(), value),
+					(Some(i), None, None) => {
+						let child = mem::take(&mut children[i]);
+						Node::join(Vec::from([i as u8]), child)
+					}
+					(_, _, value) => Node::branch(children, value),
+				}
+			}
+		}
+	}
+
+	/// `child` under `prefix`, merging it into the path of the child.
+	fn join(mut prefix: Vec<u8>, child: Node) -> Node {
+		match child.kind {
+			Kind::Empty => Node::default(),
+			Kind::Leaf { path, value } => {
+				prefix.extend_from_slice(&path);
+				Node::leaf(prefix, value)
+			}
+			Kind::Extension { path, child } => {
+				prefix.extend_from_slice(&path);
+				Node::extension(prefix, *child)
+			}
+			kind @ Kind::Branch { .. } => Node::extension(
+				prefix,
+				Node {
+					kind,
+					reference: child.reference,
+				},
+			),
+		}
+	}
+
+	/// RLP of the node, with the references of its children.
+	fn encode(&mut self) -> Vec<u8> {
+		let mut stream = RlpStream::new();
+		match &mut self.kind {
+			Kind::Empty => {
+				stream.append_empty_data();
+			}
+			Kind::Leaf { path, value } => {
+				stream.begin_list(2);
+				stream.append(&hex_prefix(path, true));
+				stream.append(value);
+			}
+			Kind::Extension { path, child } => {
+				stream.begin_list(2);
+				stream.append(&hex_prefix(path, false));
+				stream.append_raw(&child.reference(), 1);
+			}
+			Kind::Branch { children, value } => {
+				stream.begin_list(17);
+				for child in children.iter_mut() {
+					stream.append_raw(&child.reference(), 1);
+				}
+				match value {
+					Some(value) => stream.append(value),
+					None => stream.append_empty_data(),
+				};
+			}
+		}
+		stream.out().to_vec()
+	}
+
+	/// Reference of the node in its parent: its RLP if shorter than a hash,
+	/// and the hash of its RLP otherwise.
+	fn reference(&mut self) -> Vec<u8> {
+		if let Some(reference) = &self.reference {
+			return reference.clone();
+		}
+
+		let encoded = self.encode();
+		let reference = if encoded.len() < 32 {
+			encoded
+		} else {
+			rlp::encode(&keccak(&encoded)).to_vec()
+		};
+		self.reference = Some(reference.clone());
+		reference
+	}
+}
+
+/// Put `value` at `path` of a new branch.
+fn put(children: &mut [Node; 16], branch_value: &mut Option<Vec<u8>>, path: &[u8], value: Vec<u8>) {
+	match path.split_first() {
+		None => *branch_value = Some(value),
+		Some((&nibble, rest)) => children[nibble as usize] = Node::leaf(rest.to_vec(), value),
+	}
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+	a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+	key.iter()
+		.flat_map(|byte| [byte >> 4, byte & 0x0f])
+		.collect()
+}
+
+/// Compact encoding of a path, flagging odd lengths and leaves.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+	let flag = if is_leaf { 2 } else { 0 };
+	let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+	let even = if nibbles.len() % 2 == 1 {
+		out.push(((flag + 1) << 4) | nibbles[0]);
+		&nibbles[1..]
+	} else {
+		out.push(flag << 4);
+		nibbles
+	};
+	for pair in even.chunks(2) {
+		out.push((pair[0] << 4) | pair[1]);
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::str::FromStr;
+
+	fn root(items: &[(&str, &str)]) -> H256 {
+		let mut trie = Trie::new();
+		for (key, value) in items {
+			trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+		}
+		trie.root()
+	}
+
+	#[test]
+	fn empty_root() {
+		assert_eq!(Trie::new().root(), EMPTY_TRIE_ROOT);
+		assert_eq!(keccak(&rlp::encode(&"")), EMPTY_TRIE_ROOT);
+	}
+
+	#[test]
+	fn known_roots() {
+		assert_eq!(
+			root(&[
+				("do", "verb"),
+				("dog", "puppy"),
+				("doge", "coin"),
+				("horse", "stallion"),
+			]),
+			H256::from_str("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+				.unwrap()
+		);
+		assert_eq!(
+			root(&[
+				("doe", "reindeer"),
+				("dog", "puppy"),
+				("dogglesworth", "cat"),
+			]),
+			H256::from_str("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+				.unwrap()
+		);
+	}
+
+	#[test]
+	fn roots_do_not_depend_on_history() {
+		let items = [
+			("do", "verb"),
+			("dog", "puppy"),
+			("doge", "coin"),
+			("horse", "stallion"),
+		];
+
+		let mut trie = Trie::new();
+		for (key, value) in items.iter().rev() {
+			trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+		}
+		let expected = trie.root();
+		trie.insert(b"dogs", b"bark".to_vec());
+		trie.insert(b"d", b"letter".to_vec());
+		assert_ne!(trie.root(), expected);
+		trie.remove(b"dogs");
+		trie.insert(b"d", Vec::new());
+		trie.remove(b"missing");
+		assert_eq!(trie.root(), expected);
+		assert_eq!(root(&items), expected);
+
+		for (key, _) in &items {
+			trie.remove(key.as_bytes());
+		}
+		assert_eq!(trie.root(), EMPTY_TRIE_ROOT);
+	}
+}
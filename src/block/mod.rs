@@ -0,0 +1,323 @@
+//! # Block execution
+//!
+//! Executes the transactions of a block one after the other with
+//! `StackExecutor`, applying the state changes of each transaction to the
+//! backend before running the next one. Around the transactions, the block
+//! runs the system calls of its hard fork, credits its withdrawals, and pays
+//! the mining rewards before the Merge.
+
+mod view;
+
+use self::view::BlockView;
+use crate::backend::{Apply, ApplyBackend, Backend};
+use crate::executor::stack::{
+	Bloom, InvalidTransaction, MemoryStackState, PrecompileSet, Receipt, ReceiptBuilder,
+	StackExecutor, StackSubstateMetadata, TransactionError, TransactionOutcome, TypedTransaction,
+	GAS_PER_BLOB,
+};
+use crate::{Config, ExitError, BEACON_ROOTS_ADDRESS, HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS};
+use alloc::{collections::BTreeMap, vec::Vec};
+use primitive_types::{H160, H256, U256};
+
+/// Gas available to each system call, which is not part of the gas used by
+/// the block.
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+/// Header fields the execution of a block depends on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockEnv {
+	/// Hash of the parent block, also stored by the EIP-2935 system call.
+	pub parent_hash: H256,
+	/// Block number.
+	pub number: U256,
+	/// Beneficiary of the fees and rewards.
+	pub coinbase: H160,
+	/// Block timestamp.
+	pub timestamp: U256,
+	/// Block difficulty.
+	pub difficulty: U256,
+	/// `prevRandao` since the Merge.
+	pub randomness: Option<H256>,
+	/// Block gas limit.
+	pub gas_limit: U256,
+	/// Base fee per gas, since London.
+	pub base_fee_per_gas: U256,
+	/// Blob base fee, since Cancun.
+	pub blob_base_fee: U256,
+	/// Root of the parent beacon block, stored by the EIP-4788 system call.
+	pub parent_beacon_block_root: Option<H256>,
+}
+
+/// Ommer (uncle) of a block, rewarded before the Merge.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ommer {
+	/// Beneficiary of the ommer reward.
+	pub coinbase: H160,
+	/// Number of the ommer block.
+	pub number: U256,
+}
+
+/// Withdrawal from the beacon chain. See EIP-4895.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Withdrawal {
+	/// Index of the withdrawal.
+	pub index: u64,
+	/// Index of the withdrawing validator.
+	pub validator_index: u64,
+	/// Recipient of the withdrawal.
+	pub address: H160,
+	/// Withdrawn amount, in gwei.
+	pub amount: u64,
+}
+
+/// Block to be executed by the [`BlockExecutor`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Block {
+	/// Environment of the block.
+	pub env: BlockEnv,
+	/// Transactions, with their senders, in block order.
+	pub transactions: Vec<(TypedTransaction, H160)>,
+	/// Ommers of the block.
+	pub ommers: Vec<Ommer>,
+	/// Withdrawals of the block.
+	pub withdrawals: Vec<Withdrawal>,
+}
+
+/// Reason for a block to be invalid. The backend is left with the state
+/// changes made before the error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BlockError {
+	/// A transaction is invalid.
+	InvalidTransaction {
+		index: usize,
+		error: InvalidTransaction,
+	},
+	/// Accessing the state failed during a transaction.
+	TransactionAborted { index: usize, error: ExitError },
+	/// The gas limit of a transaction is above the gas left in the block.
+	GasLimitExceeded {
+		index: usize,
+		gas_limit: u64,
+		gas_left: U256,
+	},
+	/// The blob gas of a transaction is above the blob gas left in the block.
+	BlobGasLimitExceeded {
+		index: usize,
+		blob_gas: u64,
+		blob_gas_left: u64,
+	},
+	/// The block has withdrawals before Shanghai.
+	UnsupportedWithdrawals,
+	/// The block has no parent beacon block root since Cancun.
+	MissingParentBeaconBlockRoot,
+	/// The hard fork is before Byzantium, whose receipts hold the state root
+	/// after each transaction instead of a status. See EIP-658.
+	UnsupportedHardFork,
+}
+
+/// Outcome of a block, whose state changes are applied to the backend.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockOutput {
+	/// Receipts of the transactions, in block order.
+	pub receipts: Vec<Receipt>,
+	/// Outcomes of the transactions, in block order.
+	pub outcomes: Vec<TransactionOutcome>,
+	/// Gas used by the transactions.
+	pub gas_used: u64,
+	/// Blob gas used by the transactions.
+	pub blob_gas_used: u64,
+	/// Bloom filter of the logs of the block.
+	pub logs_bloom: Bloom,
+}
+
+/// Executor of whole blocks against an `ApplyBackend`, since Byzantium.
+pub struct BlockExecutor<'config, 'precompiles, 'backend, B, P> {
+	config: &'config Config,
+	precompile_set: &'precompiles P,
+	backend: &'backend mut B,
+}
+
+impl<'config, 'precompiles, 'backend, B, P> BlockExecutor<'config, 'precompiles, 'backend, B, P>
+where
+	B: Backend + ApplyBackend,
+	P: PrecompileSet,
+{
+	/// Create a new block executor. Hard forks before Byzantium are not
+	/// supported, as the receipts of their transactions need the state root
+	/// after each of them.
+	pub fn new(
+		backend: &'backend mut B,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+	) -> Result<Self, BlockError> {
+		if !config.has_revert {
+			return Err(BlockError::UnsupportedHardFork);
+		}
+
+		Ok(Self {
+			config,
+			precompile_set,
+			backend,
+		})
+	}
+
+	/// Execute the block: the system calls, the transactions in order, then
+	/// the withdrawals and rewards. Empty accounts are deleted unless
+	/// `Config::empty_considered_exists` is set.
+	pub fn execute(&mut self, block: &Block) -> Result<BlockOutput, BlockError> {
+		let env = &block.env;
+		if !block.withdrawals.is_empty() && !self.config.has_withdrawals {
+			return Err(BlockError::UnsupportedWithdrawals);
+		}
+
+		self.apply_system_calls(env)?;
+
+		let base_fee = if self.config.has_base_fee {
+			env.base_fee_per_gas
+		} else {
+			U256::zero()
+		};
+		let max_blob_gas = GAS_PER_BLOB * self.config.max_blobs_per_block as u64;
+		let mut receipts = ReceiptBuilder::new();
+		let mut outcomes = Vec::with_capacity(block.transactions.len());
+		let mut blob_gas_used = 0;
+
+		for (index, (typed, sender)) in block.transactions.iter().enumerate() {
+			let transaction = typed.to_transaction(*sender);
+
+			let gas_left = env
+				.gas_limit
+				.saturating_sub(U256::from(receipts.cumulative_gas_used()));
+			if U256::from(transaction.gas_limit) > gas_left {
+				return Err(BlockError::GasLimitExceeded {
+					index,
+					gas_limit: transaction.gas_limit,
+					gas_left,
+				});
+			}
+			let blob_gas = transaction.blob_gas();
+			if blob_gas > max_blob_gas - blob_gas_used {
+				return Err(BlockError::BlobGasLimitExceeded {
+					index,
+					blob_gas,
+					blob_gas_left: max_blob_gas - blob_gas_used,
+				});
+			}
+
+			let view = BlockView::new(
+				&*self.backend,
+				env,
+				*sender,
+				transaction.effective_gas_price(base_fee),
+				&transaction.blob_versioned_hashes,
+			);
+			let metadata = StackSubstateMetadata::new(transaction.gas_limit, self.config);
+			let state = MemoryStackState::new(metadata, &view);
+			let mut executor =
+				StackExecutor::new_with_precompiles(state, self.config, self.precompile_set, false);
+			let outcome = executor
+				.transact_typed(typed, *sender)
+				.map_err(|error| match error {
+					TransactionError::Invalid(error) => {
+						BlockError::InvalidTransaction { index, error }
+					}
+					TransactionError::Aborted(error) => {
+						BlockError::TransactionAborted { index, error }
+					}
+				})?;
+			let (values, logs) = executor.into_state().deconstruct();
+			self.backend
+				.apply(values, logs.clone(), !self.config.empty_considered_exists);
+
+			blob_gas_used += outcome.blob_gas_used;
+			receipts.push(typed.transaction_type(), &outcome, logs);
+			outcomes.push(outcome);
+		}
+
+		for withdrawal in &block.withdrawals {
+			let amount = U256::from(withdrawal.amount) * U256::from(1_000_000_000u64);
+			self.credit(withdrawal.address, amount);
+		}
+		self.pay_rewards(env, &block.ommers);
+
+		Ok(BlockOutput {
+			gas_used: receipts.cumulative_gas_used(),
+			blob_gas_used,
+			logs_bloom: receipts.logs_bloom(),
+			receipts: receipts.finish(),
+			outcomes,
+		})
+	}
+
+	/// Run the system calls made before the transactions of the block.
+	fn apply_system_calls(&mut self, env: &BlockEnv) -> Result<(), BlockError> {
+		if self.config.has_beacon_block_root {
+			let root = env
+				.parent_beacon_block_root
+				.ok_or(BlockError::MissingParentBeaconBlockRoot)?;
+			self.system_call(env, BEACON_ROOTS_ADDRESS, root.as_bytes().to_vec());
+		}
+		if self.config.has_block_hash_history && !env.number.is_zero() {
+			self.system_call(
+				env,
+				HISTORY_STORAGE_ADDRESS,
+				env.parent_hash.as_bytes().to_vec(),
+			);
+		}
+		Ok(())
+	}
+
+	/// Call a system contract, which fails silently if it is not deployed.
+	fn system_call(&mut self, env: &BlockEnv, address: H160, data: Vec<u8>) {
+		if self.backend.code(address).is_empty() {
+			return;
+		}
+
+		let view = BlockView::new(&*self.backend, env, SYSTEM_ADDRESS, U256::zero(), &[]);
+		let metadata = StackSubstateMetadata::new(SYSTEM_CALL_GAS_LIMIT, self.config);
+		let state = MemoryStackState::new(metadata, &view);
+		let mut executor =
+			StackExecutor::new_with_precompiles(state, self.config, self.precompile_set, false);
+		let _reason = executor.system_call(address, data, SYSTEM_CALL_GAS_LIMIT);
+		let (values, _logs) = executor.into_state().deconstruct();
+		self.backend
+			.apply(values, Vec::new(), !self.config.empty_considered_exists);
+	}
+
+	/// Pay the block and ommer rewards of a block mined before the Merge.
+	fn pay_rewards(&mut self, env: &BlockEnv, ommers: &[Ommer]) {
+		if self.config.block_reward == 0 {
+			return;
+		}
+
+		let reward = U256::from(self.config.block_reward);
+		// The miner gets 1/32 of the reward for each ommer it includes, and the
+		// ommer miners 1/8 of it for each block of distance they are within 8.
+		for ommer in ommers {
+			let distance_left = (ommer.number + 8).saturating_sub(env.number);
+			self.credit(ommer.coinbase, reward * distance_left / 8);
+		}
+		self.credit(
+			env.coinbase,
+			reward + reward / 32 * U256::from(ommers.len()),
+		);
+	}
+
+	/// Add `amount` to the balance of `address`, without any gas.
+	fn credit(&mut self, address: H160, amount: U256) {
+		let mut basic = self.backend.basic(address);
+		basic.balance = basic.balance.saturating_add(amount);
+		let apply = Apply::Modify {
+			address,
+			basic,
+			code: None,
+			storage: BTreeMap::new(),
+			reset_storage: false,
+		};
+		self.backend.apply(
+			Vec::from([apply]),
+			Vec::new(),
+			!self.config.empty_considered_exists,
+		);
+	}
+}
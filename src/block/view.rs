@@ -0,0 +1,99 @@
+//! Backend seen by the transactions and system calls of a block.
+
+use super::BlockEnv;
+use crate::backend::{Backend, Basic};
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+
+/// Backend serving the environment of the block and of the current
+/// transaction, and the state of the base backend.
+pub struct BlockView<'a, B> {
+	backend: &'a B,
+	env: &'a BlockEnv,
+	origin: H160,
+	gas_price: U256,
+	blob_hashes: &'a [H256],
+}
+
+impl<'a, B: Backend> BlockView<'a, B> {
+	pub fn new(
+		backend: &'a B,
+		env: &'a BlockEnv,
+		origin: H160,
+		gas_price: U256,
+		blob_hashes: &'a [H256],
+	) -> Self {
+		Self {
+			backend,
+			env,
+			origin,
+			gas_price,
+			blob_hashes,
+		}
+	}
+}
+
+impl<'a, B: Backend> Backend for BlockView<'a, B> {
+	fn gas_price(&self) -> U256 {
+		self.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		if self.env.number.checked_sub(U256::one()) == Some(number) {
+			self.env.parent_hash
+		} else {
+			self.backend.block_hash(number)
+		}
+	}
+	fn block_number(&self) -> U256 {
+		self.env.number
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.env.coinbase
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.env.timestamp
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.env.difficulty
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.env.randomness
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.env.gas_limit
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.env.base_fee_per_gas
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.env.blob_base_fee
+	}
+	fn blob_hash(&self, index: U256) -> Option<H256> {
+		if index >= U256::from(self.blob_hashes.len()) {
+			return None;
+		}
+		Some(self.blob_hashes[index.as_usize()])
+	}
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.backend.exists(address)
+	}
+	fn basic(&self, address: H160) -> Basic {
+		self.backend.basic(address)
+	}
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.backend.code(address)
+	}
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.storage(address, index)
+	}
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		self.backend.original_storage(address, index)
+	}
+}
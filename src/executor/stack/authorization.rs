@@ -0,0 +1,76 @@
+//! EIP-7702 authorizations of set-code transactions.
+
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Half of the secp256k1 curve order. Authorizations with a larger `s` are
+/// invalid.
+pub(crate) const SECP256K1N_HALF: U256 = U256([
+	0xdfe9_2f46_681b_20a0,
+	0x5d57_6e73_57a4_501d,
+	0xffff_ffff_ffff_ffff,
+	0x7fff_ffff_ffff_ffff,
+]);
+
+/// Recovers the authority that signed an authorization, returning `None` if
+/// the signature is invalid. See [`StackExecutor::set_authority_recovery`].
+///
+/// [`StackExecutor::set_authority_recovery`]: super::StackExecutor::set_authority_recovery
+pub type AuthorityRecovery = fn(&Authorization) -> Option<H160>;
+
+/// Signed authorization tuple of an EIP-7702 set-code transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Authorization {
+	/// Chain the authorization is valid on, or zero for any chain.
+	pub chain_id: U256,
+	/// Address the authority delegates to. The zero address clears the
+	/// delegation.
+	pub address: H160,
+	/// Current nonce of the authority.
+	pub nonce: u64,
+	/// Parity of the y coordinate of the signature.
+	pub y_parity: bool,
+	/// Signature `r` value.
+	pub r: H256,
+	/// Signature `s` value.
+	pub s: H256,
+}
+
+impl Authorization {
+	/// Magic byte prefixed to the signed message.
+	pub const MAGIC: u8 = 0x05;
+
+	/// Hash signed by the authority, `keccak256(0x05 || rlp([chain_id, address, nonce]))`.
+	pub fn signing_hash(&self) -> H256 {
+		let mut stream = rlp::RlpStream::new_list(3);
+		stream.append(&self.chain_id);
+		stream.append(&self.address);
+		stream.append(&self.nonce);
+
+		let mut hasher = Keccak256::new();
+		hasher.update([Self::MAGIC]);
+		hasher.update(stream.out());
+		H256::from_slice(hasher.finalize().as_slice())
+	}
+
+	/// Recover the authority with secp256k1 public key recovery. This is the
+	/// default [`AuthorityRecovery`] when the `precompiles` feature is enabled.
+	#[cfg(feature = "precompiles")]
+	pub fn recover_authority(&self) -> Option<H160> {
+		super::precompile::recover_signer(self.signing_hash(), self.y_parity, self.r, self.s)
+	}
+}
+
+/// Default [`AuthorityRecovery`].
+#[cfg(feature = "precompiles")]
+pub(crate) fn default_authority_recovery(authorization: &Authorization) -> Option<H160> {
+	authorization.recover_authority()
+}
+
+/// Default [`AuthorityRecovery`]. Without the `precompiles` feature there is
+/// no secp256k1 implementation, so every authorization is skipped until a
+/// recovery is set on the executor.
+#[cfg(not(feature = "precompiles"))]
+pub(crate) fn default_authority_recovery(_authorization: &Authorization) -> Option<H160> {
+	None
+}
@@ -1,18 +1,29 @@
-use crate::backend::Backend;
+use crate::backend::{Backend, Basic};
+use crate::executor::stack::authorization::{
+	default_authority_recovery, AuthorityRecovery, Authorization, SECP256K1N_HALF,
+};
 use crate::executor::stack::precompile::{
 	IsPrecompileResult, PrecompileFailure, PrecompileHandle, PrecompileOutput, PrecompileSet,
 };
 use crate::executor::stack::tagged_runtime::{RuntimeKind, TaggedRuntime};
-use crate::gasometer::{self, Gasometer, StorageTarget};
+use crate::executor::stack::transaction::{
+	InvalidTransaction, Transaction, TransactionAction, TransactionError, TransactionOutcome,
+	TypedTransaction, VERSIONED_HASH_VERSION_KZG,
+};
+use crate::gasometer::{self, GasCost, Gasometer, StorageTarget};
 use crate::maybe_borrowed::MaybeBorrowed;
 use crate::{
-	Capture, Config, Context, CreateScheme, ExitError, ExitReason, Handler, Opcode, Runtime, Stack,
-	Transfer
+	is_eof, Capture, Config, ContainerKind, Context, CreateScheme, EofContainer, ExitError,
+	ExitReason, Handler, Opcode, Runtime, Stack, Transfer, HISTORY_SERVE_WINDOW,
+	HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS,
 };
 use alloc::collections::BTreeMap;
-use alloc::{collections::BTreeSet, rc::Rc, vec::Vec};
-use core::{cmp::min, convert::Infallible};
-use std::collections::HashMap;
+use alloc::{collections::BTreeSet, rc::Rc, sync::Arc, vec::Vec};
+use core::{
+	cell::RefCell,
+	cmp::{max, min},
+	convert::Infallible,
+};
 use evm_core::ExitFatal;
 use evm_runtime::Resolve;
 use primitive_types::{H160, H256, U256};
@@ -213,9 +224,33 @@ pub trait StackState<'config>: Backend {
 	fn set_deleted(&mut self, address: H160);
 	fn set_code(&mut self, address: H160, code: Vec<u8>);
 	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError>;
+	fn withdraw(&mut self, address: H160, value: U256) -> Result<(), ExitError>;
+	fn deposit(&mut self, address: H160, value: U256);
 	fn reset_balance(&mut self, address: H160);
 	fn touch(&mut self, address: H160);
 
+	/// Whether the account at the address was created in the current
+	/// transaction. See EIP-6780.
+	/// Provide a default implementation tracking no creations, which is only
+	/// correct for hard forks without `Config::has_restricted_selfdestruct`.
+	fn created(&self, _address: H160) -> bool {
+		false
+	}
+
+	/// Record the account at the address as created in the current
+	/// transaction.
+	fn set_created(&mut self, _address: H160) {}
+
+	/// Get transient storage value of address at index. See EIP-1153.
+	/// Provide a default implementation without transient storage, which is
+	/// only correct for hard forks without `Config::has_transient_storage`.
+	fn transient_storage(&self, _address: H160, _key: H256) -> H256 {
+		H256::default()
+	}
+
+	/// Set transient storage value of address at index.
+	fn set_transient_storage(&mut self, _address: H160, _key: H256, _value: H256) {}
+
 	/// Fetch the code size of an address.
 	/// Provide a default implementation by fetching the code, but
 	/// can be customized to use a more performant approach that don't need to
@@ -259,21 +294,87 @@ pub trait StackState<'config>: Backend {
 	fn refund_external_cost(&mut self, _ref_time: Option<u64>, _proof_size: Option<u64>) {}
 }
 
+/// Piece of state read or written by an execution.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum StateKey {
+	/// Account balance.
+	Balance(H160),
+	/// Account nonce.
+	Nonce(H160),
+	/// Account code.
+	Code(H160),
+	/// Account code hash.
+	CodeHash(H160),
+	/// Whether the account exists, following `Config::empty_considered_exists`.
+	Exists(H160),
+	/// Storage slot.
+	Storage(H160, H256),
+	/// Whether the account is scheduled for deletion. A write of this key
+	/// affects every other key of the account.
+	Deleted(H160),
+}
+
+impl StateKey {
+	/// Address of the account the key belongs to.
+	pub fn address(&self) -> H160 {
+		match self {
+			StateKey::Balance(address)
+			| StateKey::Nonce(address)
+			| StateKey::Code(address)
+			| StateKey::CodeHash(address)
+			| StateKey::Exists(address)
+			| StateKey::Storage(address, _)
+			| StateKey::Deleted(address) => *address,
+		}
+	}
+}
+
+/// Value observed or written for a [`StateKey`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StateValue {
+	Balance(U256),
+	Nonce(U256),
+	Code(Vec<u8>),
+	CodeHash(H256),
+	Exists(bool),
+	Storage(H256),
+	Deleted(bool),
+}
 
 pub trait Simulatable {
-	fn record_read_key(&mut self, address: H160, key: H256, value: H256);
-	fn record_write_key(&mut self, address: H160, key: H256, value: H256);
+	fn record_read(&mut self, key: StateKey, value: StateValue);
+	fn record_write(&mut self, key: StateKey, value: StateValue);
+
+	fn record_read_key(&mut self, address: H160, key: H256, value: H256) {
+		self.record_read(StateKey::Storage(address, key), StateValue::Storage(value));
+	}
+
+	fn record_write_key(&mut self, address: H160, key: H256, value: H256) {
+		self.record_write(StateKey::Storage(address, key), StateValue::Storage(value));
+	}
 }
 
+/// Reads and writes of an execution.
+///
+/// Writes follow the substate stack of the executor: each open checkpoint
+/// journals the values its writes replaced, so that a reverted substate leaves
+/// no writes behind. Reads are kept regardless, as they still determined the
+/// outcome of the execution.
 #[derive(Default, Clone, Debug)]
 pub struct RwSet {
-	read_set: BTreeMap<H160, HashMap<H256, H256>>,
-	write_set: BTreeMap<H160, HashMap<H256, H256>>,
+	read_set: BTreeMap<StateKey, StateValue>,
+	write_set: BTreeMap<StateKey, StateValue>,
+	journal: Vec<Vec<(StateKey, Option<StateValue>)>>,
 }
 
 impl RwSet {
 	#[must_use]
-	pub fn destruct(self) -> (BTreeMap<H160, HashMap<H256, H256>>, BTreeMap<H160, HashMap<H256, H256>>) {
+	pub fn destruct(
+		self,
+	) -> (
+		BTreeMap<StateKey, StateValue>,
+		BTreeMap<StateKey, StateValue>,
+	) {
 		(self.read_set, self.write_set)
 	}
 
@@ -281,46 +382,191 @@ impl RwSet {
 		RwSet {
 			read_set: BTreeMap::new(),
 			write_set: BTreeMap::new(),
+			journal: Vec::new(),
+		}
+	}
+
+	/// Open a checkpoint, on entering a substate.
+	pub fn checkpoint(&mut self) {
+		self.journal.push(Vec::new());
+	}
+
+	/// Merge the writes of the last checkpoint into its parent, on a
+	/// successful exit of the substate.
+	pub fn commit(&mut self) {
+		if let Some(entries) = self.journal.pop() {
+			if let Some(parent) = self.journal.last_mut() {
+				parent.extend(entries);
+			}
+		}
+	}
+
+	/// Undo the writes of the last checkpoint, on a reverted or failed exit of
+	/// the substate.
+	pub fn revert(&mut self) {
+		if let Some(entries) = self.journal.pop() {
+			for (key, previous) in entries.into_iter().rev() {
+				match previous {
+					Some(value) => self.write_set.insert(key, value),
+					None => self.write_set.remove(&key),
+				};
+			}
 		}
 	}
 
-	pub fn writes(&self) -> &BTreeMap<H160, HashMap<H256, H256>> {
+	pub fn writes(&self) -> &BTreeMap<StateKey, StateValue> {
 		&self.write_set
 	}
 
-	pub fn reads(&self) -> &BTreeMap<H160, HashMap<H256, H256>> {
+	pub fn reads(&self) -> &BTreeMap<StateKey, StateValue> {
 		&self.read_set
 	}
+
+	/// Whether `key` is written, directly or through deletion of its account.
+	pub fn is_written(&self, key: &StateKey) -> bool {
+		self.write_set.contains_key(key)
+			|| self
+				.write_set
+				.contains_key(&StateKey::Deleted(key.address()))
+	}
+
+	/// Whether either set reads or writes a piece of state the other one
+	/// writes, in which case the two executions cannot be reordered.
+	pub fn conflicts_with(&self, other: &RwSet) -> bool {
+		self.read_set
+			.keys()
+			.chain(self.write_set.keys())
+			.any(|key| other.is_written(key))
+			|| other
+				.read_set
+				.keys()
+				.chain(other.write_set.keys())
+				.any(|key| self.is_written(key))
+	}
 }
 
 impl Simulatable for RwSet {
-	fn record_read_key(&mut self, address: H160, key: H256, value: H256) {
-		self.read_set
-			.entry(address)
-			.or_insert_with(HashMap::new)
-			.insert(key, value);
+	fn record_read(&mut self, key: StateKey, value: StateValue) {
+		self.read_set.insert(key, value);
 	}
 
-	fn record_write_key(&mut self, address: H160, key: H256, value: H256) {
-		self.write_set
-			.entry(address)
-			.or_insert_with(HashMap::new)
-			.insert(key, value);
+	fn record_write(&mut self, key: StateKey, value: StateValue) {
+		let previous = self.write_set.insert(key, value);
+		if let Some(entries) = self.journal.last_mut() {
+			entries.push((key, previous));
+		}
+	}
+}
+
+/// Read source and access recorder of a [`StackExecutor`].
+///
+/// Reads returning `None` are served by the executor state. Reads of state the
+/// current execution already wrote are always served by the executor state.
+pub trait StateAccess {
+	/// Read/write set accesses are recorded into, if any.
+	fn rw_set(&self) -> Option<&RefCell<RwSet>>;
+	/// Mutable access to the read/write set.
+	fn rw_set_mut(&mut self) -> Option<&mut RwSet>;
+
+	/// Value of a storage slot.
+	fn storage(&self, address: H160, index: H256) -> Result<Option<H256>, ExitError>;
+	/// Balance and nonce of an account.
+	fn basic(&self, address: H160) -> Result<Option<Basic>, ExitError>;
+	/// Code of an account.
+	fn code(&self, address: H160) -> Result<Option<Arc<Vec<u8>>>, ExitError>;
+	/// Whether an account exists, regardless of it being empty.
+	fn exists(&self, address: H160) -> Result<Option<bool>, ExitError>;
+}
+
+/// Reads everything from the executor state, optionally recording accesses
+/// when simulating.
+#[derive(Clone, Debug, Default)]
+pub struct DirectAccess {
+	rw_set: Option<RefCell<RwSet>>,
+}
+
+impl DirectAccess {
+	pub fn new(simulation: bool) -> Self {
+		Self {
+			rw_set: if simulation {
+				Some(RefCell::new(RwSet::new()))
+			} else {
+				None
+			},
+		}
+	}
+}
+
+impl StateAccess for DirectAccess {
+	#[inline]
+	fn rw_set(&self) -> Option<&RefCell<RwSet>> {
+		self.rw_set.as_ref()
+	}
+
+	#[inline]
+	fn rw_set_mut(&mut self) -> Option<&mut RwSet> {
+		self.rw_set.as_mut().map(RefCell::get_mut)
+	}
+
+	#[inline]
+	fn storage(&self, _address: H160, _index: H256) -> Result<Option<H256>, ExitError> {
+		Ok(None)
+	}
+
+	#[inline]
+	fn basic(&self, _address: H160) -> Result<Option<Basic>, ExitError> {
+		Ok(None)
+	}
+
+	#[inline]
+	fn code(&self, _address: H160) -> Result<Option<Arc<Vec<u8>>>, ExitError> {
+		Ok(None)
+	}
+
+	#[inline]
+	fn exists(&self, _address: H160) -> Result<Option<bool>, ExitError> {
+		Ok(None)
 	}
 }
 
-// #[cfg(not(feature = "mvcc"))]
+/// Environment of the transaction being executed by
+/// [`StackExecutor::transact`], which takes precedence over the one of the
+/// state.
+struct TransactionEnvironment {
+	origin: H160,
+	gas_price: U256,
+	blob_hashes: Vec<H256>,
+}
+
 /// Stack-based executor.
-pub struct StackExecutor<'config, 'precompiles, S, P> {
+///
+/// Plain and simulating execution use [`DirectAccess`], multi-version
+/// execution uses [`MultiversionAccess`].
+pub struct StackExecutor<'config, 'precompiles, S, P, A = DirectAccess> {
 	config: &'config Config,
 	state: S,
 	precompile_set: &'precompiles P,
-	rw_set: Option<RwSet>,
+	access: A,
+	authority_recovery: AuthorityRecovery,
+	transaction: Option<TransactionEnvironment>,
 }
 
-// #[cfg(not(feature = "mvcc"))]
 impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
-	StackExecutor<'config, 'precompiles, S, P>
+	StackExecutor<'config, 'precompiles, S, P, DirectAccess>
+{
+	/// Create a new stack-based executor with given precompiles.
+	pub fn new_with_precompiles(
+		state: S,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+		simulation: bool,
+	) -> Self {
+		Self::new_with_access(state, config, precompile_set, DirectAccess::new(simulation))
+	}
+}
+
+impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, A: StateAccess>
+	StackExecutor<'config, 'precompiles, S, P, A>
 {
 	/// Return a reference of the Config.
 	pub fn config(&self) -> &'config Config {
@@ -332,26 +578,31 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		self.precompile_set
 	}
 
-	/// Create a new stack-based executor with given precompiles.
-	pub fn new_with_precompiles(
+	/// Create a new stack-based executor with given precompiles and state
+	/// access strategy.
+	pub fn new_with_access(
 		state: S,
 		config: &'config Config,
 		precompile_set: &'precompiles P,
-		simulation: bool,
+		access: A,
 	) -> Self {
-		let mut rw_set = None;
-		if simulation {
-			rw_set = Some(RwSet::new());
-		}
-
 		Self {
 			config,
 			state,
 			precompile_set,
-			rw_set,
+			access,
+			authority_recovery: default_authority_recovery,
+			transaction: None,
 		}
 	}
 
+	/// Set how the authorities of EIP-7702 authorizations are recovered from
+	/// their signatures. Defaults to [`Authorization::recover_authority`] with
+	/// the `precompiles` feature.
+	pub fn set_authority_recovery(&mut self, authority_recovery: AuthorityRecovery) {
+		self.authority_recovery = authority_recovery;
+	}
+
 	pub fn state(&self) -> &S {
 		&self.state
 	}
@@ -360,10 +611,203 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		&mut self.state
 	}
 
+	pub fn access(&self) -> &A {
+		&self.access
+	}
+
 	pub fn rw_set(&mut self) -> Option<&mut RwSet> {
-		self.rw_set.as_mut()
+		self.access.rw_set_mut()
+	}
+
+	fn is_recording(&self) -> bool {
+		self.access.rw_set().is_some()
+	}
+
+	fn record_read(&self, key: StateKey, value: StateValue) {
+		if let Some(rw_set) = self.access.rw_set() {
+			rw_set.borrow_mut().record_read(key, value);
+		}
+	}
+
+	fn record_write(&self, key: StateKey, value: StateValue) {
+		if let Some(rw_set) = self.access.rw_set() {
+			rw_set.borrow_mut().record_write(key, value);
+		}
+	}
+
+	/// Whether the current execution wrote `key`, in which case the executor
+	/// state holds its latest value.
+	fn is_written(&self, key: &StateKey) -> bool {
+		match self.access.rw_set() {
+			Some(rw_set) => rw_set.borrow().is_written(key),
+			None => false,
+		}
+	}
+
+	/// Whether the current execution modified the account.
+	fn is_account_written(&self, address: H160) -> bool {
+		[
+			StateKey::Balance(address),
+			StateKey::Nonce(address),
+			StateKey::Code(address),
+			StateKey::Exists(address),
+		]
+		.iter()
+		.any(|key| self.is_written(key))
+	}
+
+	/// Read an account through the access strategy, unless the current
+	/// execution modified it already.
+	fn read_account<T>(
+		&self,
+		address: H160,
+		read: impl FnOnce(&A) -> Result<Option<T>, ExitError>,
+	) -> Result<Option<T>, ExitError> {
+		if self.is_account_written(address) {
+			return Ok(None);
+		}
+		read(&self.access)
+	}
+
+	fn basic(&self, address: H160) -> Result<Basic, ExitError> {
+		match self.read_account(address, |access| access.basic(address))? {
+			Some(basic) => Ok(basic),
+			None => Ok(self.state.basic(address)),
+		}
+	}
+
+	fn account_code(&self, address: H160) -> Result<Vec<u8>, ExitError> {
+		match self.read_account(address, |access| access.code(address))? {
+			Some(code) => Ok(code.as_ref().clone()),
+			None => Ok(self.state.code(address)),
+		}
+	}
+
+	fn is_empty(&self, address: H160) -> Result<bool, ExitError> {
+		let basic = self.read_account(address, |access| access.basic(address))?;
+		let code = self.read_account(address, |access| access.code(address))?;
+		if basic.is_none() && code.is_none() {
+			return Ok(self.state.is_empty(address));
+		}
+
+		let basic = basic.unwrap_or_else(|| self.state.basic(address));
+		let code_is_empty = match code {
+			Some(code) => code.is_empty(),
+			None => self.state.code_size(address) == U256::zero(),
+		};
+		Ok(basic.balance == U256::zero() && basic.nonce == U256::zero() && code_is_empty)
+	}
+
+	fn account_exists(&self, address: H160) -> Result<bool, ExitError> {
+		let exists = match self.read_account(address, |access| access.exists(address))? {
+			Some(exists) => exists,
+			None => self.state.exists(address),
+		};
+		if self.config.empty_considered_exists || !exists {
+			return Ok(exists);
+		}
+		Ok(!self.is_empty(address)?)
+	}
+
+	/// Transfer value, recording both balances in the read/write set.
+	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError> {
+		if !self.is_recording() {
+			return self.state.transfer(transfer);
+		}
+
+		let (source, target) = (transfer.source, transfer.target);
+		let target_existed = self.account_exists(target)?;
+		for address in [source, target] {
+			let balance = self.state.basic(address).balance;
+			self.record_read(StateKey::Balance(address), StateValue::Balance(balance));
+		}
+		self.state.transfer(transfer)?;
+		for address in [source, target] {
+			let balance = self.state.basic(address).balance;
+			self.record_write(StateKey::Balance(address), StateValue::Balance(balance));
+		}
+		if !target_existed && self.account_exists(target)? {
+			self.record_write(StateKey::Exists(target), StateValue::Exists(true));
+		}
+		Ok(())
+	}
+
+	/// Withdraw from a balance, e.g. to pay for gas, recording it in the
+	/// read/write set.
+	fn withdraw(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+		if self.is_recording() {
+			let balance = self.state.basic(address).balance;
+			self.record_read(StateKey::Balance(address), StateValue::Balance(balance));
+		}
+		self.state.withdraw(address, value)?;
+		if self.is_recording() {
+			let balance = self.state.basic(address).balance;
+			self.record_write(StateKey::Balance(address), StateValue::Balance(balance));
+		}
+		Ok(())
+	}
+
+	/// Deposit to a balance, e.g. a gas refund or fee, recording it in the
+	/// read/write set.
+	fn deposit(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+		if !self.is_recording() {
+			self.state.deposit(address, value);
+			return Ok(());
+		}
+
+		let existed = self.account_exists(address)?;
+		let balance = self.state.basic(address).balance;
+		self.record_read(StateKey::Balance(address), StateValue::Balance(balance));
+		self.state.deposit(address, value);
+		let balance = self.state.basic(address).balance;
+		self.record_write(StateKey::Balance(address), StateValue::Balance(balance));
+		if !existed && self.account_exists(address)? {
+			self.record_write(StateKey::Exists(address), StateValue::Exists(true));
+		}
+		Ok(())
+	}
+
+	/// Increment the nonce, recording it in the read/write set.
+	fn inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
+		if !self.is_recording() {
+			return self.state.inc_nonce(address);
+		}
+
+		let existed = self.account_exists(address)?;
+		let nonce = self.basic(address)?.nonce;
+		self.record_read(StateKey::Nonce(address), StateValue::Nonce(nonce));
+		self.state.inc_nonce(address)?;
+		let nonce = self.state.basic(address).nonce;
+		self.record_write(StateKey::Nonce(address), StateValue::Nonce(nonce));
+		if !existed {
+			self.record_write(StateKey::Exists(address), StateValue::Exists(true));
+		}
+		Ok(())
+	}
+
+	/// Read a storage slot through the access strategy, unless the current
+	/// execution wrote it already, recording it in the read/write set.
+	fn read_storage(&self, address: H160, index: H256) -> Result<H256, ExitError> {
+		let key = StateKey::Storage(address, index);
+		let value = if self.is_written(&key) {
+			None
+		} else {
+			self.access.storage(address, index)?
+		};
+		let value = value.unwrap_or_else(|| self.state.storage(address, index));
+		self.record_read(key, StateValue::Storage(value));
+		Ok(value)
 	}
 
+	/// Set the code of a created contract, recording it in the read/write set.
+	fn set_code(&mut self, address: H160, code: Vec<u8>) {
+		if self.is_recording() {
+			let code_hash = H256::from_slice(Keccak256::digest(&code).as_slice());
+			self.record_write(StateKey::CodeHash(address), StateValue::CodeHash(code_hash));
+			self.record_write(StateKey::Code(address), StateValue::Code(code.clone()));
+		}
+		self.state.set_code(address, code);
+	}
 
 	pub fn into_state(self) -> S {
 		self.state
@@ -371,11 +815,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 
 	/// Create a substate executor from the current executor.
 	pub fn enter_substate(&mut self, gas_limit: u64, is_static: bool) {
+		if let Some(rw_set) = self.rw_set() {
+			rw_set.checkpoint();
+		}
 		self.state.enter(gas_limit, is_static);
 	}
 
 	/// Exit a substate. Panic if it results an empty substate stack.
 	pub fn exit_substate(&mut self, kind: StackExitKind) -> Result<(), ExitError> {
+		if let Some(rw_set) = self.rw_set() {
+			match kind {
+				StackExitKind::Succeeded => rw_set.commit(),
+				StackExitKind::Reverted | StackExitKind::Failed => rw_set.revert(),
+			}
+		}
 		match kind {
 			StackExitKind::Succeeded => self.state.exit_commit(),
 			StackExitKind::Reverted => self.state.exit_revert(),
@@ -441,6 +894,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 						created_address,
 						reason,
 						runtime.inner.machine().return_value(),
+						runtime.inner.machine().eof().is_some(),
 					);
 					(reason, maybe_address, return_data)
 				}
@@ -522,7 +976,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			value,
 			init_code: &init_code,
 			gas_limit,
-			address: self.create_address(CreateScheme::Legacy { caller }),
+			address: self
+				.create_address(CreateScheme::Legacy { caller })
+				.unwrap_or_default(),
 		});
 
 		if let Some(limit) = self.config.max_initcode_size {
@@ -537,6 +993,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		}
 		self.initialize_with_access_list(access_list);
 
+		let eof = self.config.has_eof && is_eof(&init_code);
 		match self.create_inner(
 			caller,
 			CreateScheme::Legacy { caller },
@@ -544,6 +1001,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			init_code,
 			Some(gas_limit),
 			false,
+			eof,
 		) {
 			Capture::Exit((s, _, v)) => emit_exit!(s, v),
 			Capture::Trap(rt) => {
@@ -579,11 +1037,13 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			init_code: &init_code,
 			salt,
 			gas_limit,
-			address: self.create_address(CreateScheme::Create2 {
-				caller,
-				code_hash,
-				salt,
-			}),
+			address: self
+				.create_address(CreateScheme::Create2 {
+					caller,
+					code_hash,
+					salt,
+				})
+				.unwrap_or_default(),
 		});
 
 		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
@@ -602,6 +1062,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			init_code,
 			Some(gas_limit),
 			false,
+			false,
 		) {
 			Capture::Exit((s, _, v)) => emit_exit!(s, v),
 			Capture::Trap(rt) => {
@@ -627,6 +1088,31 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		data: Vec<u8>,
 		gas_limit: u64,
 		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitReason, Vec<u8>) {
+		self.transact_call_with_authorizations(
+			caller,
+			address,
+			value,
+			data,
+			gas_limit,
+			access_list,
+			Vec::new(),
+		)
+	}
+
+	/// Execute a `CALL` transaction like [`Self::transact_call`], with the
+	/// `authorization_list` of an EIP-7702 set-code transaction introduced in
+	/// Prague.
+	#[allow(clippy::too_many_arguments)]
+	pub fn transact_call_with_authorizations(
+		&mut self,
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+		authorization_list: Vec<Authorization>,
 	) -> (ExitReason, Vec<u8>) {
 		event!(TransactCall {
 			caller,
@@ -636,7 +1122,15 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			gas_limit,
 		});
 
-		let transaction_cost = gasometer::call_transaction_cost(&data, &access_list);
+		if !authorization_list.is_empty() && !self.config.has_authorization_list {
+			return emit_exit!(
+				ExitError::Other("authorization list is not supported".into()).into(),
+				Vec::new()
+			);
+		}
+
+		let transaction_cost =
+			gasometer::call_transaction_cost(&data, &access_list, authorization_list.len());
 		let gasometer = &mut self.state.metadata_mut().gasometer;
 		match gasometer.record_transaction(transaction_cost) {
 			Ok(()) => (),
@@ -661,10 +1155,22 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		if let Err(e) = self.record_external_operation(crate::ExternalOperation::AccountBasicRead) {
 			return (e.into(), Vec::new());
 		}
-		if let Err(e) = self.state.inc_nonce(caller) {
+		if let Err(e) = self.inc_nonce(caller) {
 			return (e.into(), Vec::new());
 		}
 
+		if self.config.has_authorization_list {
+			if let Err(e) = self.apply_authorization_list(authorization_list) {
+				return emit_exit!(e.into(), Vec::new());
+			}
+			// The delegation target of the callee is warm, like the callee.
+			match self.delegated_address(address) {
+				Ok(Some(delegate)) => self.state.metadata_mut().access_address(delegate),
+				Ok(None) => (),
+				Err(e) => return emit_exit!(e.into(), Vec::new()),
+			}
+		}
+
 		let context = Context {
 			caller,
 			address,
@@ -695,1317 +1201,399 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		}
 	}
 
-	/// Get used gas for the current executor, given the price.
-	pub fn used_gas(&self) -> u64 {
-		self.state.metadata().gasometer.total_used_gas()
-			- min(
-				self.state.metadata().gasometer.total_used_gas() / self.config.max_refund_quotient,
-				self.state.metadata().gasometer.refunded_gas() as u64,
-			)
+	/// Execute a system call to `address`, made from `SYSTEM_ADDRESS` at the
+	/// start of a block. Unlike transactions, it has no intrinsic gas, and
+	/// leaves the system address untouched.
+	pub fn system_call(
+		&mut self,
+		address: H160,
+		data: Vec<u8>,
+		gas_limit: u64,
+	) -> (ExitReason, Vec<u8>) {
+		let context = Context {
+			caller: SYSTEM_ADDRESS,
+			address,
+			apparent_value: U256::zero(),
+		};
+
+		match self.call_inner(
+			address,
+			None,
+			data,
+			Some(gas_limit),
+			false,
+			false,
+			false,
+			context,
+		) {
+			Capture::Exit((s, v)) => emit_exit!(s, v),
+			Capture::Trap(rt) => {
+				let mut cs = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
+				cs.push(rt.0);
+				let (s, _, v) = self.execute_with_call_stack(&mut cs);
+				emit_exit!(s, v)
+			}
+		}
 	}
 
-	/// Get fee needed for the current executor, given the price.
-	pub fn fee(&self, price: U256) -> U256 {
+	/// Execute a transaction, enforcing the Ethereum validity rules and paying
+	/// for its gas.
+	///
+	/// The sender pays for the gas limit at the effective gas price up front,
+	/// and gets the unused gas refunded. The coinbase receives the priority fee
+	/// of the used gas, and the base fee is burnt. The executor should be
+	/// created with the gas limit of the transaction.
+	///
+	/// ORIGIN, GASPRICE and BLOBHASH return the sender, the effective gas price
+	/// and the blob versioned hashes of the transaction.
+	///
+	/// On an error, whether the transaction is invalid or a state access
+	/// failed, e.g. on an estimate of a multi-version execution, the state
+	/// changes of the executor must be discarded.
+	pub fn transact(
+		&mut self,
+		transaction: Transaction,
+	) -> Result<TransactionOutcome, TransactionError> {
+		let (outcome, priority_fee) = self.transact_deferring_fee(transaction)?;
+		let coinbase = self.block_coinbase();
+		self.deposit(coinbase, priority_fee)?;
+		Ok(outcome)
+	}
+
+	/// Execute a transaction as [`Self::transact`] does, but return the priority
+	/// fee instead of crediting it to the coinbase, so that the coinbase is not
+	/// touched by every transaction of a parallel execution.
+	pub(crate) fn transact_deferring_fee(
+		&mut self,
+		transaction: Transaction,
+	) -> Result<(TransactionOutcome, U256), TransactionError> {
+		let base_fee = if self.config.has_base_fee {
+			self.state.block_base_fee_per_gas()
+		} else {
+			U256::zero()
+		};
+		self.validate_transaction(&transaction, base_fee)??;
+
+		// ORIGIN, GASPRICE and BLOBHASH answer for the transaction, not for
+		// the environment of the state.
+		let environment = TransactionEnvironment {
+			origin: transaction.caller,
+			gas_price: transaction.effective_gas_price(base_fee),
+			blob_hashes: transaction.blob_versioned_hashes.clone(),
+		};
+		let previous = self.transaction.replace(environment);
+		let outcome = self.execute_transaction(transaction, base_fee);
+		self.transaction = previous;
+		outcome
+	}
+
+	/// Charge and execute a valid transaction, returning its priority fee.
+	fn execute_transaction(
+		&mut self,
+		transaction: Transaction,
+		base_fee: U256,
+	) -> Result<(TransactionOutcome, U256), TransactionError> {
+		let caller = transaction.caller;
+		let gas_limit = transaction.gas_limit;
+		let effective_gas_price = transaction.effective_gas_price(base_fee);
+		// EIP-4844: blob gas is paid at the blob base fee, and burnt.
+		let blob_gas_used = transaction.blob_gas();
+		let blob_fee = U256::from(blob_gas_used) * self.state.block_blob_base_fee();
+		let fee = U256::from(gas_limit) * effective_gas_price + blob_fee;
+		self.withdraw(caller, fee)?;
+
+		let (exit_reason, output, contract_address) = match transaction.action {
+			TransactionAction::Call(address) => {
+				let (exit_reason, output) = self.transact_call_with_authorizations(
+					caller,
+					address,
+					transaction.value,
+					transaction.data,
+					gas_limit,
+					transaction.access_list,
+					transaction.authorization_list,
+				);
+				(exit_reason, output, None)
+			}
+			TransactionAction::Create => {
+				let address = self.create_address(CreateScheme::Legacy { caller })?;
+				let (exit_reason, output) = self.transact_create(
+					caller,
+					transaction.value,
+					transaction.data,
+					gas_limit,
+					transaction.access_list,
+				);
+				let address = if exit_reason.is_succeed() {
+					Some(address)
+				} else {
+					None
+				};
+				(exit_reason, output, address)
+			}
+		};
+
 		let used_gas = self.used_gas();
-		U256::from(used_gas).saturating_mul(price)
+		let refund = U256::from(gas_limit.saturating_sub(used_gas)) * effective_gas_price;
+		let priority_fee = U256::from(used_gas) * (effective_gas_price - base_fee);
+		self.deposit(caller, refund)?;
+
+		let outcome = TransactionOutcome {
+			exit_reason,
+			output,
+			used_gas,
+			effective_gas_price,
+			blob_gas_used,
+			contract_address,
+		};
+		Ok((outcome, priority_fee))
 	}
 
-	/// Get account nonce.
-	pub fn nonce(&self, address: H160) -> U256 {
-		self.state.basic(address).nonce
+	/// Execute a transaction of one of the EIP-2718 types, sent by `caller`.
+	/// Its signature is not checked, but it must be signed for the chain of
+	/// the block, and its type supported by the hard fork. See
+	/// [`Self::transact`].
+	pub fn transact_typed(
+		&mut self,
+		transaction: &TypedTransaction,
+		caller: H160,
+	) -> Result<TransactionOutcome, TransactionError> {
+		if !transaction.is_supported(self.config) {
+			return Err(InvalidTransaction::UnsupportedType(transaction.transaction_type()).into());
+		}
+		if let Some(chain_id) = transaction.chain_id() {
+			let expected = self.state.chain_id();
+			if U256::from(chain_id) != expected {
+				return Err(InvalidTransaction::ChainIdMismatch {
+					expected,
+					actual: chain_id,
+				}
+				.into());
+			}
+		}
+
+		self.transact(transaction.to_transaction(caller))
 	}
 
-	/// Get the create address from given scheme.
-	pub fn create_address(&self, scheme: CreateScheme) -> H160 {
-		match scheme {
-			CreateScheme::Create2 {
-				caller,
-				code_hash,
-				salt,
-			} => {
-				let mut hasher = Keccak256::new();
-				hasher.update([0xff]);
-				hasher.update(&caller[..]);
-				hasher.update(&salt[..]);
-				hasher.update(&code_hash[..]);
-				H256::from_slice(hasher.finalize().as_slice()).into()
-			}
-			CreateScheme::Legacy { caller } => {
-				let nonce = self.nonce(caller);
-				let mut stream = rlp::RlpStream::new_list(2);
-				stream.append(&caller);
-				stream.append(&nonce);
-				H256::from_slice(Keccak256::digest(&stream.out()).as_slice()).into()
-			}
-			CreateScheme::Fixed(naddress) => naddress,
-		}
-	}
-
-	pub fn initialize_with_access_list(&mut self, access_list: Vec<(H160, Vec<H256>)>) {
-		let addresses = access_list.iter().map(|a| a.0);
-		self.state.metadata_mut().access_addresses(addresses);
-
-		let storage_keys = access_list
-			.into_iter()
-			.flat_map(|(address, keys)| keys.into_iter().map(move |key| (address, key)));
-		self.state.metadata_mut().access_storages(storage_keys);
-	}
+	/// Check the validity rules of a transaction against the current state.
+	fn validate_transaction(
+		&self,
+		transaction: &Transaction,
+		base_fee: U256,
+	) -> Result<Result<(), InvalidTransaction>, ExitError> {
+		let caller = transaction.caller;
 
-	fn create_inner(
-		&mut self,
-		caller: H160,
-		scheme: CreateScheme,
-		value: U256,
-		init_code: Vec<u8>,
-		target_gas: Option<u64>,
-		take_l64: bool,
-	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), StackExecutorCreateInterrupt<'static>> {
-		macro_rules! try_or_fail {
-			( $e:expr ) => {
-				match $e {
-					Ok(v) => v,
-					Err(e) => return Capture::Exit((e.into(), None, Vec::new())),
-				}
-			};
+		let nonce = self.nonce(caller)?;
+		if transaction.nonce < nonce {
+			return Ok(Err(InvalidTransaction::NonceTooLow {
+				expected: nonce,
+				actual: transaction.nonce,
+			}));
 		}
-
-		fn l64(gas: u64) -> u64 {
-			gas - gas / 64
+		if transaction.nonce > nonce {
+			return Ok(Err(InvalidTransaction::NonceTooHigh {
+				expected: nonce,
+				actual: transaction.nonce,
+			}));
 		}
-
-		let address = self.create_address(scheme);
-
-		self.state.metadata_mut().access_address(caller);
-		self.state.metadata_mut().access_address(address);
-
-		event!(Create {
-			caller,
-			address,
-			scheme,
-			value,
-			init_code: &init_code,
-			target_gas
-		});
-
-		if let Some(depth) = self.state.metadata().depth {
-			if depth > self.config.call_stack_limit {
-				return Capture::Exit((ExitError::CallTooDeep.into(), None, Vec::new()));
-			}
+		// EIP-2681
+		if nonce >= U256::from(u64::MAX) {
+			return Ok(Err(InvalidTransaction::NonceOverflow));
 		}
 
-		if self.balance(caller) < value {
-			return Capture::Exit((ExitError::OutOfFund.into(), None, Vec::new()));
+		// EIP-3607, allowing EIP-7702 delegations.
+		let code = Handler::code(self, caller)?;
+		let is_delegated =
+			self.config.has_authorization_list && crate::delegated_address(&code).is_some();
+		if !code.is_empty() && !is_delegated {
+			return Ok(Err(InvalidTransaction::SenderNotEoa));
 		}
 
-		if let Err(e) = self.record_external_operation(crate::ExternalOperation::AccountBasicRead) {
-			return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
-		}
-		if let Err(e) = self.state.inc_nonce(caller) {
-			return Capture::Exit((e.into(), None, Vec::new()));
+		let block_gas_limit = self.state.block_gas_limit();
+		if U256::from(transaction.gas_limit) > block_gas_limit {
+			return Ok(Err(InvalidTransaction::GasLimitAboveBlockGasLimit {
+				gas_limit: transaction.gas_limit,
+				block_gas_limit,
+			}));
 		}
 
-		let after_gas = if take_l64 && self.config.call_l64_after_gas {
-			if self.config.estimate {
-				let initial_after_gas = self.state.metadata().gasometer.gas();
-				let diff = initial_after_gas - l64(initial_after_gas);
-				try_or_fail!(self.state.metadata_mut().gasometer.record_cost(diff));
-				self.state.metadata().gasometer.gas()
-			} else {
-				l64(self.state.metadata().gasometer.gas())
+		let transaction_cost = match transaction.action {
+			TransactionAction::Call(_) => {
+				if !transaction.authorization_list.is_empty() && !self.config.has_authorization_list
+				{
+					return Ok(Err(InvalidTransaction::UnsupportedAuthorizationList));
+				}
+				gasometer::call_transaction_cost(
+					&transaction.data,
+					&transaction.access_list,
+					transaction.authorization_list.len(),
+				)
+			}
+			TransactionAction::Create => {
+				if !transaction.authorization_list.is_empty() {
+					return Ok(Err(InvalidTransaction::UnsupportedAuthorizationList));
+				}
+				if let Some(max_size) = self.config.max_initcode_size {
+					if transaction.data.len() > max_size {
+						return Ok(Err(InvalidTransaction::InitCodeTooLarge {
+							size: transaction.data.len(),
+							max_size,
+						}));
+					}
+				}
+				gasometer::create_transaction_cost(&transaction.data, &transaction.access_list)
 			}
-		} else {
-			self.state.metadata().gasometer.gas()
 		};
-
-		let target_gas = target_gas.unwrap_or(after_gas);
-
-		let gas_limit = min(after_gas, target_gas);
-		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
-
-		self.enter_substate(gas_limit, false);
-
+		let mut gasometer = Gasometer::new(u64::MAX, self.config);
+		gasometer.record_transaction(transaction_cost)?;
+		let intrinsic_gas = max(gasometer.total_used_gas(), gasometer.floor_gas());
+		if transaction.gas_limit < intrinsic_gas {
+			return Ok(Err(InvalidTransaction::IntrinsicGasTooLow {
+				gas_limit: transaction.gas_limit,
+				intrinsic_gas,
+			}));
+		}
+
+		if let Some(max_priority_fee_per_gas) = transaction.max_priority_fee_per_gas {
+			if max_priority_fee_per_gas > transaction.gas_price {
+				return Ok(Err(InvalidTransaction::PriorityFeeAboveMaxFee {
+					max_priority_fee_per_gas,
+					max_fee_per_gas: transaction.gas_price,
+				}));
+			}
+		}
+		if transaction.gas_price < base_fee {
+			return Ok(Err(InvalidTransaction::GasPriceBelowBaseFee {
+				gas_price: transaction.gas_price,
+				base_fee,
+			}));
+		}
+
+		if let Some(max_fee_per_blob_gas) = transaction.max_fee_per_blob_gas {
+			if let Err(invalid) = self.validate_blobs(transaction, max_fee_per_blob_gas) {
+				return Ok(Err(invalid));
+			}
+		}
+
+		let blob_fee = U256::from(transaction.blob_gas())
+			.checked_mul(transaction.max_fee_per_blob_gas.unwrap_or_default());
+		let required = U256::from(transaction.gas_limit)
+			.checked_mul(transaction.gas_price)
+			.zip(blob_fee)
+			.and_then(|(fee, blob_fee)| fee.checked_add(blob_fee))
+			.and_then(|fee| fee.checked_add(transaction.value))
+			.unwrap_or(U256::MAX);
+		let balance = Handler::balance(self, caller)?;
+		if balance < required {
+			return Ok(Err(InvalidTransaction::InsufficientBalance {
+				required,
+				balance,
+			}));
+		}
+
+		Ok(Ok(()))
+	}
+
+	/// Check the blobs of an EIP-4844 transaction.
+	fn validate_blobs(
+		&self,
+		transaction: &Transaction,
+		max_fee_per_blob_gas: U256,
+	) -> Result<(), InvalidTransaction> {
+		if transaction.action == TransactionAction::Create {
+			return Err(InvalidTransaction::BlobContractCreation);
+		}
+
+		let count = transaction.blob_versioned_hashes.len();
+		if count == 0 {
+			return Err(InvalidTransaction::NoBlobs);
+		}
+		if count > self.config.max_blobs_per_block {
+			return Err(InvalidTransaction::TooManyBlobs {
+				count,
+				max_count: self.config.max_blobs_per_block,
+			});
+		}
+		if let Some(hash) = transaction
+			.blob_versioned_hashes
+			.iter()
+			.find(|hash| hash[0] != VERSIONED_HASH_VERSION_KZG)
 		{
-			if let Err(e) =
-				self.record_external_operation(crate::ExternalOperation::AddressCodeRead(address))
-			{
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
-			}
-			let code_size = self.code_size(address);
-			if code_size != U256::zero() {
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
-			}
-
-			if self.nonce(address) > U256::zero() {
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
-			}
-
-			self.state.reset_storage(address);
-		}
-
-		let context = Context {
-			address,
-			caller,
-			apparent_value: value,
-		};
-		let transfer = Transfer {
-			source: caller,
-			target: address,
-			value,
-		};
-		match self.state.transfer(transfer) {
-			Ok(()) => (),
-			Err(e) => {
-				let _ = self.exit_substate(StackExitKind::Reverted);
-				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
-			}
+			return Err(InvalidTransaction::InvalidBlobVersionedHash(*hash));
 		}
 
-		if self.config.create_increase_nonce {
-			if let Err(e) =
-				self.record_external_operation(crate::ExternalOperation::AccountBasicRead)
-			{
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
-			}
-			if let Err(e) = self.state.inc_nonce(address) {
-				return Capture::Exit((e.into(), None, Vec::new()));
-			}
+		let blob_base_fee = self.state.block_blob_base_fee();
+		if max_fee_per_blob_gas < blob_base_fee {
+			return Err(InvalidTransaction::BlobGasPriceBelowBlobBaseFee {
+				max_fee_per_blob_gas,
+				blob_base_fee,
+			});
 		}
-
-		let runtime = Runtime::new(
-			Rc::new(init_code),
-			Rc::new(Vec::new()),
-			context,
-			self.config.stack_limit,
-			self.config.memory_limit,
-		);
-
-		Capture::Trap(StackExecutorCreateInterrupt(TaggedRuntime {
-			kind: RuntimeKind::Create(address),
-			inner: MaybeBorrowed::Owned(runtime),
-		}))
+		Ok(())
 	}
 
-	#[allow(clippy::too_many_arguments)]
-	fn call_inner(
+	/// Process an EIP-7702 authorization list, writing the delegation
+	/// designators of the authorities. Invalid authorizations are skipped.
+	fn apply_authorization_list(
 		&mut self,
-		code_address: H160,
-		transfer: Option<Transfer>,
-		input: Vec<u8>,
-		target_gas: Option<u64>,
-		is_static: bool,
-		take_l64: bool,
-		take_stipend: bool,
-		context: Context,
-	) -> Capture<(ExitReason, Vec<u8>), StackExecutorCallInterrupt<'static>> {
-		macro_rules! try_or_fail {
-			( $e:expr ) => {
-				match $e {
-					Ok(v) => v,
-					Err(e) => return Capture::Exit((e.into(), Vec::new())),
-				}
-			};
-		}
-
-		fn l64(gas: u64) -> u64 {
-			gas - gas / 64
-		}
-
-		event!(Call {
-			code_address,
-			transfer: &transfer,
-			input: &input,
-			target_gas,
-			is_static,
-			context: &context,
-		});
-
-		let after_gas = if take_l64 && self.config.call_l64_after_gas {
-			if self.config.estimate {
-				let initial_after_gas = self.state.metadata().gasometer.gas();
-				let diff = initial_after_gas - l64(initial_after_gas);
-				try_or_fail!(self.state.metadata_mut().gasometer.record_cost(diff));
-				self.state.metadata().gasometer.gas()
-			} else {
-				l64(self.state.metadata().gasometer.gas())
-			}
-		} else {
-			self.state.metadata().gasometer.gas()
-		};
-
-		let target_gas = target_gas.unwrap_or(after_gas);
-		let mut gas_limit = min(target_gas, after_gas);
-
-		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
-
-		if let Some(transfer) = transfer.as_ref() {
-			if take_stipend && transfer.value != U256::zero() {
-				gas_limit = gas_limit.saturating_add(self.config.call_stipend);
-			}
-		}
-
-		self.enter_substate(gas_limit, is_static);
-		self.state.touch(context.address);
-
-		if let Err(e) =
-			self.record_external_operation(crate::ExternalOperation::AddressCodeRead(code_address))
-		{
-			let _ = self.exit_substate(StackExitKind::Failed);
-			return Capture::Exit((ExitReason::Error(e), Vec::new()));
-		}
-		let code = self.code(code_address);
-		if let Some(depth) = self.state.metadata().depth {
-			if depth > self.config.call_stack_limit {
-				let _ = self.exit_substate(StackExitKind::Reverted);
-				return Capture::Exit((ExitError::CallTooDeep.into(), Vec::new()));
-			}
-		}
-
-		if let Some(transfer) = transfer {
-			if let Err(e) =
-				self.record_external_operation(crate::ExternalOperation::AccountBasicRead)
-			{
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitReason::Error(e), Vec::new()));
-			}
-			match self.state.transfer(transfer) {
-				Ok(()) => (),
-				Err(e) => {
-					let _ = self.exit_substate(StackExitKind::Reverted);
-					return Capture::Exit((ExitReason::Error(e), Vec::new()));
-				}
-			}
-		}
-
-		// At this point, the state has been modified in enter_substate to
-		// reflect both the is_static parameter of this call and the is_static
-		// of the caller context.
-		let precompile_is_static = self.state.metadata().is_static();
-		if let Some(result) = self.precompile_set.execute(&mut StackExecutorHandle {
-			executor: self,
-			code_address,
-			input: &input,
-			gas_limit: Some(gas_limit),
-			context: &context,
-			is_static: precompile_is_static,
-		}) {
-			return match result {
-				Ok(PrecompileOutput {
-					exit_status,
-					output,
-				}) => {
-					let _ = self.exit_substate(StackExitKind::Succeeded);
-					Capture::Exit((ExitReason::Succeed(exit_status), output))
-				}
-				Err(PrecompileFailure::Error { exit_status }) => {
-					let _ = self.exit_substate(StackExitKind::Failed);
-					Capture::Exit((ExitReason::Error(exit_status), Vec::new()))
-				}
-				Err(PrecompileFailure::Revert {
-					exit_status,
-					output,
-				}) => {
-					let _ = self.exit_substate(StackExitKind::Reverted);
-					Capture::Exit((ExitReason::Revert(exit_status), output))
-				}
-				Err(PrecompileFailure::Fatal { exit_status }) => {
-					self.state.metadata_mut().gasometer.fail();
-					let _ = self.exit_substate(StackExitKind::Failed);
-					Capture::Exit((ExitReason::Fatal(exit_status), Vec::new()))
-				}
-			};
-		}
-
-		let runtime = Runtime::new(
-			Rc::new(code),
-			Rc::new(input),
-			context,
-			self.config.stack_limit,
-			self.config.memory_limit,
-		);
-
-		Capture::Trap(StackExecutorCallInterrupt(TaggedRuntime {
-			kind: RuntimeKind::Call(code_address),
-			inner: MaybeBorrowed::Owned(runtime),
-		}))
-	}
-
-	fn cleanup_for_create(
-		&mut self,
-		created_address: H160,
-		reason: ExitReason,
-		return_data: Vec<u8>,
-	) -> (ExitReason, Option<H160>, Vec<u8>) {
-		fn check_first_byte(config: &Config, code: &[u8]) -> Result<(), ExitError> {
-			if config.disallow_executable_format && Some(&Opcode::EOFMAGIC.as_u8()) == code.first()
-			{
-				return Err(ExitError::InvalidCode(Opcode::EOFMAGIC));
-			}
-			Ok(())
-		}
-
-		log::debug!(target: "evm", "Create execution using address {}: {:?}", created_address, reason);
-
-		match reason {
-			ExitReason::Succeed(s) => {
-				let out = return_data;
-				let address = created_address;
-				// As of EIP-3541 code starting with 0xef cannot be deployed
-				if let Err(e) = check_first_byte(self.config, &out) {
-					self.state.metadata_mut().gasometer.fail();
-					let _ = self.exit_substate(StackExitKind::Failed);
-					return (e.into(), None, Vec::new());
-				}
-
-				if let Some(limit) = self.config.create_contract_limit {
-					if out.len() > limit {
-						self.state.metadata_mut().gasometer.fail();
-						let _ = self.exit_substate(StackExitKind::Failed);
-						return (ExitError::CreateContractLimit.into(), None, Vec::new());
-					}
-				}
-
-				match self
-					.state
-					.metadata_mut()
-					.gasometer
-					.record_deposit(out.len())
-				{
-					Ok(()) => {
-						let exit_result = self.exit_substate(StackExitKind::Succeeded);
-
-						if let Err(e) =
-							self.record_external_operation(crate::ExternalOperation::Write)
-						{
-							return (e.into(), None, Vec::new());
-						}
-						self.state.set_code(address, out);
-						if let Err(e) = exit_result {
-							return (e.into(), None, Vec::new());
-						}
-						(ExitReason::Succeed(s), Some(address), Vec::new())
-					}
-					Err(e) => {
-						let _ = self.exit_substate(StackExitKind::Failed);
-						(ExitReason::Error(e), None, Vec::new())
-					}
-				}
-			}
-			ExitReason::Error(e) => {
-				self.state.metadata_mut().gasometer.fail();
-				let _ = self.exit_substate(StackExitKind::Failed);
-				(ExitReason::Error(e), None, Vec::new())
-			}
-			ExitReason::Revert(e) => {
-				let _ = self.exit_substate(StackExitKind::Reverted);
-				(ExitReason::Revert(e), None, return_data)
-			}
-			ExitReason::Fatal(e) => {
-				self.state.metadata_mut().gasometer.fail();
-				let _ = self.exit_substate(StackExitKind::Failed);
-				(ExitReason::Fatal(e), None, Vec::new())
-			}
-		}
-	}
-
-	fn cleanup_for_call(
-		&mut self,
-		code_address: H160,
-		reason: &ExitReason,
-		return_data: Vec<u8>,
-	) -> Vec<u8> {
-		log::debug!(target: "evm", "Call execution using address {}: {:?}", code_address, reason);
-		match reason {
-			ExitReason::Succeed(_) => {
-				let _ = self.exit_substate(StackExitKind::Succeeded);
-				return_data
-			}
-			ExitReason::Error(_) => {
-				let _ = self.exit_substate(StackExitKind::Failed);
-				Vec::new()
-			}
-			ExitReason::Revert(_) => {
-				let _ = self.exit_substate(StackExitKind::Reverted);
-				return_data
-			}
-			ExitReason::Fatal(_) => {
-				self.state.metadata_mut().gasometer.fail();
-				let _ = self.exit_substate(StackExitKind::Failed);
-				Vec::new()
-			}
-		}
-	}
-}
-
-pub struct StackExecutorCallInterrupt<'borrow>(TaggedRuntime<'borrow>);
-pub struct StackExecutorCreateInterrupt<'borrow>(TaggedRuntime<'borrow>);
-
-// #[cfg(not(feature="mvcc"))]
-impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
-	for StackExecutor<'config, 'precompiles, S, P>
-{
-	type CreateInterrupt = StackExecutorCreateInterrupt<'static>;
-	type CreateFeedback = Infallible;
-	type CallInterrupt = StackExecutorCallInterrupt<'static>;
-	type CallFeedback = Infallible;
-
-	fn balance(&self, address: H160) -> U256 {
-		self.state.basic(address).balance
-	}
-
-	fn code_size(&self, address: H160) -> U256 {
-		self.state.code_size(address)
-	}
-
-	fn code_hash(&self, address: H160) -> H256 {
-		if !self.exists(address) {
-			return H256::default();
-		}
-
-		self.state.code_hash(address)
-	}
-
-	fn code(&self, address: H160) -> Vec<u8> {
-		self.state.code(address)
-	}
-
-	fn storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError>  {
-		let value = self.state.storage(address, index);
-		if let Some(rw_set) = self.rw_set() {
-			Simulatable::record_read_key(rw_set, address, index, value);
-		};
-		Ok(value)
-	}
-
-	fn original_storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError>  {
-		let value = self.state
-			.original_storage(address, index)
-			.unwrap_or_default();
-		if let Some(rw_set) = self.rw_set() {
-			Simulatable::record_read_key(rw_set, address, index, value);
-		};
-		Ok(value)
-	}
-
-	fn exists(&self, address: H160) -> bool {
-		if self.config.empty_considered_exists {
-			self.state.exists(address)
-		} else {
-			self.state.exists(address) && !self.state.is_empty(address)
-		}
-	}
-
-	fn is_cold(&mut self, address: H160, maybe_index: Option<H256>) -> Result<bool, ExitError> {
-		Ok(match maybe_index {
-			None => {
-				let is_precompile = match self
-					.precompile_set
-					.is_precompile(address, self.state.metadata().gasometer.gas())
-				{
-					IsPrecompileResult::Answer {
-						is_precompile,
-						extra_cost,
-					} => {
-						self.state
-							.metadata_mut()
-							.gasometer
-							.record_cost(extra_cost)?;
-						is_precompile
-					}
-					IsPrecompileResult::OutOfGas => return Err(ExitError::OutOfGas),
-				};
-
-				!is_precompile && self.state.is_cold(address)
-			}
-			Some(index) => self.state.is_storage_cold(address, index),
-		})
-	}
-
-	fn gas_left(&self) -> U256 {
-		U256::from(self.state.metadata().gasometer.gas())
-	}
-
-	fn gas_price(&self) -> U256 {
-		self.state.gas_price()
-	}
-	fn origin(&self) -> H160 {
-		self.state.origin()
-	}
-	fn block_hash(&self, number: U256) -> H256 {
-		self.state.block_hash(number)
-	}
-	fn block_number(&self) -> U256 {
-		self.state.block_number()
-	}
-	fn block_coinbase(&self) -> H160 {
-		self.state.block_coinbase()
-	}
-	fn block_timestamp(&self) -> U256 {
-		self.state.block_timestamp()
-	}
-	fn block_difficulty(&self) -> U256 {
-		self.state.block_difficulty()
-	}
-	fn block_randomness(&self) -> Option<H256> {
-		self.state.block_randomness()
-	}
-	fn block_gas_limit(&self) -> U256 {
-		self.state.block_gas_limit()
-	}
-	fn block_base_fee_per_gas(&self) -> U256 {
-		self.state.block_base_fee_per_gas()
-	}
-	fn chain_id(&self) -> U256 {
-		self.state.chain_id()
-	}
-
-	fn deleted(&self, address: H160) -> bool {
-		self.state.deleted(address)
-	}
-
-	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
-		self.state.set_storage(address, index, value);
-		if let Some(rw_set) = self.rw_set() {
-			Simulatable::record_write_key(rw_set, address, index, value);
-		};
-		Ok(())
-	}
-
-	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
-		self.state.log(address, topics, data);
-		Ok(())
-	}
-
-	fn mark_delete(&mut self, address: H160, target: H160) -> Result<(), ExitError> {
-		let balance = self.balance(address);
-
-		event!(Suicide {
-			target,
-			address,
-			balance,
-		});
-
-		self.state.transfer(Transfer {
-			source: address,
-			target,
-			value: balance,
-		})?;
-		self.state.reset_balance(address);
-		self.state.set_deleted(address);
-
-		Ok(())
-	}
-
-	#[cfg(not(feature = "tracing"))]
-	fn create(
-		&mut self,
-		caller: H160,
-		scheme: CreateScheme,
-		value: U256,
-		init_code: Vec<u8>,
-		target_gas: Option<u64>,
-	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-		if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
-			let reason: ExitReason = e.into();
-			emit_exit!(reason.clone());
-			return Capture::Exit((reason, None, Vec::new()));
-		}
-
-		self.create_inner(caller, scheme, value, init_code, target_gas, true)
-	}
-
-	#[cfg(feature = "tracing")]
-	fn create(
-		&mut self,
-		caller: H160,
-		scheme: CreateScheme,
-		value: U256,
-		init_code: Vec<u8>,
-		target_gas: Option<u64>,
-	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-		if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
-			let reason: ExitReason = e.into();
-			emit_exit!(reason.clone());
-			return Capture::Exit((reason, None, Vec::new()));
-		}
-
-		let capture = self.create_inner(caller, scheme, value, init_code, target_gas, true);
-
-		if let Capture::Exit((ref reason, _, ref return_value)) = capture {
-			emit_exit!(reason, return_value);
-		}
-
-		capture
-	}
-
-	#[cfg(not(feature = "tracing"))]
-	fn call(
-		&mut self,
-		code_address: H160,
-		transfer: Option<Transfer>,
-		input: Vec<u8>,
-		target_gas: Option<u64>,
-		is_static: bool,
-		context: Context,
-	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
-		self.call_inner(
-			code_address,
-			transfer,
-			input,
-			target_gas,
-			is_static,
-			true,
-			true,
-			context,
-		)
-	}
-
-	#[cfg(feature = "tracing")]
-	fn call(
-		&mut self,
-		code_address: H160,
-		transfer: Option<Transfer>,
-		input: Vec<u8>,
-		target_gas: Option<u64>,
-		is_static: bool,
-		context: Context,
-	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
-		let capture = self.call_inner(
-			code_address,
-			transfer,
-			input,
-			target_gas,
-			is_static,
-			true,
-			true,
-			context,
-		);
-
-		if let Capture::Exit((ref reason, ref return_value)) = capture {
-			emit_exit!(reason, return_value);
-		}
-
-		capture
-	}
-
-	#[inline]
-	fn pre_validate(
-		&mut self,
-		context: &Context,
-		opcode: Opcode,
-		stack: &Stack,
-	) -> Result<(), ExitError> {
-		// log::trace!(target: "evm", "Running opcode: {:?}, Pre gas-left: {:?}", opcode, gasometer.gas());
-
-		if let Some(cost) = gasometer::static_opcode_cost(opcode) {
-			self.state.metadata_mut().gasometer.record_cost(cost)?;
-		} else {
-			let is_static = self.state.metadata().is_static;
-			let (gas_cost, target, memory_cost) = gasometer::dynamic_opcode_cost(
-				context.address,
-				opcode,
-				stack,
-				is_static,
-				self.config,
-				self,
-			)?;
-
-			let gasometer = &mut self.state.metadata_mut().gasometer;
-			gasometer.record_dynamic_cost(gas_cost, memory_cost)?;
-
-			self.state
-				.record_external_dynamic_opcode_cost(opcode, gas_cost, target)?;
-
-			match target {
-				StorageTarget::Address(address) => {
-					self.state.metadata_mut().access_address(address)
-				}
-				StorageTarget::Slot(address, key) => {
-					self.state.metadata_mut().access_storage(address, key)
-				}
-				StorageTarget::None => (),
-			}
-		}
-
-		Ok(())
-	}
-
-	fn record_external_operation(&mut self, op: crate::ExternalOperation) -> Result<(), ExitError> {
-		self.state.record_external_operation(op)
-	}
-}
-
-// #[cfg(not(feature = "mvcc"))]
-struct StackExecutorHandle<'inner, 'config, 'precompiles, S, P> {
-	executor: &'inner mut StackExecutor<'config, 'precompiles, S, P>,
-	code_address: H160,
-	input: &'inner [u8],
-	gas_limit: Option<u64>,
-	context: &'inner Context,
-	is_static: bool,
-}
-
-// #[cfg(not(feature = "mvcc"))]
-impl<'inner, 'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
-	for StackExecutorHandle<'inner, 'config, 'precompiles, S, P>
-{
-	// Perform subcall in provided context.
-	/// Precompile specifies in which context the subcall is executed.
-	fn call(
-		&mut self,
-		code_address: H160,
-		transfer: Option<Transfer>,
-		input: Vec<u8>,
-		gas_limit: Option<u64>,
-		is_static: bool,
-		context: &Context,
-	) -> (ExitReason, Vec<u8>) {
-		// For normal calls the cost is recorded at opcode level.
-		// Since we don't go through opcodes we need manually record the call
-		// cost. Not doing so will make the code panic as recording the call stipend
-		// will do an underflow.
-		let target_is_cold = match self.executor.is_cold(code_address, None) {
-			Ok(x) => x,
-			Err(err) => return (ExitReason::Error(err), Vec::new()),
-		};
-
-		let target_exists = self.executor.exists(code_address);
-
-		let gas_cost = crate::gasometer::GasCost::Call {
-			value: transfer.clone().map(|x| x.value).unwrap_or_else(U256::zero),
-			gas: U256::from(gas_limit.unwrap_or(u64::MAX)),
-			target_is_cold,
-			target_exists,
-		};
-
-		// We record the length of the input.
-		let memory_cost = Some(crate::gasometer::MemoryCost {
-			offset: U256::zero(),
-			len: input.len().into(),
-		});
-
-		if let Err(error) = self
-			.executor
-			.state
-			.metadata_mut()
-			.gasometer
-			.record_dynamic_cost(gas_cost, memory_cost)
-		{
-			return (ExitReason::Error(error), Vec::new());
-		}
-
-		event!(PrecompileSubcall {
-			code_address,
-			transfer: &transfer,
-			input: &input,
-			target_gas: gas_limit,
-			is_static,
-			context
-		});
-
-		// Perform the subcall
-		match Handler::call(
-			self.executor,
-			code_address,
-			transfer,
-			input,
-			gas_limit,
-			is_static,
-			context.clone(),
-		) {
-			Capture::Exit((s, v)) => (s, v),
-			Capture::Trap(rt) => {
-				// Ideally this would pass the interrupt back to the executor so it could be
-				// handled like any other call, however the type signature of this function does
-				// not allow it. For now we'll make a recursive call instead of making a breaking
-				// change to the precompile API. But this means a custom precompile could still
-				// potentially cause a stack overflow if you're not careful.
-				let mut call_stack = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
-				call_stack.push(rt.0);
-				let (reason, _, return_data) =
-					self.executor.execute_with_call_stack(&mut call_stack);
-				emit_exit!(reason, return_data)
-			}
-		}
-	}
-
-	/// Record cost to the Runtime gasometer.
-	fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
-		self.executor
-			.state
-			.metadata_mut()
-			.gasometer
-			.record_cost(cost)
-	}
-
-	/// Record Substrate specific cost.
-	fn record_external_cost(
-		&mut self,
-		ref_time: Option<u64>,
-		proof_size: Option<u64>,
-	) -> Result<(), ExitError> {
-		self.executor
-			.state
-			.record_external_cost(ref_time, proof_size)
-	}
-
-	/// Refund Substrate specific cost.
-	fn refund_external_cost(&mut self, ref_time: Option<u64>, proof_size: Option<u64>) {
-		self.executor
-			.state
-			.refund_external_cost(ref_time, proof_size);
-	}
-
-	/// Retreive the remaining gas.
-	fn remaining_gas(&self) -> u64 {
-		self.executor.state.metadata().gasometer.gas()
-	}
-
-	/// Record a log.
-	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
-		Handler::log(self.executor, address, topics, data)
-	}
-
-	/// Retreive the code address (what is the address of the precompile being called).
-	fn code_address(&self) -> H160 {
-		self.code_address
-	}
-
-	/// Retreive the input data the precompile is called with.
-	fn input(&self) -> &[u8] {
-		self.input
-	}
-
-	/// Retreive the context in which the precompile is executed.
-	fn context(&self) -> &Context {
-		self.context
-	}
-
-	/// Is the precompile call is done statically.
-	fn is_static(&self) -> bool {
-		self.is_static
-	}
-
-	/// Retreive the gas limit of this call.
-	fn gas_limit(&self) -> Option<u64> {
-		self.gas_limit
-	}
-}
-
-// #[cfg(feature = "mvcc")]
-use std::sync::Arc;
-
-// #[cfg(feature = "mvcc")]
-pub trait MultiversionView {
-	type ReadDescriptor;
-	type ReadResult;
-	type TxnIdx;
-
-	fn take_reads(&self) -> Vec<Self::ReadDescriptor>;
-
-    /// Captures a read from the VM execution.
-    fn read(&self, address: &H160, key: &H256) -> anyhow::Result<Option<Arc<H256>>>;
-
-    /// Return txn_idx associated with the MVHashMapView
-    fn txn_idx(&self) -> Self::TxnIdx;
-
-    /// Return whether a read dependency was encountered during VM execution.
-    fn read_dependency(&self) -> bool;
-}
-
-// #[cfg(feature = "mvcc")]
-pub struct MultiversionStackExecutor<'config, 'precompiles, 'mv_view, S, P, M> {
-	config: &'config Config,
-	state: S,
-	precompile_set: &'precompiles P,
-	rw_set: RwSet,
-	multiversion_view: &'mv_view M 
-}
-
-// #[cfg(feature = "mvcc")]
-#[allow(dead_code)]
-impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet, M: MultiversionView>
-	MultiversionStackExecutor<'config, 'precompiles,'mv_view, S, P, M>
-{
-	/// Return a reference of the Config.
-	pub fn config(&self) -> &'config Config {
-		self.config
-	}
-
-	/// Return a reference to the precompile set.
-	pub fn precompiles(&self) -> &'precompiles P {
-		self.precompile_set
-	}
-
-	/// Create a new stack-based executor with given precompiles.
-	pub fn new_with_precompiles(
-		state: S,
-		config: &'config Config,
-		precompile_set: &'precompiles P,
-		multiversion_view: &'mv_view M,
-	) -> Self {
-		Self {
-			config,
-			state,
-			precompile_set,
-			rw_set: RwSet::new(),
-			multiversion_view
-		}
-	}
-
-	pub fn state(&self) -> &S {
-		&self.state
-	}
-
-	pub fn state_mut(&mut self) -> &mut S {
-		&mut self.state
-	}
-
-	pub fn rw_set(&mut self) -> &mut RwSet {
-		&mut self.rw_set
-	}
-
-
-	pub fn into_state(self) -> S {
-		self.state
-	}
-
-	/// Create a substate executor from the current executor.
-	pub fn enter_substate(&mut self, gas_limit: u64, is_static: bool) {
-		self.state.enter(gas_limit, is_static);
-	}
-
-	/// Exit a substate. Panic if it results an empty substate stack.
-	pub fn exit_substate(&mut self, kind: StackExitKind) -> Result<(), ExitError> {
-		match kind {
-			StackExitKind::Succeeded => self.state.exit_commit(),
-			StackExitKind::Reverted => self.state.exit_revert(),
-			StackExitKind::Failed => self.state.exit_discard(),
-		}
-	}
-
-	/// Execute the runtime until it returns.
-	pub fn execute(&mut self, runtime: &mut Runtime) -> ExitReason {
-		let mut call_stack = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
-		call_stack.push(TaggedRuntime {
-			kind: RuntimeKind::Execute,
-			inner: MaybeBorrowed::Borrowed(runtime),
-		});
-		let (reason, _, _) = self.execute_with_call_stack(&mut call_stack);
-		reason
-	}
-
-	/// Execute using Runtimes on the call_stack until it returns.
-	fn execute_with_call_stack(
-		&mut self,
-		call_stack: &mut Vec<TaggedRuntime<'_>>,
-	) -> (ExitReason, Option<H160>, Vec<u8>) {
-		// This `interrupt_runtime` is used to pass the runtime obtained from the
-		// `Capture::Trap` branch in the match below back to the top of the call stack.
-		// The reason we can't simply `push` the runtime directly onto the stack in the
-		// `Capture::Trap` branch is because the borrow-checker complains that the stack
-		// is already borrowed as long as we hold a pointer on the last element
-		// (i.e. the currently executing runtime).
-		let mut interrupt_runtime = None;
-		loop {
-			if let Some(rt) = interrupt_runtime.take() {
-				call_stack.push(rt);
-			}
-			let runtime = match call_stack.last_mut() {
-				Some(runtime) => runtime,
-				None => {
-					return (
-						ExitReason::Fatal(ExitFatal::UnhandledInterrupt),
-						None,
-						Vec::new(),
-					);
-				}
-			};
-			let reason = {
-				let inner_runtime = &mut runtime.inner;
-				match inner_runtime.run(self) {
-					Capture::Exit(reason) => reason,
-					Capture::Trap(Resolve::Call(rt, _)) => {
-						interrupt_runtime = Some(rt.0);
-						continue;
-					}
-					Capture::Trap(Resolve::Create(rt, _)) => {
-						interrupt_runtime = Some(rt.0);
-						continue;
-					}
-				}
-			};
-			let runtime_kind = runtime.kind;
-			let (reason, maybe_address, return_data) = match runtime_kind {
-				RuntimeKind::Create(created_address) => {
-					let (reason, maybe_address, return_data) = self.cleanup_for_create(
-						created_address,
-						reason,
-						runtime.inner.machine().return_value(),
-					);
-					(reason, maybe_address, return_data)
-				}
-				RuntimeKind::Call(code_address) => {
-					let return_data = self.cleanup_for_call(
-						code_address,
-						&reason,
-						runtime.inner.machine().return_value(),
-					);
-					(reason, None, return_data)
-				}
-				RuntimeKind::Execute => (reason, None, runtime.inner.machine().return_value()),
-			};
-			// We're done with that runtime now, so can pop it off the call stack
-			call_stack.pop();
-			// Now pass the results from that runtime on to the next one in the stack
-			let runtime = match call_stack.last_mut() {
-				Some(r) => r,
-				None => return (reason, None, return_data),
-			};
-			emit_exit!(&reason, &return_data);
-			let inner_runtime = &mut runtime.inner;
-			let maybe_error = match runtime_kind {
-				RuntimeKind::Create(_) => {
-					inner_runtime.finish_create(reason, maybe_address, return_data)
-				}
-				RuntimeKind::Call(_) => inner_runtime.finish_call(reason, return_data),
-				RuntimeKind::Execute => inner_runtime.finish_call(reason, return_data),
-			};
-			// Early exit if passing on the result caused an error
-			if let Err(e) = maybe_error {
-				return (e, None, Vec::new());
-			}
-		}
-	}
-
-	/// Get remaining gas.
-	pub fn gas(&self) -> u64 {
-		self.state.metadata().gasometer.gas()
-	}
-
-	fn record_create_transaction_cost(
-		&mut self,
-		init_code: &[u8],
-		access_list: &[(H160, Vec<H256>)],
+		authorization_list: Vec<Authorization>,
 	) -> Result<(), ExitError> {
-		let transaction_cost = gasometer::create_transaction_cost(init_code, access_list);
-		let gasometer = &mut self.state.metadata_mut().gasometer;
-		gasometer.record_transaction(transaction_cost)
-	}
-
-	fn maybe_record_init_code_cost(&mut self, init_code: &[u8]) -> Result<(), ExitError> {
-		if let Some(limit) = self.config.max_initcode_size {
-			// EIP-3860
-			if init_code.len() > limit {
-				self.state.metadata_mut().gasometer.fail();
-				return Err(ExitError::CreateContractLimit);
-			}
-			return self
-				.state
-				.metadata_mut()
-				.gasometer
-				.record_cost(gasometer::init_code_cost(init_code));
-		}
-		Ok(())
-	}
-
-	/// Execute a `CREATE` transaction.
-	pub fn transact_create(
-		&mut self,
-		caller: H160,
-		value: U256,
-		init_code: Vec<u8>,
-		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
-	) -> (ExitReason, Vec<u8>) {
-		event!(TransactCreate {
-			caller,
-			value,
-			init_code: &init_code,
-			gas_limit,
-			address: self.create_address(CreateScheme::Legacy { caller }),
-		});
-
-		if let Some(limit) = self.config.max_initcode_size {
-			if init_code.len() > limit {
-				self.state.metadata_mut().gasometer.fail();
-				return emit_exit!(ExitError::CreateContractLimit.into(), Vec::new());
-			}
-		}
-
-		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-			return emit_exit!(e.into(), Vec::new());
-		}
-		self.initialize_with_access_list(access_list);
-
-		match self.create_inner(
-			caller,
-			CreateScheme::Legacy { caller },
-			value,
-			init_code,
-			Some(gas_limit),
-			false,
-		) {
-			Capture::Exit((s, _, v)) => emit_exit!(s, v),
-			Capture::Trap(rt) => {
-				let mut cs = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
-				cs.push(rt.0);
-				let (s, _, v) = self.execute_with_call_stack(&mut cs);
-				emit_exit!(s, v)
-			}
-		}
-	}
-
-	/// Execute a `CREATE2` transaction.
-	pub fn transact_create2(
-		&mut self,
-		caller: H160,
-		value: U256,
-		init_code: Vec<u8>,
-		salt: H256,
-		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
-	) -> (ExitReason, Vec<u8>) {
-		if let Some(limit) = self.config.max_initcode_size {
-			if init_code.len() > limit {
-				self.state.metadata_mut().gasometer.fail();
-				return emit_exit!(ExitError::CreateContractLimit.into(), Vec::new());
-			}
-		}
-
-		let code_hash = H256::from_slice(Keccak256::digest(&init_code).as_slice());
-		event!(TransactCreate2 {
-			caller,
-			value,
-			init_code: &init_code,
-			salt,
-			gas_limit,
-			address: self.create_address(CreateScheme::Create2 {
-				caller,
-				code_hash,
-				salt,
-			}),
-		});
-
-		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-			return emit_exit!(e.into(), Vec::new());
-		}
-		self.initialize_with_access_list(access_list);
-
-		match self.create_inner(
-			caller,
-			CreateScheme::Create2 {
-				caller,
-				code_hash,
-				salt,
-			},
-			value,
-			init_code,
-			Some(gas_limit),
-			false,
-		) {
-			Capture::Exit((s, _, v)) => emit_exit!(s, v),
-			Capture::Trap(rt) => {
-				let mut cs = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
-				cs.push(rt.0);
-				let (s, _, v) = self.execute_with_call_stack(&mut cs);
-				emit_exit!(s, v)
-			}
-		}
-	}
-
-	/// Execute a `CALL` transaction with a given caller, address, value and
-	/// gas limit and data.
-	///
-	/// Takes in an additional `access_list` parameter for EIP-2930 which was
-	/// introduced in the Ethereum Berlin hard fork. If you do not wish to use
-	/// this functionality, just pass in an empty vector.
-	pub fn transact_call(
-		&mut self,
-		caller: H160,
-		address: H160,
-		value: U256,
-		data: Vec<u8>,
-		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>,
-	) -> (ExitReason, Vec<u8>) {
-		event!(TransactCall {
-			caller,
-			address,
-			value,
-			data: &data,
-			gas_limit,
-		});
-
-		let transaction_cost = gasometer::call_transaction_cost(&data, &access_list);
-		let gasometer = &mut self.state.metadata_mut().gasometer;
-		match gasometer.record_transaction(transaction_cost) {
-			Ok(()) => (),
-			Err(e) => return emit_exit!(e.into(), Vec::new()),
-		}
-
-		// Initialize initial addresses for EIP-2929
-		if self.config.increase_state_access_gas {
-			if self.config.warm_coinbase_address {
-				// Warm coinbase address for EIP-3651
-				let addresses = core::iter::once(caller)
-					.chain(core::iter::once(address))
-					.chain(core::iter::once(self.block_coinbase()));
-				self.state.metadata_mut().access_addresses(addresses);
-			} else {
-				let addresses = core::iter::once(caller).chain(core::iter::once(address));
-				self.state.metadata_mut().access_addresses(addresses);
-			}
-
-			self.initialize_with_access_list(access_list);
-		}
-		if let Err(e) = self.record_external_operation(crate::ExternalOperation::AccountBasicRead) {
-			return (e.into(), Vec::new());
-		}
-		if let Err(e) = self.state.inc_nonce(caller) {
-			return (e.into(), Vec::new());
-		}
-
-		let context = Context {
-			caller,
-			address,
-			apparent_value: value,
-		};
-
-		match self.call_inner(
-			address,
-			Some(Transfer {
-				source: caller,
-				target: address,
-				value,
-			}),
-			data,
-			Some(gas_limit),
-			false,
-			false,
-			false,
-			context,
-		) {
-			Capture::Exit((s, v)) => emit_exit!(s, v),
-			Capture::Trap(rt) => {
-				let mut cs = Vec::with_capacity(DEFAULT_CALL_STACK_CAPACITY);
-				cs.push(rt.0);
-				let (s, _, v) = self.execute_with_call_stack(&mut cs);
-				emit_exit!(s, v)
+		let chain_id = self.chain_id();
+		let mut refund = 0;
+		for authorization in authorization_list {
+			if !authorization.chain_id.is_zero() && authorization.chain_id != chain_id {
+				continue;
+			}
+			if authorization.nonce == u64::MAX
+				|| U256::from_big_endian(authorization.s.as_bytes()) > SECP256K1N_HALF
+			{
+				continue;
+			}
+			let authority = match (self.authority_recovery)(&authorization) {
+				Some(authority) => authority,
+				None => continue,
+			};
+			self.state.metadata_mut().access_address(authority);
+
+			self.record_external_operation(crate::ExternalOperation::AddressCodeRead(authority))?;
+			let code = self.code(authority)?;
+			if !code.is_empty() && crate::delegated_address(&code).is_none() {
+				continue;
 			}
+			self.record_external_operation(crate::ExternalOperation::AccountBasicRead)?;
+			if self.nonce(authority)? != U256::from(authorization.nonce) {
+				continue;
+			}
+
+			if self.account_exists(authority)? {
+				refund +=
+					self.config.gas_per_empty_account_cost - self.config.gas_per_auth_base_cost;
+			}
+			let code = if authorization.address.is_zero() {
+				Vec::new()
+			} else {
+				crate::delegation_designator(authorization.address)
+			};
+			self.record_external_operation(crate::ExternalOperation::Write)?;
+			self.set_code(authority, code);
+			self.inc_nonce(authority)?;
 		}
+
+		self.state
+			.metadata_mut()
+			.gasometer
+			.record_refund(refund as i64)
 	}
 
 	/// Get used gas for the current executor, given the price.
 	pub fn used_gas(&self) -> u64 {
-		self.state.metadata().gasometer.total_used_gas()
+		let used_gas = self.state.metadata().gasometer.total_used_gas()
 			- min(
 				self.state.metadata().gasometer.total_used_gas() / self.config.max_refund_quotient,
 				self.state.metadata().gasometer.refunded_gas() as u64,
-			)
+			);
+		// EIP-7623
+		max(used_gas, self.state.metadata().gasometer.floor_gas())
 	}
 
 	/// Get fee needed for the current executor, given the price.
@@ -2015,13 +1603,15 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	}
 
 	/// Get account nonce.
-	pub fn nonce(&self, address: H160) -> U256 {
-		self.state.basic(address).nonce
+	pub fn nonce(&self, address: H160) -> Result<U256, ExitError> {
+		let nonce = self.basic(address)?.nonce;
+		self.record_read(StateKey::Nonce(address), StateValue::Nonce(nonce));
+		Ok(nonce)
 	}
 
 	/// Get the create address from given scheme.
-	pub fn create_address(&self, scheme: CreateScheme) -> H160 {
-		match scheme {
+	pub fn create_address(&self, scheme: CreateScheme) -> Result<H160, ExitError> {
+		Ok(match scheme {
 			CreateScheme::Create2 {
 				caller,
 				code_hash,
@@ -2035,14 +1625,21 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 				H256::from_slice(hasher.finalize().as_slice()).into()
 			}
 			CreateScheme::Legacy { caller } => {
-				let nonce = self.nonce(caller);
+				let nonce = self.nonce(caller)?;
 				let mut stream = rlp::RlpStream::new_list(2);
 				stream.append(&caller);
 				stream.append(&nonce);
 				H256::from_slice(Keccak256::digest(&stream.out()).as_slice()).into()
 			}
+			CreateScheme::Eof { caller, salt } => {
+				let mut hasher = Keccak256::new();
+				hasher.update([0xff]);
+				hasher.update(H256::from(caller));
+				hasher.update(&salt[..]);
+				H256::from_slice(hasher.finalize().as_slice()).into()
+			}
 			CreateScheme::Fixed(naddress) => naddress,
-		}
+		})
 	}
 
 	pub fn initialize_with_access_list(&mut self, access_list: Vec<(H160, Vec<H256>)>) {
@@ -2055,14 +1652,16 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		self.state.metadata_mut().access_storages(storage_keys);
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	fn create_inner(
 		&mut self,
 		caller: H160,
 		scheme: CreateScheme,
 		value: U256,
-		init_code: Vec<u8>,
+		mut init_code: Vec<u8>,
 		target_gas: Option<u64>,
 		take_l64: bool,
+		eof: bool,
 	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), StackExecutorCreateInterrupt<'static>> {
 		macro_rules! try_or_fail {
 			( $e:expr ) => {
@@ -2077,7 +1676,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			gas - gas / 64
 		}
 
-		let address = self.create_address(scheme);
+		let address = try_or_fail!(self.create_address(scheme));
 
 		self.state.metadata_mut().access_address(caller);
 		self.state.metadata_mut().access_address(address);
@@ -2097,14 +1696,14 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			}
 		}
 
-		if self.balance(caller) < value {
+		if try_or_fail!(self.balance(caller)) < value {
 			return Capture::Exit((ExitError::OutOfFund.into(), None, Vec::new()));
 		}
 
 		if let Err(e) = self.record_external_operation(crate::ExternalOperation::AccountBasicRead) {
 			return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
 		}
-		if let Err(e) = self.state.inc_nonce(caller) {
+		if let Err(e) = self.inc_nonce(caller) {
 			return Capture::Exit((e.into(), None, Vec::new()));
 		}
 
@@ -2135,18 +1734,32 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
 			}
-			let code_size = self.code_size(address);
+			let code_size = match self.code_size(address) {
+				Ok(code_size) => code_size,
+				Err(e) => {
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return Capture::Exit((e.into(), None, Vec::new()));
+				}
+			};
 			if code_size != U256::zero() {
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
 			}
 
-			if self.nonce(address) > U256::zero() {
+			let nonce = match self.nonce(address) {
+				Ok(nonce) => nonce,
+				Err(e) => {
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return Capture::Exit((e.into(), None, Vec::new()));
+				}
+			};
+			if nonce > U256::zero() {
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
 			}
 
 			self.state.reset_storage(address);
+			self.state.set_created(address);
 		}
 
 		let context = Context {
@@ -2159,7 +1772,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			target: address,
 			value,
 		};
-		match self.state.transfer(transfer) {
+		match self.transfer(transfer) {
 			Ok(()) => (),
 			Err(e) => {
 				let _ = self.exit_substate(StackExitKind::Reverted);
@@ -2174,18 +1787,45 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
 			}
-			if let Err(e) = self.state.inc_nonce(address) {
+			if let Err(e) = self.inc_nonce(address) {
 				return Capture::Exit((e.into(), None, Vec::new()));
 			}
 		}
 
-		let runtime = Runtime::new(
-			Rc::new(init_code),
-			Rc::new(Vec::new()),
-			context,
-			self.config.stack_limit,
-			self.config.memory_limit,
-		);
+		let runtime = if eof {
+			// The initcontainer is followed by its calldata. See EIP-7698.
+			let container = match EofContainer::parse_prefix(&init_code).and_then(|container| {
+				EofContainer::validate(&init_code[..container.size()], ContainerKind::Initcode)
+			}) {
+				Ok(container) => container,
+				Err(_) => {
+					self.state.metadata_mut().gasometer.fail();
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return Capture::Exit((
+						ExitError::InvalidCode(Opcode::EOFMAGIC).into(),
+						None,
+						Vec::new(),
+					));
+				}
+			};
+			let input = init_code.split_off(container.size());
+			Runtime::new_eof(
+				Rc::new(init_code),
+				container,
+				Rc::new(input),
+				context,
+				self.config.stack_limit,
+				self.config.memory_limit,
+			)
+		} else {
+			Runtime::new(
+				Rc::new(init_code),
+				Rc::new(Vec::new()),
+				context,
+				self.config.stack_limit,
+				self.config.memory_limit,
+			)
+		};
 
 		Capture::Trap(StackExecutorCreateInterrupt(TaggedRuntime {
 			kind: RuntimeKind::Create(address),
@@ -2260,7 +1900,13 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			let _ = self.exit_substate(StackExitKind::Failed);
 			return Capture::Exit((ExitReason::Error(e), Vec::new()));
 		}
-		let code = self.code(code_address);
+		let code = match self.resolved_code(code_address) {
+			Ok(code) => code,
+			Err(e) => {
+				let _ = self.exit_substate(StackExitKind::Failed);
+				return Capture::Exit((ExitReason::Error(e), Vec::new()));
+			}
+		};
 		if let Some(depth) = self.state.metadata().depth {
 			if depth > self.config.call_stack_limit {
 				let _ = self.exit_substate(StackExitKind::Reverted);
@@ -2275,7 +1921,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitReason::Error(e), Vec::new()));
 			}
-			match self.state.transfer(transfer) {
+			match self.transfer(transfer) {
 				Ok(()) => (),
 				Err(e) => {
 					let _ = self.exit_substate(StackExitKind::Reverted);
@@ -2288,7 +1934,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		// reflect both the is_static parameter of this call and the is_static
 		// of the caller context.
 		let precompile_is_static = self.state.metadata().is_static();
-		if let Some(result) = self.precompile_set.execute(&mut MultiversionStackExecutorHandle {
+		if let Some(result) = self.precompile_set.execute(&mut StackExecutorHandle {
 			executor: self,
 			code_address,
 			input: &input,
@@ -2323,13 +1969,35 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			};
 		}
 
-		let runtime = Runtime::new(
-			Rc::new(code),
-			Rc::new(input),
-			context,
-			self.config.stack_limit,
-			self.config.memory_limit,
-		);
+		let runtime = if self.config.has_eof && is_eof(&code) {
+			let container = match EofContainer::validate(&code, ContainerKind::Runtime) {
+				Ok(container) => container,
+				Err(_) => {
+					self.state.metadata_mut().gasometer.fail();
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return Capture::Exit((
+						ExitError::InvalidCode(Opcode::EOFMAGIC).into(),
+						Vec::new(),
+					));
+				}
+			};
+			Runtime::new_eof(
+				Rc::new(code),
+				container,
+				Rc::new(input),
+				context,
+				self.config.stack_limit,
+				self.config.memory_limit,
+			)
+		} else {
+			Runtime::new(
+				Rc::new(code),
+				Rc::new(input),
+				context,
+				self.config.stack_limit,
+				self.config.memory_limit,
+			)
+		};
 
 		Capture::Trap(StackExecutorCallInterrupt(TaggedRuntime {
 			kind: RuntimeKind::Call(code_address),
@@ -2337,11 +2005,31 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		}))
 	}
 
+	/// Load the code run when calling `address`. With EIP-7702, the code of an
+	/// account holding a delegation designator is the code of the delegation
+	/// target, which is empty if the target is a precompile.
+	fn resolved_code(&mut self, address: H160) -> Result<Vec<u8>, ExitError> {
+		let code = self.code(address)?;
+		if !self.config.has_authorization_list {
+			return Ok(code);
+		}
+		match crate::delegated_address(&code) {
+			Some(delegate) => {
+				self.record_external_operation(crate::ExternalOperation::AddressCodeRead(
+					delegate,
+				))?;
+				self.code(delegate)
+			}
+			None => Ok(code),
+		}
+	}
+
 	fn cleanup_for_create(
 		&mut self,
 		created_address: H160,
 		reason: ExitReason,
 		return_data: Vec<u8>,
+		is_eof: bool,
 	) -> (ExitReason, Option<H160>, Vec<u8>) {
 		fn check_first_byte(config: &Config, code: &[u8]) -> Result<(), ExitError> {
 			if config.disallow_executable_format && Some(&Opcode::EOFMAGIC.as_u8()) == code.first()
@@ -2355,13 +2043,23 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 
 		match reason {
 			ExitReason::Succeed(s) => {
-				let out = return_data;
+				let mut out = return_data;
 				let address = created_address;
-				// As of EIP-3541 code starting with 0xef cannot be deployed
-				if let Err(e) = check_first_byte(self.config, &out) {
-					self.state.metadata_mut().gasometer.fail();
-					let _ = self.exit_substate(StackExitKind::Failed);
-					return (e.into(), None, Vec::new());
+				// Before EIP-2 the contract is created with empty code if there
+				// is not enough gas left for the code deposit.
+				if !self.config.err_on_code_deposit_out_of_gas
+					&& !self.state.metadata().gasometer.can_deposit(out.len())
+				{
+					out = Vec::new();
+				}
+				// As of EIP-3541 code starting with 0xef cannot be deployed,
+				// except for containers deployed by EOF initcode.
+				if !is_eof {
+					if let Err(e) = check_first_byte(self.config, &out) {
+						self.state.metadata_mut().gasometer.fail();
+						let _ = self.exit_substate(StackExitKind::Failed);
+						return (e.into(), None, Vec::new());
+					}
 				}
 
 				if let Some(limit) = self.config.create_contract_limit {
@@ -2386,7 +2084,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 						{
 							return (e.into(), None, Vec::new());
 						}
-						self.state.set_code(address, out);
+						self.set_code(address, out);
 						if let Err(e) = exit_result {
 							return (e.into(), None, Vec::new());
 						}
@@ -2444,79 +2142,85 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	}
 }
 
-// #[cfg(feature = "mvcc")]
-impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet, M: MultiversionView> Handler
-	for MultiversionStackExecutor<'config, 'precompiles, 'mv_view, S, P, M>
+pub struct StackExecutorCallInterrupt<'borrow>(TaggedRuntime<'borrow>);
+pub struct StackExecutorCreateInterrupt<'borrow>(TaggedRuntime<'borrow>);
+
+impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, A: StateAccess> Handler
+	for StackExecutor<'config, 'precompiles, S, P, A>
 {
 	type CreateInterrupt = StackExecutorCreateInterrupt<'static>;
 	type CreateFeedback = Infallible;
 	type CallInterrupt = StackExecutorCallInterrupt<'static>;
 	type CallFeedback = Infallible;
 
-	fn balance(&self, address: H160) -> U256 {
-		self.state.basic(address).balance
+	fn balance(&self, address: H160) -> Result<U256, ExitError> {
+		let balance = self.basic(address)?.balance;
+		self.record_read(StateKey::Balance(address), StateValue::Balance(balance));
+		Ok(balance)
 	}
 
-	fn code_size(&self, address: H160) -> U256 {
-		self.state.code_size(address)
+	fn code_size(&self, address: H160) -> Result<U256, ExitError> {
+		if self.is_recording() {
+			return Ok(U256::from(self.code(address)?.len()));
+		}
+
+		match self.read_account(address, |access| access.code(address))? {
+			Some(code) => Ok(U256::from(code.len())),
+			None => Ok(self.state.code_size(address)),
+		}
 	}
 
-	fn code_hash(&self, address: H160) -> H256 {
-		if !self.exists(address) {
-			return H256::default();
+	fn code_hash(&self, address: H160) -> Result<H256, ExitError> {
+		if !self.exists(address)? {
+			return Ok(H256::default());
 		}
 
-		self.state.code_hash(address)
+		let code_hash = match self.read_account(address, |access| access.code(address))? {
+			Some(code) => H256::from_slice(Keccak256::digest(code.as_slice()).as_slice()),
+			None => self.state.code_hash(address),
+		};
+		self.record_read(StateKey::CodeHash(address), StateValue::CodeHash(code_hash));
+		Ok(code_hash)
 	}
 
-	fn code(&self, address: H160) -> Vec<u8> {
-		self.state.code(address)
+	fn code(&self, address: H160) -> Result<Vec<u8>, ExitError> {
+		let code = self.account_code(address)?;
+		if self.is_recording() {
+			self.record_read(StateKey::Code(address), StateValue::Code(code.clone()));
+		}
+		Ok(code)
 	}
 
 	fn storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError> {
-		match self.multiversion_view.read(&address, &index) {
-			Ok(Some(value)) => {
-				let value = (*value).clone();
-				Simulatable::record_read_key(self.rw_set(), address, index, value);
-				Ok(value)
-			},
-			Ok(None) => {
-				let value = self.state.storage(address, index);
-				Simulatable::record_read_key(self.rw_set(), address, index, value);
-				Ok(value)
-			}
-			Err(_) => {
-				Err(ExitError::NotEstimatedYet)
-			}
-		}
+		self.read_storage(address, index)
 	}
 
 	fn original_storage(&mut self, address: H160, index: H256) -> Result<H256, ExitError> {
-		match self.multiversion_view.read(&address, &index) {
-			Ok(Some(value)) => {
-				let value = (*value).clone();
-				Simulatable::record_read_key(self.rw_set(), address, index, value);
-				Ok(value)
-			},
-			Ok(None) => {
-				let value = self.state
-					.original_storage(address, index)
-					.unwrap_or_default();
-				Simulatable::record_read_key(self.rw_set(), address, index, value);
-				Ok(value)
-			}
-			Err(_) => {
-				Err(ExitError::NotEstimatedYet)
-			}
-		}
+		// Values of the access strategy are the ones at the start of the
+		// execution, so they are the original ones regardless of later writes.
+		let value = match self.access.storage(address, index)? {
+			Some(value) => value,
+			None => self
+				.state
+				.original_storage(address, index)
+				.unwrap_or_default(),
+		};
+		if let Some(rw_set) = self.rw_set() {
+			Simulatable::record_read_key(rw_set, address, index, value);
+		};
+		Ok(value)
 	}
 
-	fn exists(&self, address: H160) -> bool {
-		if self.config.empty_considered_exists {
-			self.state.exists(address)
-		} else {
-			self.state.exists(address) && !self.state.is_empty(address)
-		}
+	// Transient storage is discarded at the end of the transaction, so it is
+	// never part of the read/write set.
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.state.transient_storage(address, index)
+	}
+
+	fn exists(&self, address: H160) -> Result<bool, ExitError> {
+		let exists = self.account_exists(address)?;
+		self.record_read(StateKey::Exists(address), StateValue::Exists(exists));
+		Ok(exists)
 	}
 
 	fn is_cold(&mut self, address: H160, maybe_index: Option<H256>) -> Result<bool, ExitError> {
@@ -2550,13 +2254,33 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	}
 
 	fn gas_price(&self) -> U256 {
-		self.state.gas_price()
+		match &self.transaction {
+			Some(transaction) => transaction.gas_price,
+			None => self.state.gas_price(),
+		}
 	}
 	fn origin(&self) -> H160 {
-		self.state.origin()
-	}
-	fn block_hash(&self, number: U256) -> H256 {
-		self.state.block_hash(number)
+		match &self.transaction {
+			Some(transaction) => transaction.origin,
+			None => self.state.origin(),
+		}
+	}
+	fn block_hash(&self, number: U256) -> Result<H256, ExitError> {
+		// EIP-2935: recent block hashes are also kept in the state of the
+		// history storage contract. BLOCKHASH still serves the last 256 only.
+		let current = self.state.block_number();
+		if self.config.has_block_hash_history
+			&& number < current
+			&& current - number <= U256::from(256)
+		{
+			let mut index = H256::default();
+			(number % U256::from(HISTORY_SERVE_WINDOW)).to_big_endian(&mut index[..]);
+			let hash = self.read_storage(HISTORY_STORAGE_ADDRESS, index)?;
+			if hash != H256::zero() {
+				return Ok(hash);
+			}
+		}
+		Ok(self.state.block_hash(number))
 	}
 	fn block_number(&self) -> U256 {
 		self.state.block_number()
@@ -2579,17 +2303,45 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.state.block_base_fee_per_gas()
 	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.state.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: U256) -> Option<H256> {
+		match &self.transaction {
+			Some(transaction) => {
+				if index >= U256::from(transaction.blob_hashes.len()) {
+					return None;
+				}
+				Some(transaction.blob_hashes[index.as_usize()])
+			}
+			None => self.state.blob_hash(index),
+		}
+	}
 	fn chain_id(&self) -> U256 {
 		self.state.chain_id()
 	}
 
 	fn deleted(&self, address: H160) -> bool {
-		self.state.deleted(address)
+		let deleted = self.state.deleted(address);
+		self.record_read(StateKey::Deleted(address), StateValue::Deleted(deleted));
+		deleted
 	}
 
 	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
 		self.state.set_storage(address, index, value);
-		Simulatable::record_write_key(self.rw_set(), address, index, value);
+		if let Some(rw_set) = self.rw_set() {
+			Simulatable::record_write_key(rw_set, address, index, value);
+		};
+		Ok(())
+	}
+
+	fn set_transient_storage(
+		&mut self,
+		address: H160,
+		index: H256,
+		value: H256,
+	) -> Result<(), ExitError> {
+		self.state.set_transient_storage(address, index, value);
 		Ok(())
 	}
 
@@ -2599,7 +2351,7 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	}
 
 	fn mark_delete(&mut self, address: H160, target: H160) -> Result<(), ExitError> {
-		let balance = self.balance(address);
+		let balance = self.balance(address)?;
 
 		event!(Suicide {
 			target,
@@ -2607,13 +2359,31 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 			balance,
 		});
 
-		self.state.transfer(Transfer {
+		// EIP-6780: only accounts created in the current transaction are
+		// deleted, otherwise SELFDESTRUCT just moves the balance.
+		if self.config.has_restricted_selfdestruct && !self.state.created(address) {
+			if address != target {
+				self.transfer(Transfer {
+					source: address,
+					target,
+					value: balance,
+				})?;
+			}
+			return Ok(());
+		}
+
+		self.transfer(Transfer {
 			source: address,
 			target,
 			value: balance,
 		})?;
 		self.state.reset_balance(address);
+		self.record_write(
+			StateKey::Balance(address),
+			StateValue::Balance(U256::zero()),
+		);
 		self.state.set_deleted(address);
+		self.record_write(StateKey::Deleted(address), StateValue::Deleted(true));
 
 		Ok(())
 	}
@@ -2627,13 +2397,17 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		init_code: Vec<u8>,
 		target_gas: Option<u64>,
 	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-		if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
-			let reason: ExitReason = e.into();
-			emit_exit!(reason.clone());
-			return Capture::Exit((reason, None, Vec::new()));
+		// EOF initcode is validated instead of metered. See EIP-7620.
+		let eof = matches!(scheme, CreateScheme::Eof { .. });
+		if !eof {
+			if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
+				let reason: ExitReason = e.into();
+				emit_exit!(reason.clone());
+				return Capture::Exit((reason, None, Vec::new()));
+			}
 		}
 
-		self.create_inner(caller, scheme, value, init_code, target_gas, true)
+		self.create_inner(caller, scheme, value, init_code, target_gas, true, eof)
 	}
 
 	#[cfg(feature = "tracing")]
@@ -2645,13 +2419,17 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		init_code: Vec<u8>,
 		target_gas: Option<u64>,
 	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-		if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
-			let reason: ExitReason = e.into();
-			emit_exit!(reason.clone());
-			return Capture::Exit((reason, None, Vec::new()));
+		// EOF initcode is validated instead of metered. See EIP-7620.
+		let eof = matches!(scheme, CreateScheme::Eof { .. });
+		if !eof {
+			if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
+				let reason: ExitReason = e.into();
+				emit_exit!(reason.clone());
+				return Capture::Exit((reason, None, Vec::new()));
+			}
 		}
 
-		let capture = self.create_inner(caller, scheme, value, init_code, target_gas, true);
+		let capture = self.create_inner(caller, scheme, value, init_code, target_gas, true, eof);
 
 		if let Capture::Exit((ref reason, _, ref return_value)) = capture {
 			emit_exit!(reason, return_value);
@@ -2710,6 +2488,56 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 		capture
 	}
 
+	#[cfg(not(feature = "tracing"))]
+	fn ext_call(
+		&mut self,
+		code_address: H160,
+		transfer: Option<Transfer>,
+		input: Vec<u8>,
+		target_gas: Option<u64>,
+		is_static: bool,
+		context: Context,
+	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
+		self.call_inner(
+			code_address,
+			transfer,
+			input,
+			target_gas,
+			is_static,
+			true,
+			false,
+			context,
+		)
+	}
+
+	#[cfg(feature = "tracing")]
+	fn ext_call(
+		&mut self,
+		code_address: H160,
+		transfer: Option<Transfer>,
+		input: Vec<u8>,
+		target_gas: Option<u64>,
+		is_static: bool,
+		context: Context,
+	) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
+		let capture = self.call_inner(
+			code_address,
+			transfer,
+			input,
+			target_gas,
+			is_static,
+			true,
+			false,
+			context,
+		);
+
+		if let Capture::Exit((ref reason, ref return_value)) = capture {
+			emit_exit!(reason, return_value);
+		}
+
+		capture
+	}
+
 	#[inline]
 	fn pre_validate(
 		&mut self,
@@ -2747,6 +2575,33 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 				}
 				StorageTarget::None => (),
 			}
+
+			// EIP-7702: calls to a delegating account also access its
+			// delegation target.
+			if let (
+				GasCost::Call {
+					delegation_is_cold: Some(_),
+					..
+				}
+				| GasCost::CallCode {
+					delegation_is_cold: Some(_),
+					..
+				}
+				| GasCost::DelegateCall {
+					delegation_is_cold: Some(_),
+					..
+				}
+				| GasCost::StaticCall {
+					delegation_is_cold: Some(_),
+					..
+				},
+				StorageTarget::Address(address),
+			) = (gas_cost, target)
+			{
+				if let Some(delegate) = self.delegated_address(address)? {
+					self.state.metadata_mut().access_address(delegate);
+				}
+			}
 		}
 
 		Ok(())
@@ -2757,9 +2612,8 @@ impl<'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet,
 	}
 }
 
-// #[cfg(feature = "mvcc")]
-struct MultiversionStackExecutorHandle<'inner, 'config, 'precompiles, 'mv_view, S, P, M> {
-	executor: &'inner mut MultiversionStackExecutor<'config, 'precompiles, 'mv_view, S, P, M>,
+struct StackExecutorHandle<'inner, 'config, 'precompiles, S, P, A> {
+	executor: &'inner mut StackExecutor<'config, 'precompiles, S, P, A>,
 	code_address: H160,
 	input: &'inner [u8],
 	gas_limit: Option<u64>,
@@ -2767,9 +2621,8 @@ struct MultiversionStackExecutorHandle<'inner, 'config, 'precompiles, 'mv_view,
 	is_static: bool,
 }
 
-// #[cfg(feature = "mvcc")]
-impl<'inner, 'config, 'precompiles, 'mv_view, S: StackState<'config>, P: PrecompileSet, M: MultiversionView> PrecompileHandle
-	for MultiversionStackExecutorHandle<'inner, 'config, 'precompiles, 'mv_view, S, P, M>
+impl<'inner, 'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, A: StateAccess>
+	PrecompileHandle for StackExecutorHandle<'inner, 'config, 'precompiles, S, P, A>
 {
 	// Perform subcall in provided context.
 	/// Precompile specifies in which context the subcall is executed.
@@ -2791,13 +2644,33 @@ impl<'inner, 'config, 'precompiles, 'mv_view, S: StackState<'config>, P: Precomp
 			Err(err) => return (ExitReason::Error(err), Vec::new()),
 		};
 
-		let target_exists = self.executor.exists(code_address);
+		let target_exists = match self.executor.exists(code_address) {
+			Ok(x) => x,
+			Err(err) => return (ExitReason::Error(err), Vec::new()),
+		};
+
+		let delegate = if self.executor.config.has_authorization_list {
+			match self.executor.delegated_address(code_address) {
+				Ok(x) => x,
+				Err(err) => return (ExitReason::Error(err), Vec::new()),
+			}
+		} else {
+			None
+		};
+		let delegation_is_cold = match delegate {
+			Some(delegate) => match self.executor.is_cold(delegate, None) {
+				Ok(x) => Some(x),
+				Err(err) => return (ExitReason::Error(err), Vec::new()),
+			},
+			None => None,
+		};
 
 		let gas_cost = crate::gasometer::GasCost::Call {
 			value: transfer.clone().map(|x| x.value).unwrap_or_else(U256::zero),
 			gas: U256::from(gas_limit.unwrap_or(u64::MAX)),
 			target_is_cold,
 			target_exists,
+			delegation_is_cold,
 		};
 
 		// We record the length of the input.
@@ -2912,4 +2785,162 @@ impl<'inner, 'config, 'precompiles, 'mv_view, S: StackState<'config>, P: Precomp
 	fn gas_limit(&self) -> Option<u64> {
 		self.gas_limit
 	}
+
+	/// Read a storage slot of the precompile address, charged like `SLOAD`.
+	fn storage(&mut self, index: H256) -> Result<H256, ExitError> {
+		let address = self.code_address;
+		let gas_cost = GasCost::SLoad {
+			target_is_cold: self.executor.is_cold(address, Some(index))?,
+		};
+		self.record_access_cost(Opcode::SLOAD, gas_cost, StorageTarget::Slot(address, index))?;
+
+		Handler::storage(self.executor, address, index)
+	}
+
+	/// Write a storage slot of the precompile address, charged and refunded
+	/// like `SSTORE`. Fails like `SSTORE` in a static call.
+	fn set_storage(&mut self, index: H256, value: H256) -> Result<(), ExitError> {
+		if self.is_static {
+			return Err(ExitError::InvalidCode(Opcode::SSTORE));
+		}
+
+		let address = self.code_address;
+		let gas_cost = GasCost::SStore {
+			original: Handler::original_storage(self.executor, address, index)?,
+			current: Handler::storage(self.executor, address, index)?,
+			new: value,
+			target_is_cold: self.executor.is_cold(address, Some(index))?,
+		};
+		self.record_access_cost(
+			Opcode::SSTORE,
+			gas_cost,
+			StorageTarget::Slot(address, index),
+		)?;
+
+		Handler::set_storage(self.executor, address, index, value)
+	}
+
+	/// Retreive the balance of an account, charged like `BALANCE`.
+	fn balance(&mut self, address: H160) -> Result<U256, ExitError> {
+		let gas_cost = GasCost::Balance {
+			target_is_cold: self.executor.is_cold(address, None)?,
+		};
+		self.record_access_cost(Opcode::BALANCE, gas_cost, StorageTarget::Address(address))?;
+
+		Handler::balance(self.executor, address)
+	}
+}
+
+impl<'inner, 'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, A: StateAccess>
+	StackExecutorHandle<'inner, 'config, 'precompiles, S, P, A>
+{
+	/// Record the cost of a state access done as `opcode`, warming `target`
+	/// up.
+	fn record_access_cost(
+		&mut self,
+		opcode: Opcode,
+		gas_cost: GasCost,
+		target: StorageTarget,
+	) -> Result<(), ExitError> {
+		let state = &mut self.executor.state;
+		state
+			.metadata_mut()
+			.gasometer
+			.record_dynamic_cost(gas_cost, None)?;
+		state.record_external_dynamic_opcode_cost(opcode, gas_cost, target)?;
+
+		match target {
+			StorageTarget::Address(address) => state.metadata_mut().access_address(address),
+			StorageTarget::Slot(address, index) => {
+				state.metadata_mut().access_storage(address, index)
+			}
+			StorageTarget::None => (),
+		}
+		Ok(())
+	}
+}
+
+pub trait MultiversionView {
+	type ReadDescriptor;
+	type ReadResult;
+	type TxnIdx;
+
+	fn take_reads(&self) -> Vec<Self::ReadDescriptor>;
+
+	/// Captures a read from the VM execution. Reads of a pending write of a
+	/// lower transaction fail with `ExitError::NotEstimatedYet`.
+	fn read(&self, address: &H160, key: &H256) -> Result<Option<Arc<H256>>, ExitError>;
+
+	/// Captures a read of the balance and nonce of an account. `None` leaves the
+	/// value to the executor state.
+	///
+	/// Balance and nonce changes are applied by the executor state on top of
+	/// the values of its own backend, so a view returning `Some` here should
+	/// also back that state.
+	fn read_basic(&self, address: &H160) -> Result<Option<Basic>, ExitError>;
+
+	/// Captures a read of the code of an account. `None` leaves the value to
+	/// the executor state.
+	fn read_code(&self, address: &H160) -> Result<Option<Arc<Vec<u8>>>, ExitError>;
+
+	/// Captures a read of whether an account exists, regardless of it being
+	/// empty. `None` leaves the value to the executor state.
+	fn read_exists(&self, address: &H160) -> Result<Option<bool>, ExitError>;
+
+	/// Return txn_idx associated with the MVHashMapView
+	fn txn_idx(&self) -> Self::TxnIdx;
+
+	/// Return whether a read dependency was encountered during VM execution.
+	fn read_dependency(&self) -> bool;
+}
+
+/// Reads state through a [`MultiversionView`] first, recording every access.
+pub struct MultiversionAccess<'mv_view, M> {
+	rw_set: RefCell<RwSet>,
+	multiversion_view: &'mv_view M,
+}
+
+impl<'mv_view, M: MultiversionView> MultiversionAccess<'mv_view, M> {
+	pub fn new(multiversion_view: &'mv_view M) -> Self {
+		Self {
+			rw_set: RefCell::new(RwSet::new()),
+			multiversion_view,
+		}
+	}
+
+	pub fn multiversion_view(&self) -> &'mv_view M {
+		self.multiversion_view
+	}
+}
+
+impl<'mv_view, M: MultiversionView> StateAccess for MultiversionAccess<'mv_view, M> {
+	fn rw_set(&self) -> Option<&RefCell<RwSet>> {
+		Some(&self.rw_set)
+	}
+
+	fn rw_set_mut(&mut self) -> Option<&mut RwSet> {
+		Some(self.rw_set.get_mut())
+	}
+
+	fn storage(&self, address: H160, index: H256) -> Result<Option<H256>, ExitError> {
+		self.multiversion_view
+			.read(&address, &index)
+			.map(|value| value.map(|value| *value))
+	}
+
+	fn basic(&self, address: H160) -> Result<Option<Basic>, ExitError> {
+		self.multiversion_view.read_basic(&address)
+	}
+
+	fn code(&self, address: H160) -> Result<Option<Arc<Vec<u8>>>, ExitError> {
+		self.multiversion_view.read_code(&address)
+	}
+
+	fn exists(&self, address: H160) -> Result<Option<bool>, ExitError> {
+		self.multiversion_view.read_exists(&address)
+	}
 }
+
+/// Stack-based executor reading through a [`MultiversionView`].
+pub type MultiversionStackExecutor<'config, 'precompiles, 'mv_view, S, P, M> =
+	StackExecutor<'config, 'precompiles, S, P, MultiversionAccess<'mv_view, M>>;
@@ -23,7 +23,9 @@ pub struct MemoryStackSubstate<'config> {
 	logs: Vec<Log>,
 	accounts: BTreeMap<H160, MemoryStackAccount>,
 	storages: BTreeMap<(H160, H256), H256>,
+	transient_storage: BTreeMap<(H160, H256), H256>,
 	deletes: BTreeSet<H160>,
+	creates: BTreeSet<H160>,
 }
 
 impl<'config> MemoryStackSubstate<'config> {
@@ -34,7 +36,9 @@ impl<'config> MemoryStackSubstate<'config> {
 			logs: Vec::new(),
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
+			transient_storage: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			creates: BTreeSet::new(),
 		}
 	}
 
@@ -57,12 +61,20 @@ impl<'config> MemoryStackSubstate<'config> {
 	/// Deconstruct the memory stack substate, return state to be applied. Panic if the
 	/// substate is not in the top-level substate.
 	#[must_use]
-	pub fn deconstruct<B: Backend>(
-		mut self,
-		backend: &B,
-	) -> (Vec<Apply>, Vec<Log>) {
+	pub fn deconstruct<B: Backend>(mut self, backend: &B) -> (Vec<Apply>, Vec<Log>) {
 		assert!(self.parent.is_none());
 
+		// Under EIP-6780 only accounts created in this transaction can be deleted.
+		if self
+			.metadata
+			.gasometer()
+			.config()
+			.has_restricted_selfdestruct
+		{
+			let creates = &self.creates;
+			self.deletes.retain(|address| creates.contains(address));
+		}
+
 		let mut applies = Vec::<Apply>::new();
 
 		let mut addresses = BTreeSet::new();
@@ -116,7 +128,9 @@ impl<'config> MemoryStackSubstate<'config> {
 			logs: Vec::new(),
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
+			transient_storage: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			creates: BTreeSet::new(),
 		};
 		mem::swap(&mut entering, self);
 
@@ -148,7 +162,9 @@ impl<'config> MemoryStackSubstate<'config> {
 
 		self.accounts.append(&mut exited.accounts);
 		self.storages.append(&mut exited.storages);
+		self.transient_storage.append(&mut exited.transient_storage);
 		self.deletes.append(&mut exited.deletes);
+		self.creates.append(&mut exited.creates);
 
 		Ok(())
 	}
@@ -229,6 +245,18 @@ impl<'config> MemoryStackSubstate<'config> {
 		None
 	}
 
+	pub fn known_transient_storage(&self, address: H160, key: H256) -> Option<H256> {
+		if let Some(value) = self.transient_storage.get(&(address, key)) {
+			return Some(*value);
+		}
+
+		if let Some(parent) = self.parent.as_ref() {
+			return parent.known_transient_storage(address, key);
+		}
+
+		None
+	}
+
 	pub fn known_original_storage(&self, address: H160) -> Option<H256> {
 		if let Some(account) = self.accounts.get(&address) {
 			if account.reset {
@@ -263,6 +291,18 @@ impl<'config> MemoryStackSubstate<'config> {
 		}
 	}
 
+	pub fn created(&self, address: H160) -> bool {
+		if self.creates.contains(&address) {
+			return true;
+		}
+
+		if let Some(parent) = self.parent.as_ref() {
+			return parent.created(address);
+		}
+
+		false
+	}
+
 	pub fn deleted(&self, address: H160) -> bool {
 		if self.deletes.contains(&address) {
 			return true;
@@ -311,6 +351,10 @@ impl<'config> MemoryStackSubstate<'config> {
 		self.storages.insert((address, key), value);
 	}
 
+	pub fn set_transient_storage(&mut self, address: H160, key: H256, value: H256) {
+		self.transient_storage.insert((address, key), value);
+	}
+
 	pub fn reset_storage<B: Backend>(&mut self, address: H160, backend: &B) {
 		let mut removing = Vec::new();
 
@@ -339,6 +383,10 @@ impl<'config> MemoryStackSubstate<'config> {
 		self.deletes.insert(address);
 	}
 
+	pub fn set_created(&mut self, address: H160) {
+		self.creates.insert(address);
+	}
+
 	pub fn set_code<B: Backend>(&mut self, address: H160, code: Vec<u8>, backend: &B) {
 		self.account_mut(address, backend).code = Some(code);
 	}
@@ -364,7 +412,6 @@ impl<'config> MemoryStackSubstate<'config> {
 		Ok(())
 	}
 
-	// Only needed for jsontests.
 	pub fn withdraw<B: Backend>(
 		&mut self,
 		address: H160,
@@ -380,7 +427,6 @@ impl<'config> MemoryStackSubstate<'config> {
 		Ok(())
 	}
 
-	// Only needed for jsontests.
 	pub fn deposit<B: Backend>(&mut self, address: H160, value: U256, backend: &B) {
 		let target = self.account_mut(address, backend);
 		target.basic.balance = target.basic.balance.saturating_add(value);
@@ -432,6 +478,12 @@ impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'conf
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.backend.block_base_fee_per_gas()
 	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.backend.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: U256) -> Option<H256> {
+		self.backend.blob_hash(index)
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.backend.chain_id()
@@ -508,6 +560,10 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.deleted(address)
 	}
 
+	fn created(&self, address: H160) -> bool {
+		self.substate.created(address)
+	}
+
 	fn is_cold(&self, address: H160) -> bool {
 		self.substate.is_cold(address)
 	}
@@ -528,6 +584,16 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.reset_storage(address, self.backend);
 	}
 
+	fn transient_storage(&self, address: H160, key: H256) -> H256 {
+		self.substate
+			.known_transient_storage(address, key)
+			.unwrap_or_default()
+	}
+
+	fn set_transient_storage(&mut self, address: H160, key: H256, value: H256) {
+		self.substate.set_transient_storage(address, key, value)
+	}
+
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) {
 		self.substate.log(address, topics, data);
 	}
@@ -536,6 +602,10 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.set_deleted(address)
 	}
 
+	fn set_created(&mut self, address: H160) {
+		self.substate.set_created(address)
+	}
+
 	fn set_code(&mut self, address: H160, code: Vec<u8>) {
 		self.substate.set_code(address, code, self.backend);
 	}
@@ -544,6 +614,14 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.transfer(transfer, self.backend)
 	}
 
+	fn withdraw(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+		self.substate.withdraw(address, value, self.backend)
+	}
+
+	fn deposit(&mut self, address: H160, value: U256) {
+		self.substate.deposit(address, value, self.backend)
+	}
+
 	fn reset_balance(&mut self, address: H160) {
 		self.substate.reset_balance(address, self.backend)
 	}
@@ -567,9 +645,7 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
 	}
 
 	#[must_use]
-	pub fn deconstruct(
-		self,
-	) -> (Vec<Apply>, Vec<Log>) {
+	pub fn deconstruct(self) -> (Vec<Apply>, Vec<Log>) {
 		self.substate.deconstruct(self.backend)
 	}
 
@@ -2,18 +2,41 @@
 //! A memory-based state is provided, but can replaced by a custom
 //! implementation, for exemple one interacting with a database.
 
+mod authorization;
 mod executor;
 mod memory;
+// The multi-version map is shared through `std` locks.
+#[cfg(all(feature = "mvcc", feature = "std"))]
+mod mvhashmap;
 mod precompile;
+mod receipt;
 mod tagged_runtime;
+mod transaction;
 
+pub use self::authorization::{AuthorityRecovery, Authorization};
 pub use self::executor::{
-	Accessed, StackExecutor, StackExitKind, StackState, StackSubstateMetadata, RwSet, Simulatable, MultiversionStackExecutor, MultiversionView
+	Accessed, DirectAccess, MultiversionAccess, MultiversionStackExecutor, MultiversionView, RwSet,
+	Simulatable, StackExecutor, StackExitKind, StackState, StackSubstateMetadata, StateAccess,
+	StateKey, StateValue,
 };
 pub use self::memory::{MemoryStackAccount, MemoryStackState, MemoryStackSubstate};
+#[cfg(all(feature = "mvcc", feature = "std"))]
+pub use self::mvhashmap::{
+	Incarnation, MVHashMap, MVHashMapError, MVHashMapView, ReadDescriptor, ReadOrigin, TxnIndex,
+	Version,
+};
 pub use self::precompile::{
-	IsPrecompileResult, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput,
-	PrecompileSet,
+	AddressRange, Chain, ClosurePrecompiles, DenyList, ForkGated, IsPrecompileResult,
+	PrecompileClosure, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput,
+	PrecompileResult, PrecompileSet,
+};
+#[cfg(feature = "bls12-381")]
+pub use self::precompile::{Bls12381Precompile, Bls12381Precompiles};
+#[cfg(feature = "precompiles")]
+pub use self::precompile::{P256Verify, StandardPrecompile, StandardPrecompiles, P256_VERIFY_COST};
+pub use self::receipt::{logs_bloom, receipts_root, Bloom, Receipt, ReceiptBuilder, ReceiptStatus};
+pub use self::transaction::{
+	EIP4844Transaction, InvalidTransaction, Transaction, TransactionAction, TransactionError,
+	TransactionOutcome, TypedTransaction, GAS_PER_BLOB,
 };
 pub use ethereum::Log;
-
@@ -0,0 +1,476 @@
+//! Multi-version hash map for optimistic parallel execution.
+//!
+//! Every key holds one entry per transaction that wrote it. Transaction `i`
+//! observes the entry of the highest transaction below `i`. Entries of
+//! transactions being re-executed are replaced by ESTIMATE markers, so that
+//! readers detect the dependency instead of reading a likely stale value.
+
+use crate::backend::Basic;
+use crate::executor::stack::executor::MultiversionView;
+use crate::ExitError;
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::cell::{Cell, RefCell};
+use core::hash::Hash;
+use primitive_types::{H160, H256};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+/// Index of a transaction in the block.
+pub type TxnIndex = usize;
+/// Number of times a transaction has been (re-)executed.
+pub type Incarnation = usize;
+/// A specific execution of a transaction.
+pub type Version = (TxnIndex, Incarnation);
+
+#[derive(Clone, Debug)]
+enum Entry<V> {
+	Write(Incarnation, Arc<V>),
+	/// Writes of the given incarnation, aborted and pending re-execution.
+	Estimate(Incarnation),
+}
+
+/// Entries of a single key, indexed by the writing transaction.
+type Entries<V> = Mutex<BTreeMap<TxnIndex, Entry<V>>>;
+
+/// Unsuccessful multi-version read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MVHashMapError {
+	/// No lower transaction wrote the key, the value comes from storage.
+	NotFound,
+	/// The highest lower write is an ESTIMATE of the given transaction, left
+	/// by the given incarnation.
+	Dependency(Version),
+}
+
+/// Where a recorded read got its value from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadOrigin {
+	/// Underlying storage.
+	Storage,
+	/// Write of a lower transaction.
+	Version(Version),
+}
+
+/// A read captured during execution, to be checked again on validation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReadDescriptor<K = (H160, H256)> {
+	pub key: K,
+	pub origin: ReadOrigin,
+}
+
+/// Concurrent multi-version map, keyed by `(address, storage index)` by default.
+pub struct MVHashMap<K = (H160, H256), V = H256> {
+	data: RwLock<HashMap<K, Entries<V>>>,
+}
+
+impl<K: Hash + Eq + Clone, V> Default for MVHashMap<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<K: Hash + Eq + Clone, V> MVHashMap<K, V> {
+	pub fn new() -> Self {
+		Self {
+			data: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Write `value` at `key` for the given version, replacing any previous
+	/// write or ESTIMATE of the same transaction.
+	pub fn write(&self, key: &K, version: Version, value: V) {
+		let (txn_idx, incarnation) = version;
+		self.insert(key, txn_idx, Entry::Write(incarnation, Arc::new(value)));
+	}
+
+	/// Replace the write of `txn_idx` at `key` by an ESTIMATE marker.
+	pub fn mark_estimate(&self, key: &K, txn_idx: TxnIndex) {
+		let data = self.data.read().unwrap();
+		if let Some(entries) = data.get(key) {
+			if let Some(entry) = entries.lock().unwrap().get_mut(&txn_idx) {
+				let incarnation = match entry {
+					Entry::Write(incarnation, _) | Entry::Estimate(incarnation) => *incarnation,
+				};
+				*entry = Entry::Estimate(incarnation);
+			}
+		}
+	}
+
+	/// Remove the write of `txn_idx` at `key`.
+	pub fn delete(&self, key: &K, txn_idx: TxnIndex) {
+		let data = self.data.read().unwrap();
+		if let Some(entries) = data.get(key) {
+			entries.lock().unwrap().remove(&txn_idx);
+		}
+	}
+
+	/// Read `key` as seen by transaction `txn_idx`.
+	pub fn read(&self, key: &K, txn_idx: TxnIndex) -> Result<(Version, Arc<V>), MVHashMapError> {
+		let data = self.data.read().unwrap();
+		let entries = data
+			.get(key)
+			.ok_or(MVHashMapError::NotFound)?
+			.lock()
+			.unwrap();
+
+		match entries.range(..txn_idx).next_back() {
+			None => Err(MVHashMapError::NotFound),
+			Some((idx, Entry::Estimate(incarnation))) => {
+				Err(MVHashMapError::Dependency((*idx, *incarnation)))
+			}
+			Some((idx, Entry::Write(incarnation, value))) => {
+				Ok(((*idx, *incarnation), value.clone()))
+			}
+		}
+	}
+
+	/// Whether every read would still observe the same origin for `txn_idx`.
+	pub fn validate_reads(&self, txn_idx: TxnIndex, reads: &[ReadDescriptor<K>]) -> bool {
+		reads
+			.iter()
+			.all(|read| match (self.read(&read.key, txn_idx), read.origin) {
+				(Err(MVHashMapError::NotFound), ReadOrigin::Storage) => true,
+				(Ok((version, _)), ReadOrigin::Version(expected)) => version == expected,
+				_ => false,
+			})
+	}
+
+	/// Highest write of every key, with the index of the transaction that wrote
+	/// it. Keys whose highest entry is an ESTIMATE are skipped.
+	pub fn latest_writes(&self) -> Vec<(K, TxnIndex, Arc<V>)> {
+		let data = self.data.read().unwrap();
+		data.iter()
+			.filter_map(
+				|(key, entries)| match entries.lock().unwrap().iter().next_back() {
+					Some((idx, Entry::Write(_, value))) => Some((key.clone(), *idx, value.clone())),
+					_ => None,
+				},
+			)
+			.collect()
+	}
+
+	fn insert(&self, key: &K, txn_idx: TxnIndex, entry: Entry<V>) {
+		{
+			let data = self.data.read().unwrap();
+			if let Some(entries) = data.get(key) {
+				entries.lock().unwrap().insert(txn_idx, entry);
+				return;
+			}
+		}
+
+		self.data
+			.write()
+			.unwrap()
+			.entry(key.clone())
+			.or_insert_with(|| Mutex::new(BTreeMap::new()))
+			.get_mut()
+			.unwrap()
+			.insert(txn_idx, entry);
+	}
+}
+
+impl MVHashMap {
+	/// View of the map for a single execution of transaction `txn_idx`.
+	pub fn view(&self, txn_idx: TxnIndex) -> MVHashMapView<'_> {
+		MVHashMapView {
+			map: self,
+			txn_idx,
+			reads: RefCell::new(Vec::new()),
+			dependency: Cell::new(None),
+		}
+	}
+}
+
+/// Storage view of one transaction execution over an [`MVHashMap`]. Records
+/// every read, and the first dependency on an ESTIMATE marker.
+pub struct MVHashMapView<'a> {
+	map: &'a MVHashMap,
+	txn_idx: TxnIndex,
+	reads: RefCell<Vec<ReadDescriptor>>,
+	dependency: Cell<Option<Version>>,
+}
+
+impl<'a> MVHashMapView<'a> {
+	/// The first lower transaction whose ESTIMATE this execution hit.
+	pub fn dependency(&self) -> Option<Version> {
+		self.dependency.get()
+	}
+}
+
+impl<'a> MultiversionView for MVHashMapView<'a> {
+	type ReadDescriptor = ReadDescriptor;
+	type ReadResult = Result<(Version, Arc<H256>), MVHashMapError>;
+	type TxnIdx = TxnIndex;
+
+	fn take_reads(&self) -> Vec<ReadDescriptor> {
+		self.reads.take()
+	}
+
+	fn read(&self, address: &H160, key: &H256) -> Result<Option<Arc<H256>>, ExitError> {
+		let key = (*address, *key);
+		match self.map.read(&key, self.txn_idx) {
+			Ok((version, value)) => {
+				self.reads.borrow_mut().push(ReadDescriptor {
+					key,
+					origin: ReadOrigin::Version(version),
+				});
+				Ok(Some(value))
+			}
+			Err(MVHashMapError::NotFound) => {
+				self.reads.borrow_mut().push(ReadDescriptor {
+					key,
+					origin: ReadOrigin::Storage,
+				});
+				Ok(None)
+			}
+			Err(MVHashMapError::Dependency((txn_idx, incarnation))) => {
+				if self.dependency.get().is_none() {
+					self.dependency.set(Some((txn_idx, incarnation)));
+				}
+				Err(ExitError::NotEstimatedYet {
+					txn_idx: txn_idx as u64,
+					incarnation: incarnation as u64,
+				})
+			}
+		}
+	}
+
+	// Only storage slots are kept in this map, accounts are left to the
+	// executor state.
+	fn read_basic(&self, _address: &H160) -> Result<Option<Basic>, ExitError> {
+		Ok(None)
+	}
+
+	fn read_code(&self, _address: &H160) -> Result<Option<Arc<Vec<u8>>>, ExitError> {
+		Ok(None)
+	}
+
+	fn read_exists(&self, _address: &H160) -> Result<Option<bool>, ExitError> {
+		Ok(None)
+	}
+
+	fn txn_idx(&self) -> TxnIndex {
+		self.txn_idx
+	}
+
+	fn read_dependency(&self) -> bool {
+		self.dependency.get().is_some()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::executor::stack::{
+		MemoryStackState, MultiversionAccess, MultiversionStackExecutor, StackSubstateMetadata,
+		StateKey, StateValue,
+	};
+	use crate::{Config, ExitReason, ExitSucceed};
+	use primitive_types::U256;
+
+	fn key(n: u64) -> (H160, H256) {
+		(H160::from_low_u64_be(n), H256::from_low_u64_be(n))
+	}
+
+	#[test]
+	fn reads_highest_lower_write() {
+		let map = MVHashMap::new();
+		map.write(&key(1), (1, 0), H256::from_low_u64_be(10));
+		map.write(&key(1), (3, 1), H256::from_low_u64_be(30));
+
+		assert_eq!(map.read(&key(1), 0).unwrap_err(), MVHashMapError::NotFound);
+		assert_eq!(map.read(&key(1), 1).unwrap_err(), MVHashMapError::NotFound);
+		assert_eq!(
+			map.read(&key(1), 3).unwrap(),
+			((1, 0), Arc::new(H256::from_low_u64_be(10)))
+		);
+		assert_eq!(
+			map.read(&key(1), 7).unwrap(),
+			((3, 1), Arc::new(H256::from_low_u64_be(30)))
+		);
+		assert_eq!(map.read(&key(2), 7).unwrap_err(), MVHashMapError::NotFound);
+
+		map.delete(&key(1), 3);
+		assert_eq!(map.read(&key(1), 7).unwrap().0, (1, 0));
+	}
+
+	#[test]
+	fn estimate_is_a_dependency() {
+		let map = MVHashMap::new();
+		map.write(&key(1), (2, 0), H256::from_low_u64_be(20));
+		map.mark_estimate(&key(1), 2);
+		assert_eq!(
+			map.read(&key(1), 5).unwrap_err(),
+			MVHashMapError::Dependency((2, 0))
+		);
+
+		let view = map.view(5);
+		assert_eq!(
+			view.read(&key(1).0, &key(1).1).unwrap_err(),
+			ExitError::NotEstimatedYet {
+				txn_idx: 2,
+				incarnation: 0,
+			}
+		);
+		assert!(view.read_dependency());
+		assert_eq!(view.dependency(), Some((2, 0)));
+
+		map.write(&key(1), (2, 1), H256::from_low_u64_be(21));
+		assert_eq!(map.read(&key(1), 5).unwrap().0, (2, 1));
+	}
+
+	#[test]
+	fn view_records_reads_for_validation() {
+		let map = MVHashMap::new();
+		map.write(&key(1), (0, 0), H256::from_low_u64_be(1));
+
+		let view = map.view(2);
+		assert_eq!(
+			view.read(&key(1).0, &key(1).1).unwrap(),
+			Some(Arc::new(H256::from_low_u64_be(1)))
+		);
+		assert_eq!(view.read(&key(2).0, &key(2).1).unwrap(), None);
+		assert!(!view.read_dependency());
+
+		let reads = view.take_reads();
+		assert_eq!(
+			reads,
+			vec![
+				ReadDescriptor {
+					key: key(1),
+					origin: ReadOrigin::Version((0, 0)),
+				},
+				ReadDescriptor {
+					key: key(2),
+					origin: ReadOrigin::Storage,
+				},
+			]
+		);
+		assert!(map.validate_reads(2, &reads));
+
+		map.write(&key(2), (1, 0), H256::from_low_u64_be(2));
+		assert!(!map.validate_reads(2, &reads));
+		assert!(map.validate_reads(1, &reads));
+	}
+
+	// SLOAD(0), stored to slot 1. `target` is either the contract itself or
+	// one calling it.
+	fn execute_copy_slot(view: &MVHashMapView, target: H160) -> (ExitReason, Option<H256>) {
+		let contract = H160::from_low_u64_be(0x10);
+		let vicinity = MemoryVicinity {
+			gas_price: U256::zero(),
+			origin: H160::default(),
+			block_hashes: Vec::new(),
+			block_number: Default::default(),
+			block_coinbase: Default::default(),
+			block_timestamp: Default::default(),
+			block_difficulty: Default::default(),
+			block_gas_limit: Default::default(),
+			chain_id: U256::one(),
+			block_base_fee_per_gas: U256::zero(),
+			block_blob_base_fee: U256::zero(),
+			blob_hashes: Vec::new(),
+			block_randomness: None,
+		};
+		let mut state = hashbrown::HashMap::default();
+		state.insert(
+			contract,
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: hashbrown::HashMap::default(),
+				code: vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x55, 0x00],
+			},
+		);
+		state.insert(
+			H160::from_low_u64_be(0x20),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: hashbrown::HashMap::default(),
+				// CALL 0x10 with all gas, then STOP.
+				code: hex::decode("6000600060006000600060105af100").unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(vicinity, state);
+
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = MultiversionStackExecutor::new_with_access(
+			state,
+			&config,
+			&precompiles,
+			MultiversionAccess::new(view),
+		);
+
+		let (reason, _) = executor.transact_call(
+			H160::from_low_u64_be(1),
+			target,
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		let written = match executor
+			.rw_set()
+			.unwrap()
+			.writes()
+			.get(&StateKey::Storage(contract, H256::from_low_u64_be(1)))
+		{
+			Some(StateValue::Storage(value)) => Some(*value),
+			_ => None,
+		};
+		(reason, written)
+	}
+
+	#[test]
+	fn executor_reads_through_view() {
+		let contract = H160::from_low_u64_be(0x10);
+		let map = MVHashMap::new();
+		map.write(&(contract, H256::zero()), (0, 0), H256::from_low_u64_be(42));
+
+		let view = map.view(1);
+		let (reason, written) = execute_copy_slot(&view, contract);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(written, Some(H256::from_low_u64_be(42)));
+		assert!(!view.read_dependency());
+		assert!(view.take_reads().contains(&ReadDescriptor {
+			key: (contract, H256::zero()),
+			origin: ReadOrigin::Version((0, 0)),
+		}));
+
+		map.mark_estimate(&(contract, H256::zero()), 0);
+		let view = map.view(1);
+		let (reason, written) = execute_copy_slot(&view, contract);
+		assert_eq!(
+			reason,
+			ExitReason::Error(ExitError::NotEstimatedYet {
+				txn_idx: 0,
+				incarnation: 0,
+			})
+		);
+		assert_eq!(written, None);
+		assert_eq!(view.dependency(), Some((0, 0)));
+	}
+
+	#[test]
+	fn dependency_aborts_calling_frames() {
+		let contract = H160::from_low_u64_be(0x10);
+		let map = MVHashMap::new();
+		map.write(&(contract, H256::zero()), (0, 3), H256::from_low_u64_be(42));
+		map.mark_estimate(&(contract, H256::zero()), 0);
+
+		let view = map.view(1);
+		let (reason, _) = execute_copy_slot(&view, H160::from_low_u64_be(0x20));
+		assert_eq!(
+			reason,
+			ExitReason::Error(ExitError::NotEstimatedYet {
+				txn_idx: 0,
+				incarnation: 3,
+			})
+		);
+	}
+}
@@ -0,0 +1,288 @@
+//! BLS12-381 operations on the EIP-2537 encoding, backed by `blst`.
+//!
+//! This is the only module of the crate calling into foreign code, and keeps
+//! every `unsafe` block next to the checks that make it sound: all points
+//! and field elements handed to `blst` are fully initialised values decoded
+//! from the input.
+
+#![allow(unsafe_code)]
+
+use super::error;
+use crate::ExitError;
+use alloc::vec::Vec;
+use blst::{
+	blst_bendian_from_fp, blst_fp, blst_fp12, blst_fp2, blst_fp_from_bendian, blst_map_to_g1,
+	blst_map_to_g2, blst_p1, blst_p1_add_or_double, blst_p1_affine, blst_p1_affine_in_g1,
+	blst_p1_affine_is_inf, blst_p1_affine_on_curve, blst_p1_from_affine, blst_p1_mult,
+	blst_p1_to_affine, blst_p2, blst_p2_add_or_double, blst_p2_affine, blst_p2_affine_in_g2,
+	blst_p2_affine_is_inf, blst_p2_affine_on_curve, blst_p2_from_affine, blst_p2_mult,
+	blst_p2_to_affine,
+};
+
+/// Length of a base field element, left-padded to 64 bytes.
+pub const FP_LEN: usize = 64;
+/// Length of a G1 point, encoded as `x || y`.
+pub const G1_LEN: usize = 2 * FP_LEN;
+/// Length of a G2 point, encoded as `x_c0 || x_c1 || y_c0 || y_c1`.
+pub const G2_LEN: usize = 4 * FP_LEN;
+/// Length of a scalar.
+pub const SCALAR_LEN: usize = 32;
+
+/// Length of the zero padding in front of a base field element.
+const PADDING_LEN: usize = 16;
+
+/// The base field modulus `p`, big-endian.
+const MODULUS: [u8; 48] = [
+	0x1a, 0x01, 0x11, 0xea, 0x39, 0x7f, 0xe6, 0x9a, 0x4b, 0x1b, 0xa7, 0xb6, 0x43, 0x4b, 0xac, 0xd7,
+	0x64, 0x77, 0x4b, 0x84, 0xf3, 0x85, 0x12, 0xbf, 0x67, 0x30, 0xd2, 0xa0, 0xf6, 0xb0, 0xf6, 0x24,
+	0x1e, 0xab, 0xff, 0xfe, 0xb1, 0x53, 0xff, 0xff, 0xb9, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xab,
+];
+
+/// A base field element, which must be zero-padded and smaller than `p`.
+fn read_fp(input: &[u8]) -> Result<blst_fp, ExitError> {
+	assert_eq!(input.len(), FP_LEN);
+	let (padding, value) = input.split_at(PADDING_LEN);
+	if padding.iter().any(|byte| *byte != 0) {
+		return Err(error("invalid BLS12-381 field element padding"));
+	}
+	if value >= &MODULUS[..] {
+		return Err(error("BLS12-381 field element is not canonical"));
+	}
+
+	let mut fp = blst_fp::default();
+	// SAFETY: `value` holds the 48 bytes read by `blst`, after the padding.
+	unsafe { blst_fp_from_bendian(&mut fp, value.as_ptr()) };
+	Ok(fp)
+}
+
+/// A quadratic extension field element, encoded as `c0 || c1`.
+fn read_fp2(input: &[u8]) -> Result<blst_fp2, ExitError> {
+	Ok(blst_fp2 {
+		fp: [read_fp(&input[..FP_LEN])?, read_fp(&input[FP_LEN..])?],
+	})
+}
+
+fn write_fp(output: &mut [u8], fp: &blst_fp) {
+	assert_eq!(output.len(), FP_LEN);
+	// SAFETY: `output` is `FP_LEN` bytes long, leaving room for the 48 bytes
+	// written by `blst` after the padding.
+	unsafe { blst_bendian_from_fp(output[PADDING_LEN..].as_mut_ptr(), fp) };
+}
+
+/// A G1 point on the curve, with all zeros as the point at infinity. The
+/// subgroup check is only required for multiplication and pairing.
+fn read_g1(input: &[u8], subgroup_check: bool) -> Result<blst_p1_affine, ExitError> {
+	let point = blst_p1_affine {
+		x: read_fp(&input[..FP_LEN])?,
+		y: read_fp(&input[FP_LEN..G1_LEN])?,
+	};
+
+	// SAFETY: the check only reads the initialised point.
+	if !unsafe { blst_p1_affine_on_curve(&point) } {
+		return Err(error("BLS12-381 G1 point is not on the curve"));
+	}
+	// SAFETY: the check only reads the initialised point.
+	if subgroup_check && !unsafe { blst_p1_affine_in_g1(&point) } {
+		return Err(error("BLS12-381 G1 point is not in the subgroup"));
+	}
+	Ok(point)
+}
+
+/// A G2 point on the curve, with all zeros as the point at infinity. The
+/// subgroup check is only required for multiplication and pairing.
+fn read_g2(input: &[u8], subgroup_check: bool) -> Result<blst_p2_affine, ExitError> {
+	let point = blst_p2_affine {
+		x: read_fp2(&input[..2 * FP_LEN])?,
+		y: read_fp2(&input[2 * FP_LEN..G2_LEN])?,
+	};
+
+	// SAFETY: the check only reads the initialised point.
+	if !unsafe { blst_p2_affine_on_curve(&point) } {
+		return Err(error("BLS12-381 G2 point is not on the curve"));
+	}
+	// SAFETY: the check only reads the initialised point.
+	if subgroup_check && !unsafe { blst_p2_affine_in_g2(&point) } {
+		return Err(error("BLS12-381 G2 point is not in the subgroup"));
+	}
+	Ok(point)
+}
+
+/// A big-endian scalar, converted to the little-endian order of `blst`.
+/// Scalars are not reduced, and may be larger than the group order.
+fn read_scalar(input: &[u8]) -> [u8; SCALAR_LEN] {
+	let mut scalar = [0u8; SCALAR_LEN];
+	scalar.copy_from_slice(input);
+	scalar.reverse();
+	scalar
+}
+
+fn g1_from_affine(point: &blst_p1_affine) -> blst_p1 {
+	let mut out = blst_p1::default();
+	// SAFETY: both arguments are valid references.
+	unsafe { blst_p1_from_affine(&mut out, point) };
+	out
+}
+
+fn g2_from_affine(point: &blst_p2_affine) -> blst_p2 {
+	let mut out = blst_p2::default();
+	// SAFETY: both arguments are valid references.
+	unsafe { blst_p2_from_affine(&mut out, point) };
+	out
+}
+
+fn encode_g1(point: &blst_p1) -> Vec<u8> {
+	let mut affine = blst_p1_affine::default();
+	// SAFETY: both arguments are valid references.
+	unsafe { blst_p1_to_affine(&mut affine, point) };
+
+	let mut output = alloc::vec![0u8; G1_LEN];
+	write_fp(&mut output[..FP_LEN], &affine.x);
+	write_fp(&mut output[FP_LEN..], &affine.y);
+	output
+}
+
+fn encode_g2(point: &blst_p2) -> Vec<u8> {
+	let mut affine = blst_p2_affine::default();
+	// SAFETY: both arguments are valid references.
+	unsafe { blst_p2_to_affine(&mut affine, point) };
+
+	let mut output = alloc::vec![0u8; G2_LEN];
+	for (chunk, fp) in output
+		.chunks_mut(FP_LEN)
+		.zip(affine.x.fp.iter().chain(affine.y.fp.iter()))
+	{
+		write_fp(chunk, fp);
+	}
+	output
+}
+
+fn check_len(input: &[u8], len: usize) -> Result<(), ExitError> {
+	if input.len() != len {
+		return Err(error("invalid BLS12-381 input length"));
+	}
+	Ok(())
+}
+
+/// Chunks of `len` bytes of a non-empty input.
+fn pairs(input: &[u8], len: usize) -> Result<core::slice::ChunksExact<'_, u8>, ExitError> {
+	let chunks = input.chunks_exact(len);
+	if input.is_empty() || !chunks.remainder().is_empty() {
+		return Err(error("invalid BLS12-381 input length"));
+	}
+	Ok(chunks)
+}
+
+pub fn g1_add(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	check_len(input, 2 * G1_LEN)?;
+	let a = g1_from_affine(&read_g1(&input[..G1_LEN], false)?);
+	let b = g1_from_affine(&read_g1(&input[G1_LEN..], false)?);
+
+	let mut sum = blst_p1::default();
+	// SAFETY: all arguments are valid references to initialised points.
+	unsafe { blst_p1_add_or_double(&mut sum, &a, &b) };
+	Ok(encode_g1(&sum))
+}
+
+pub fn g1_msm(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let mut sum = blst_p1::default();
+	for pair in pairs(input, G1_LEN + SCALAR_LEN)? {
+		let point = read_g1(&pair[..G1_LEN], true)?;
+		let scalar = read_scalar(&pair[G1_LEN..]);
+		// SAFETY: the check only reads the initialised point.
+		if unsafe { blst_p1_affine_is_inf(&point) } || scalar == [0u8; SCALAR_LEN] {
+			continue;
+		}
+
+		let mut product = blst_p1::default();
+		// SAFETY: `scalar` holds the `SCALAR_LEN * 8` bits read by `blst`, and
+		// the sum is added from a copy so that no output aliases an input.
+		unsafe {
+			blst_p1_mult(
+				&mut product,
+				&g1_from_affine(&point),
+				scalar.as_ptr(),
+				SCALAR_LEN * 8,
+			);
+			let partial = sum;
+			blst_p1_add_or_double(&mut sum, &partial, &product);
+		}
+	}
+	Ok(encode_g1(&sum))
+}
+
+pub fn g2_add(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	check_len(input, 2 * G2_LEN)?;
+	let a = g2_from_affine(&read_g2(&input[..G2_LEN], false)?);
+	let b = g2_from_affine(&read_g2(&input[G2_LEN..], false)?);
+
+	let mut sum = blst_p2::default();
+	// SAFETY: all arguments are valid references to initialised points.
+	unsafe { blst_p2_add_or_double(&mut sum, &a, &b) };
+	Ok(encode_g2(&sum))
+}
+
+pub fn g2_msm(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let mut sum = blst_p2::default();
+	for pair in pairs(input, G2_LEN + SCALAR_LEN)? {
+		let point = read_g2(&pair[..G2_LEN], true)?;
+		let scalar = read_scalar(&pair[G2_LEN..]);
+		// SAFETY: the check only reads the initialised point.
+		if unsafe { blst_p2_affine_is_inf(&point) } || scalar == [0u8; SCALAR_LEN] {
+			continue;
+		}
+
+		let mut product = blst_p2::default();
+		// SAFETY: `scalar` holds the `SCALAR_LEN * 8` bits read by `blst`, and
+		// the sum is added from a copy so that no output aliases an input.
+		unsafe {
+			blst_p2_mult(
+				&mut product,
+				&g2_from_affine(&point),
+				scalar.as_ptr(),
+				SCALAR_LEN * 8,
+			);
+			let partial = sum;
+			blst_p2_add_or_double(&mut sum, &partial, &product);
+		}
+	}
+	Ok(encode_g2(&sum))
+}
+
+pub fn pairing_check(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let mut product = blst_fp12::default();
+	for pair in pairs(input, G1_LEN + G2_LEN)? {
+		let p = read_g1(&pair[..G1_LEN], true)?;
+		let q = read_g2(&pair[G1_LEN..], true)?;
+		// SAFETY: the checks only read the initialised points.
+		if unsafe { blst_p1_affine_is_inf(&p) || blst_p2_affine_is_inf(&q) } {
+			continue;
+		}
+		product *= blst_fp12::miller_loop(&q, &p);
+	}
+
+	let mut output = alloc::vec![0u8; 32];
+	if product.final_exp() == blst_fp12::default() {
+		output[31] = 1;
+	}
+	Ok(output)
+}
+
+pub fn map_fp_to_g1(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	check_len(input, FP_LEN)?;
+	let u = read_fp(input)?;
+
+	let mut point = blst_p1::default();
+	// SAFETY: `u` is initialised, and a null second element maps `u` alone.
+	unsafe { blst_map_to_g1(&mut point, &u, core::ptr::null()) };
+	Ok(encode_g1(&point))
+}
+
+pub fn map_fp2_to_g2(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	check_len(input, 2 * FP_LEN)?;
+	let u = read_fp2(input)?;
+
+	let mut point = blst_p2::default();
+	// SAFETY: `u` is initialised, and a null second element maps `u` alone.
+	unsafe { blst_map_to_g2(&mut point, &u, core::ptr::null()) };
+	Ok(encode_g2(&point))
+}
@@ -0,0 +1,252 @@
+//! BLS12-381 curve operation precompiles. See EIP-2537.
+
+mod curve;
+
+use super::{
+	IsPrecompileResult, PrecompileHandle, PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use crate::{Config, ExitError, ExitSucceed};
+use alloc::{borrow::Cow, collections::BTreeMap, vec::Vec};
+use primitive_types::H160;
+
+use self::curve::{G1_LEN, G2_LEN, SCALAR_LEN};
+
+/// Discounts applied to G1 multi-scalar multiplications of 1 to 128 pairs,
+/// in thousandths. Larger inputs get the last discount.
+const G1_MSM_DISCOUNTS: [u64; 128] = [
+	1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665,
+	661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615,
+	613, 611, 609, 608, 606, 604, 603, 601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585,
+	584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564, 563,
+	562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547, 547, 546, 545,
+	544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529,
+	528, 528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+
+/// Discounts applied to G2 multi-scalar multiplications of 1 to 128 pairs,
+/// in thousandths. Larger inputs get the last discount.
+const G2_MSM_DISCOUNTS: [u64; 128] = [
+	1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704,
+	699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632,
+	629, 627, 624, 622, 620, 618, 615, 613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593,
+	592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569, 568,
+	567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552, 551, 550, 549,
+	548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534,
+	533, 532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+/// A BLS12-381 precompile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Bls12381Precompile {
+	/// G1 point addition, at `0x0b`.
+	G1Add,
+	/// G1 multi-scalar multiplication, at `0x0c`.
+	G1Msm,
+	/// G2 point addition, at `0x0d`.
+	G2Add,
+	/// G2 multi-scalar multiplication, at `0x0e`.
+	G2Msm,
+	/// Pairing check, at `0x0f`.
+	PairingCheck,
+	/// Mapping of a base field element to G1, at `0x10`.
+	MapFpToG1,
+	/// Mapping of a quadratic extension field element to G2, at `0x11`.
+	MapFp2ToG2,
+}
+
+impl Bls12381Precompile {
+	/// All BLS12-381 precompiles, in address order.
+	pub const ALL: [Self; 7] = [
+		Self::G1Add,
+		Self::G1Msm,
+		Self::G2Add,
+		Self::G2Msm,
+		Self::PairingCheck,
+		Self::MapFpToG1,
+		Self::MapFp2ToG2,
+	];
+
+	/// Address the precompile lives at.
+	pub fn address(self) -> H160 {
+		let index = match self {
+			Self::G1Add => 0x0b,
+			Self::G1Msm => 0x0c,
+			Self::G2Add => 0x0d,
+			Self::G2Msm => 0x0e,
+			Self::PairingCheck => 0x0f,
+			Self::MapFpToG1 => 0x10,
+			Self::MapFp2ToG2 => 0x11,
+		};
+		let mut address = H160::zero();
+		address.0[19] = index;
+		address
+	}
+}
+
+/// BLS12-381 precompiles, active when `Config::has_bls12_381_precompiles`
+/// is set.
+///
+/// They only cover `0x0b` to `0x11`, and are meant to be combined with a set
+/// providing the other precompiles, e.g. as `Chain::new(standard, bls12_381)`.
+#[derive(Clone, Debug)]
+pub struct Bls12381Precompiles {
+	precompiles: BTreeMap<H160, Bls12381Precompile>,
+}
+
+impl Bls12381Precompiles {
+	/// Create the precompile set of the hard fork described by `config`.
+	pub fn new(config: &Config) -> Self {
+		let active: &[Bls12381Precompile] = if config.has_bls12_381_precompiles {
+			&Bls12381Precompile::ALL
+		} else {
+			&[]
+		};
+
+		Self {
+			precompiles: active
+				.iter()
+				.map(|precompile| (precompile.address(), *precompile))
+				.collect(),
+		}
+	}
+
+	/// Addresses of the active precompiles, in ascending order.
+	pub fn addresses(&self) -> impl Iterator<Item = H160> + '_ {
+		self.precompiles.keys().copied()
+	}
+
+	/// The precompile active at `address`, if any.
+	pub fn get(&self, address: H160) -> Option<Bls12381Precompile> {
+		self.precompiles.get(&address).copied()
+	}
+
+	/// Gas charged for running `precompile` on `input`.
+	pub fn cost(&self, precompile: Bls12381Precompile, input: &[u8]) -> u64 {
+		match precompile {
+			Bls12381Precompile::G1Add => 375,
+			Bls12381Precompile::G1Msm => {
+				msm_cost(input, G1_LEN + SCALAR_LEN, 12000, &G1_MSM_DISCOUNTS)
+			}
+			Bls12381Precompile::G2Add => 600,
+			Bls12381Precompile::G2Msm => {
+				msm_cost(input, G2_LEN + SCALAR_LEN, 22500, &G2_MSM_DISCOUNTS)
+			}
+			Bls12381Precompile::PairingCheck => {
+				let pairs = (input.len() / (G1_LEN + G2_LEN)) as u64;
+				37700u64.saturating_add(pairs.saturating_mul(32600))
+			}
+			Bls12381Precompile::MapFpToG1 => 5500,
+			Bls12381Precompile::MapFp2ToG2 => 23800,
+		}
+	}
+
+	/// Run `precompile` on `input`, without charging any gas.
+	pub fn run(&self, precompile: Bls12381Precompile, input: &[u8]) -> Result<Vec<u8>, ExitError> {
+		match precompile {
+			Bls12381Precompile::G1Add => curve::g1_add(input),
+			Bls12381Precompile::G1Msm => curve::g1_msm(input),
+			Bls12381Precompile::G2Add => curve::g2_add(input),
+			Bls12381Precompile::G2Msm => curve::g2_msm(input),
+			Bls12381Precompile::PairingCheck => curve::pairing_check(input),
+			Bls12381Precompile::MapFpToG1 => curve::map_fp_to_g1(input),
+			Bls12381Precompile::MapFp2ToG2 => curve::map_fp2_to_g2(input),
+		}
+	}
+}
+
+impl PrecompileSet for Bls12381Precompiles {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		let precompile = self.get(handle.code_address())?;
+
+		let cost = self.cost(precompile, handle.input());
+		if let Err(error) = handle.record_cost(cost) {
+			return Some(Err(error.into()));
+		}
+
+		Some(
+			self.run(precompile, handle.input())
+				.map(|output| PrecompileOutput {
+					exit_status: ExitSucceed::Returned,
+					output,
+				})
+				.map_err(Into::into),
+		)
+	}
+
+	fn is_precompile(&self, address: H160, _: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: self.precompiles.contains_key(&address),
+			extra_cost: 0,
+		}
+	}
+}
+
+/// `pairs * multiplication_cost * discount / 1000`, with the discount of the
+/// number of `pair_len` chunks in `input`.
+fn msm_cost(input: &[u8], pair_len: usize, multiplication_cost: u64, discounts: &[u64]) -> u64 {
+	let pairs = input.len() / pair_len;
+	if pairs == 0 {
+		return 0;
+	}
+
+	let discount = discounts[pairs.min(discounts.len()) - 1];
+	(pairs as u64)
+		.saturating_mul(multiplication_cost)
+		.saturating_mul(discount)
+		/ 1000
+}
+
+fn error(message: &'static str) -> ExitError {
+	ExitError::Other(Cow::Borrowed(message))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cost(precompile: Bls12381Precompile, len: usize) -> u64 {
+		Bls12381Precompiles::new(&Config::prague()).cost(precompile, &alloc::vec![0u8; len])
+	}
+
+	#[test]
+	fn active_from_prague() {
+		let count = |config: &Config| Bls12381Precompiles::new(config).addresses().count();
+
+		assert_eq!(count(&Config::cancun()), 0);
+		assert_eq!(count(&Config::prague()), 7);
+	}
+
+	#[test]
+	fn msm_pricing_is_discounted() {
+		assert_eq!(cost(Bls12381Precompile::G1Msm, 0), 0);
+		assert_eq!(cost(Bls12381Precompile::G1Msm, 160), 12000);
+		assert_eq!(
+			cost(Bls12381Precompile::G1Msm, 2 * 160),
+			2 * 12000 * 949 / 1000
+		);
+		assert_eq!(
+			cost(Bls12381Precompile::G1Msm, 128 * 160),
+			128 * 12000 * 519 / 1000
+		);
+		assert_eq!(
+			cost(Bls12381Precompile::G1Msm, 200 * 160),
+			200 * 12000 * 519 / 1000
+		);
+
+		assert_eq!(cost(Bls12381Precompile::G2Msm, 288), 22500);
+		assert_eq!(cost(Bls12381Precompile::G2Msm, 2 * 288), 2 * 22500);
+		assert_eq!(
+			cost(Bls12381Precompile::G2Msm, 3 * 288),
+			3 * 22500 * 923 / 1000
+		);
+		assert_eq!(
+			cost(Bls12381Precompile::G2Msm, 300 * 288),
+			300 * 22500 * 524 / 1000
+		);
+
+		assert_eq!(
+			cost(Bls12381Precompile::PairingCheck, 2 * 384),
+			37700 + 2 * 32600
+		);
+	}
+}
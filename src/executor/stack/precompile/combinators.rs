@@ -0,0 +1,230 @@
+//! Precompile sets built out of other precompile sets or closures.
+
+use super::{IsPrecompileResult, PrecompileHandle, PrecompileResult, PrecompileSet};
+use crate::{Config, ForkActivation};
+use alloc::{boxed::Box, collections::BTreeMap, collections::BTreeSet};
+use core::ops::RangeInclusive;
+use primitive_types::{H160, U256};
+
+/// A precompile capturing its own state.
+pub type PrecompileClosure =
+	Box<dyn Fn(&mut dyn PrecompileHandle) -> PrecompileResult + Send + Sync>;
+
+/// Precompiles capturing their own state, by address.
+#[derive(Default)]
+pub struct ClosurePrecompiles {
+	precompiles: BTreeMap<H160, PrecompileClosure>,
+}
+
+impl ClosurePrecompiles {
+	/// Create an empty precompile set.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Run `precompile` at `address`, replacing any previous one.
+	pub fn with(
+		mut self,
+		address: H160,
+		precompile: impl Fn(&mut dyn PrecompileHandle) -> PrecompileResult + Send + Sync + 'static,
+	) -> Self {
+		self.precompiles.insert(address, Box::new(precompile));
+		self
+	}
+}
+
+impl PrecompileSet for ClosurePrecompiles {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		let precompile = self.precompiles.get(&handle.code_address())?;
+		Some(precompile(handle))
+	}
+
+	fn is_precompile(&self, address: H160, _: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: self.precompiles.contains_key(&address),
+			extra_cost: 0,
+		}
+	}
+}
+
+/// Two precompile sets, where the first one takes precedence at addresses
+/// both of them cover.
+///
+/// The second set is only asked about addresses the first one does not
+/// cover, with the gas left after the first one's `extra_cost`, and the
+/// costs of both checks add up.
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+	first: A,
+	second: B,
+}
+
+impl<A, B> Chain<A, B> {
+	/// Chain `first` with `second`.
+	pub fn new(first: A, second: B) -> Self {
+		Self { first, second }
+	}
+}
+
+impl<A: PrecompileSet, B: PrecompileSet> PrecompileSet for Chain<A, B> {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		self.first
+			.execute(handle)
+			.or_else(|| self.second.execute(handle))
+	}
+
+	fn is_precompile(&self, address: H160, remaining_gas: u64) -> IsPrecompileResult {
+		let first_cost = match self.first.is_precompile(address, remaining_gas) {
+			IsPrecompileResult::Answer {
+				is_precompile: false,
+				extra_cost,
+			} => extra_cost,
+			answer => return answer,
+		};
+		let remaining_gas = match remaining_gas.checked_sub(first_cost) {
+			Some(remaining_gas) => remaining_gas,
+			None => return IsPrecompileResult::OutOfGas,
+		};
+
+		match self.second.is_precompile(address, remaining_gas) {
+			IsPrecompileResult::Answer {
+				is_precompile,
+				extra_cost,
+			} => IsPrecompileResult::Answer {
+				is_precompile,
+				extra_cost: first_cost.saturating_add(extra_cost),
+			},
+			IsPrecompileResult::OutOfGas => IsPrecompileResult::OutOfGas,
+		}
+	}
+}
+
+/// A single precompile covering a range of addresses, e.g. one per token
+/// of a native asset. It can tell them apart with
+/// `PrecompileHandle::code_address`.
+pub struct AddressRange {
+	range: RangeInclusive<H160>,
+	precompile: PrecompileClosure,
+}
+
+impl AddressRange {
+	/// Run `precompile` at all addresses of `range`.
+	pub fn new(
+		range: RangeInclusive<H160>,
+		precompile: impl Fn(&mut dyn PrecompileHandle) -> PrecompileResult + Send + Sync + 'static,
+	) -> Self {
+		Self {
+			range,
+			precompile: Box::new(precompile),
+		}
+	}
+}
+
+impl PrecompileSet for AddressRange {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		if !self.range.contains(&handle.code_address()) {
+			return None;
+		}
+		Some((self.precompile)(handle))
+	}
+
+	fn is_precompile(&self, address: H160, _: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: self.range.contains(&address),
+			extra_cost: 0,
+		}
+	}
+}
+
+/// A precompile set that is only active from a hard fork on.
+#[derive(Clone, Debug)]
+pub struct ForkGated<S> {
+	set: S,
+	active: bool,
+}
+
+impl<S> ForkGated<S> {
+	/// Activate `set` at `activation`, for a block of the given number and
+	/// timestamp.
+	pub fn new(
+		set: S,
+		activation: ForkActivation,
+		block_number: U256,
+		block_timestamp: U256,
+	) -> Self {
+		Self {
+			set,
+			active: activation.is_active(block_number, block_timestamp),
+		}
+	}
+
+	/// Activate `set` in the hard forks described by the configurations
+	/// matching `is_active`.
+	pub fn with_config(set: S, config: &Config, is_active: impl FnOnce(&Config) -> bool) -> Self {
+		Self {
+			set,
+			active: is_active(config),
+		}
+	}
+
+	/// Whether the set is active.
+	pub fn is_active(&self) -> bool {
+		self.active
+	}
+}
+
+impl<S: PrecompileSet> PrecompileSet for ForkGated<S> {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		if !self.active {
+			return None;
+		}
+		self.set.execute(handle)
+	}
+
+	fn is_precompile(&self, address: H160, remaining_gas: u64) -> IsPrecompileResult {
+		if !self.active {
+			return IsPrecompileResult::Answer {
+				is_precompile: false,
+				extra_cost: 0,
+			};
+		}
+		self.set.is_precompile(address, remaining_gas)
+	}
+}
+
+/// A precompile set with some of its addresses disabled, which then behave
+/// like regular accounts.
+#[derive(Clone, Debug)]
+pub struct DenyList<S> {
+	set: S,
+	denied: BTreeSet<H160>,
+}
+
+impl<S> DenyList<S> {
+	/// Disable `denied` addresses of `set`.
+	pub fn new(set: S, denied: impl IntoIterator<Item = H160>) -> Self {
+		Self {
+			set,
+			denied: denied.into_iter().collect(),
+		}
+	}
+}
+
+impl<S: PrecompileSet> PrecompileSet for DenyList<S> {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		if self.denied.contains(&handle.code_address()) {
+			return None;
+		}
+		self.set.execute(handle)
+	}
+
+	fn is_precompile(&self, address: H160, remaining_gas: u64) -> IsPrecompileResult {
+		if self.denied.contains(&address) {
+			return IsPrecompileResult::Answer {
+				is_precompile: false,
+				extra_cost: 0,
+			};
+		}
+		self.set.is_precompile(address, remaining_gas)
+	}
+}
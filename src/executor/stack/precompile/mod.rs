@@ -1,6 +1,26 @@
+#[cfg(feature = "bls12-381")]
+mod bls12_381;
+mod combinators;
+#[cfg(feature = "precompiles")]
+mod p256_verify;
+#[cfg(feature = "precompiles")]
+mod standard;
+
+#[cfg(feature = "bls12-381")]
+pub use self::bls12_381::{Bls12381Precompile, Bls12381Precompiles};
+pub use self::combinators::{
+	AddressRange, Chain, ClosurePrecompiles, DenyList, ForkGated, PrecompileClosure,
+};
+#[cfg(feature = "precompiles")]
+pub use self::p256_verify::{P256Verify, P256_VERIFY_COST};
+#[cfg(feature = "precompiles")]
+pub(crate) use self::standard::recover_signer;
+#[cfg(feature = "precompiles")]
+pub use self::standard::{StandardPrecompile, StandardPrecompiles};
+
 use crate::{Context, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Transfer};
 use alloc::{collections::BTreeMap, vec::Vec};
-use primitive_types::{H160, H256};
+use primitive_types::{H160, H256, U256};
 
 /// A precompile result.
 pub type PrecompileResult = Result<PrecompileOutput, PrecompileFailure>;
@@ -80,6 +100,16 @@ pub trait PrecompileHandle {
 
 	/// Retreive the gas limit of this call.
 	fn gas_limit(&self) -> Option<u64>;
+
+	/// Read a storage slot of the precompile address, charged like `SLOAD`.
+	fn storage(&mut self, index: H256) -> Result<H256, ExitError>;
+
+	/// Write a storage slot of the precompile address, charged and refunded
+	/// like `SSTORE`. Fails like `SSTORE` in a static call.
+	fn set_storage(&mut self, index: H256, value: H256) -> Result<(), ExitError>;
+
+	/// Retreive the balance of an account, charged like `BALANCE`.
+	fn balance(&mut self, address: H160) -> Result<U256, ExitError>;
 }
 
 /// A set of precompiles.
@@ -97,6 +127,7 @@ pub trait PrecompileSet {
 	fn is_precompile(&self, address: H160, remaining_gas: u64) -> IsPrecompileResult;
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum IsPrecompileResult {
 	Answer {
 		is_precompile: bool,
@@ -0,0 +1,148 @@
+//! secp256r1 signature verification precompile. See RIP-7212.
+
+use super::{
+	IsPrecompileResult, PrecompileHandle, PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use crate::{Config, ExitSucceed};
+use alloc::vec::Vec;
+use p256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+use p256::EncodedPoint;
+use primitive_types::H160;
+
+/// Gas charged for every call, whether the signature is valid or not.
+pub const P256_VERIFY_COST: u64 = 3450;
+
+/// The P256VERIFY precompile at `0x100`, active when
+/// `Config::has_p256_verify` is set.
+///
+/// The input is `hash || r || s || x || y`. A valid signature returns a
+/// 32-byte `1`; anything else, including a malformed input, returns no
+/// output rather than failing. It only covers `0x100`, and is meant to be
+/// combined with a set providing the other precompiles, e.g. as
+/// `Chain::new(standard, p256)`.
+#[derive(Clone, Copy, Debug)]
+pub struct P256Verify {
+	active: bool,
+}
+
+impl P256Verify {
+	/// Create the precompile set of the chain described by `config`.
+	pub fn new(config: &Config) -> Self {
+		Self {
+			active: config.has_p256_verify,
+		}
+	}
+
+	/// Address the precompile lives at.
+	pub fn address() -> H160 {
+		let mut address = H160::zero();
+		address.0[18] = 0x01;
+		address
+	}
+
+	/// Verify the signature of `input`, without charging any gas.
+	pub fn run(input: &[u8]) -> Vec<u8> {
+		if verify(input).is_some() {
+			let mut output = alloc::vec![0u8; 32];
+			output[31] = 1;
+			output
+		} else {
+			Vec::new()
+		}
+	}
+}
+
+fn verify(input: &[u8]) -> Option<()> {
+	if input.len() != 160 {
+		return None;
+	}
+	let (hash, signature, x, y) = (
+		&input[0..32],
+		&input[32..96],
+		&input[96..128],
+		&input[128..160],
+	);
+
+	// Rejects `r` and `s` outside of `[1, n - 1]`. High `s` values are valid.
+	let signature = Signature::from_slice(signature).ok()?;
+	// Rejects the point at infinity and points that are not on the curve.
+	let point = EncodedPoint::from_affine_coordinates(x.into(), y.into(), false);
+	let key = VerifyingKey::from_encoded_point(&point).ok()?;
+
+	key.verify_prehash(hash, &signature).ok()
+}
+
+impl PrecompileSet for P256Verify {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		if !self.active || handle.code_address() != Self::address() {
+			return None;
+		}
+
+		if let Err(error) = handle.record_cost(P256_VERIFY_COST) {
+			return Some(Err(error.into()));
+		}
+
+		Some(Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			output: Self::run(handle.input()),
+		}))
+	}
+
+	fn is_precompile(&self, address: H160, _: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: self.active && address == Self::address(),
+			extra_cost: 0,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const HASH: &str = "d62368051f6203114956ec160657c67bcb9c42dbab691dfb393241225372629b";
+	const R: &str = "8d292129235f0f96cddd78039477ee0a0b7b34af22eaa2f20afab7599eca4ee2";
+	const S: &str = "0c798c07d4f1ddc8d073ed8018d6df1c79d03ab0f7798acbed53f85e68a6fd57";
+	const HIGH_S: &str = "f38673f72b0e22382f8c127fe72920e34316bffcaf9e13b90665d26493bc27fa";
+	const X: &str = "f2be7511701ebd56eed31917b6b73861d7649c4034725e7f6539215ad00f754b";
+	const Y: &str = "05393782b74873db482186a8c39320d090245ccf1babc7e794c30af32fa666bd";
+	const ZERO: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+	fn run(parts: &[&str]) -> Vec<u8> {
+		P256Verify::run(&hex::decode(parts.concat()).unwrap())
+	}
+
+	#[test]
+	fn verifies_signatures() {
+		let valid = hex::decode("0000000000000000000000000000000000000000000000000000000000000001")
+			.unwrap();
+		assert_eq!(run(&[HASH, R, S, X, Y]), valid);
+		// Both `s` and `n - s` are accepted.
+		assert_eq!(run(&[HASH, R, HIGH_S, X, Y]), valid);
+	}
+
+	#[test]
+	fn invalid_inputs_return_nothing() {
+		assert!(run(&[R, R, S, X, Y]).is_empty());
+		assert!(run(&[HASH, ZERO, S, X, Y]).is_empty());
+		assert!(run(&[HASH, R, S, X, R]).is_empty());
+		assert!(run(&[HASH, R, S, ZERO, ZERO]).is_empty());
+		assert!(run(&[HASH, R, S, X]).is_empty());
+		assert!(run(&[HASH, R, S, X, Y, "00"]).is_empty());
+	}
+
+	#[test]
+	fn follows_config() {
+		let address = P256Verify::address();
+		let active = |config: &Config| match P256Verify::new(config).is_precompile(address, 0) {
+			IsPrecompileResult::Answer { is_precompile, .. } => is_precompile,
+			IsPrecompileResult::OutOfGas => unreachable!(),
+		};
+
+		assert!(!active(&Config::prague()));
+		assert!(active(&Config {
+			has_p256_verify: true,
+			..Config::prague()
+		}));
+	}
+}
@@ -0,0 +1,88 @@
+use super::{error, padded};
+use crate::ExitError;
+use alloc::vec::Vec;
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+/// Length of a (G1, G2) pair in the pairing check input.
+pub const PAIR_LEN: usize = 192;
+
+fn read_fq(input: &[u8]) -> Result<Fq, ExitError> {
+	Fq::from_slice(input).map_err(|_| error("invalid alt_bn128 field element"))
+}
+
+/// A G1 point encoded as `x || y`, with `(0, 0)` as the point at infinity.
+fn read_g1(input: &[u8]) -> Result<G1, ExitError> {
+	let x = read_fq(&input[0..32])?;
+	let y = read_fq(&input[32..64])?;
+
+	if x.is_zero() && y.is_zero() {
+		Ok(G1::zero())
+	} else {
+		AffineG1::new(x, y)
+			.map(Into::into)
+			.map_err(|_| error("invalid alt_bn128 G1 point"))
+	}
+}
+
+/// A G2 point encoded as `x_im || x_re || y_im || y_re`, with all zeros as
+/// the point at infinity.
+fn read_g2(input: &[u8]) -> Result<G2, ExitError> {
+	let x = Fq2::new(read_fq(&input[32..64])?, read_fq(&input[0..32])?);
+	let y = Fq2::new(read_fq(&input[96..128])?, read_fq(&input[64..96])?);
+
+	if x.is_zero() && y.is_zero() {
+		Ok(G2::zero())
+	} else {
+		AffineG2::new(x, y)
+			.map(Into::into)
+			.map_err(|_| error("invalid alt_bn128 G2 point"))
+	}
+}
+
+fn encode_g1(point: G1) -> Vec<u8> {
+	let mut output = alloc::vec![0u8; 64];
+	if let Some(point) = AffineG1::from_jacobian(point) {
+		point
+			.x()
+			.to_big_endian(&mut output[0..32])
+			.expect("output holds a field element");
+		point
+			.y()
+			.to_big_endian(&mut output[32..64])
+			.expect("output holds a field element");
+	}
+	output
+}
+
+pub fn add(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let input = padded(input, 0, 128);
+	let p1 = read_g1(&input[0..64])?;
+	let p2 = read_g1(&input[64..128])?;
+
+	Ok(encode_g1(p1 + p2))
+}
+
+pub fn mul(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let input = padded(input, 0, 96);
+	let p = read_g1(&input[0..64])?;
+	let scalar = Fr::from_slice(&input[64..96]).map_err(|_| error("invalid alt_bn128 scalar"))?;
+
+	Ok(encode_g1(p * scalar))
+}
+
+pub fn pairing(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let chunks = input.chunks_exact(PAIR_LEN);
+	if !chunks.remainder().is_empty() {
+		return Err(error("invalid alt_bn128 pairing input length"));
+	}
+
+	let pairs = chunks
+		.map(|pair| Ok((read_g1(&pair[0..64])?, read_g2(&pair[64..192])?)))
+		.collect::<Result<Vec<_>, ExitError>>()?;
+
+	let mut output = alloc::vec![0u8; 32];
+	if bn::pairing_batch(&pairs) == Gt::one() {
+		output[31] = 1;
+	}
+	Ok(output)
+}
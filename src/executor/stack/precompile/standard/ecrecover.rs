@@ -0,0 +1,51 @@
+use super::padded;
+use alloc::vec::Vec;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use primitive_types::{H160, H256};
+use sha3::{Digest, Keccak256};
+
+pub const COST: u64 = 3000;
+
+/// Recover the address that signed a message hash. Invalid signatures are
+/// not an error, they return an empty output.
+pub fn run(input: &[u8]) -> Vec<u8> {
+	let input = padded(input, 0, 128);
+	recover(&input).unwrap_or_default()
+}
+
+/// Recover the address that signed `hash`, given the y parity of the
+/// signature instead of `v`.
+pub fn recover_signer(hash: H256, y_parity: bool, r: H256, s: H256) -> Option<H160> {
+	let mut input = [0u8; 128];
+	input[0..32].copy_from_slice(hash.as_bytes());
+	input[63] = 27 + y_parity as u8;
+	input[64..96].copy_from_slice(r.as_bytes());
+	input[96..128].copy_from_slice(s.as_bytes());
+	recover(&input).map(|output| H160::from_slice(&output[12..]))
+}
+
+fn recover(input: &[u8]) -> Option<Vec<u8>> {
+	let (hash, v, signature) = (&input[0..32], &input[32..64], &input[64..128]);
+
+	// `v` is a full word, holding either 27 or 28.
+	if v[..31].iter().any(|byte| *byte != 0) || !(v[31] == 27 || v[31] == 28) {
+		return None;
+	}
+	let mut recovery_id = RecoveryId::from_byte(v[31] - 27)?;
+
+	// Ethereum accepts high `s` values here, unlike transaction signatures,
+	// so normalise them before handing the signature to `k256`.
+	let mut signature = Signature::from_slice(signature).ok()?;
+	if let Some(normalized) = signature.normalize_s() {
+		signature = normalized;
+		recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+	}
+
+	let key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id).ok()?;
+	let key = key.to_encoded_point(false);
+	let hash = Keccak256::digest(&key.as_bytes()[1..]);
+
+	let mut output = alloc::vec![0u8; 32];
+	output[12..].copy_from_slice(&hash[12..]);
+	Some(output)
+}
@@ -0,0 +1,14 @@
+use alloc::vec::Vec;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+pub fn sha256(input: &[u8]) -> Vec<u8> {
+	Sha256::digest(input).to_vec()
+}
+
+/// The 20-byte digest, left-padded to a 32-byte word.
+pub fn ripemd160(input: &[u8]) -> Vec<u8> {
+	let mut output = alloc::vec![0u8; 32];
+	output[12..].copy_from_slice(&Ripemd160::digest(input));
+	output
+}
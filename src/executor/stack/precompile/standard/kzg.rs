@@ -0,0 +1,43 @@
+use super::error;
+use crate::ExitError;
+use alloc::vec::Vec;
+use c_kzg::{Bytes32, Bytes48, KzgProof};
+use sha2::{Digest, Sha256};
+
+/// Version byte of a KZG commitment's versioned hash.
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// `FIELD_ELEMENTS_PER_BLOB` and `BLS_MODULUS`, returned on success.
+const RETURN_VALUE: [u8; 64] = [
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00,
+	0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+	0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Verify that a blob committed to by a versioned hash evaluates to `y` at
+/// `z`. The input is `versioned_hash || z || y || commitment || proof`.
+pub fn run(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	if input.len() != 192 {
+		return Err(error("invalid point evaluation input length"));
+	}
+
+	let versioned_hash = &input[0..32];
+	let commitment = &input[96..144];
+	let mut expected_hash = Sha256::digest(commitment);
+	expected_hash[0] = VERSIONED_HASH_VERSION_KZG;
+	if versioned_hash != expected_hash.as_slice() {
+		return Err(error("mismatched versioned hash"));
+	}
+
+	let invalid = |_| error("invalid point evaluation input");
+	let z = Bytes32::from_bytes(&input[32..64]).map_err(invalid)?;
+	let y = Bytes32::from_bytes(&input[64..96]).map_err(invalid)?;
+	let commitment = Bytes48::from_bytes(commitment).map_err(invalid)?;
+	let proof = Bytes48::from_bytes(&input[144..192]).map_err(invalid)?;
+
+	match KzgProof::verify_kzg_proof(&commitment, &z, &y, &proof, c_kzg::ethereum_kzg_settings()) {
+		Ok(true) => Ok(RETURN_VALUE.to_vec()),
+		_ => Err(error("invalid point evaluation proof")),
+	}
+}
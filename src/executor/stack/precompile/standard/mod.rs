@@ -0,0 +1,412 @@
+//! Precompiled contracts defined by the Ethereum specification.
+
+mod blake2f;
+mod bn128;
+mod ecrecover;
+mod hash;
+#[cfg(feature = "kzg")]
+mod kzg;
+mod modexp;
+
+pub(crate) use self::ecrecover::recover_signer;
+
+use super::{
+	IsPrecompileResult, PrecompileHandle, PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use crate::{Config, ExitError, ExitSucceed};
+use alloc::{borrow::Cow, collections::BTreeMap, vec::Vec};
+use primitive_types::H160;
+
+/// A precompile of the standard Ethereum precompile set.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum StandardPrecompile {
+	/// Public key recovery, at `0x01`.
+	ECRecover,
+	/// SHA2-256 hash, at `0x02`.
+	Sha256,
+	/// RIPEMD-160 hash, at `0x03`.
+	Ripemd160,
+	/// Identity function, at `0x04`.
+	Identity,
+	/// Modular exponentiation, at `0x05`. See EIP-198 and EIP-2565.
+	ModExp,
+	/// Point addition on alt_bn128, at `0x06`. See EIP-196.
+	Bn128Add,
+	/// Scalar multiplication on alt_bn128, at `0x07`. See EIP-196.
+	Bn128Mul,
+	/// Pairing check on alt_bn128, at `0x08`. See EIP-197.
+	Bn128Pairing,
+	/// BLAKE2 compression function F, at `0x09`. See EIP-152.
+	Blake2F,
+	/// KZG point evaluation, at `0x0a`. See EIP-4844.
+	PointEvaluation,
+}
+
+impl StandardPrecompile {
+	/// Address the precompile lives at.
+	pub fn address(self) -> H160 {
+		let index = match self {
+			Self::ECRecover => 0x01,
+			Self::Sha256 => 0x02,
+			Self::Ripemd160 => 0x03,
+			Self::Identity => 0x04,
+			Self::ModExp => 0x05,
+			Self::Bn128Add => 0x06,
+			Self::Bn128Mul => 0x07,
+			Self::Bn128Pairing => 0x08,
+			Self::Blake2F => 0x09,
+			Self::PointEvaluation => 0x0a,
+		};
+		let mut address = H160::zero();
+		address.0[19] = index;
+		address
+	}
+}
+
+/// Standard Ethereum precompiles, with the active addresses and the gas
+/// schedule of the hard fork described by a `Config`.
+///
+/// The Byzantium precompiles are active alongside `RETURNDATASIZE` (EIP-211),
+/// and the Istanbul one alongside `CHAINID` (EIP-1344). The alt_bn128 and
+/// modexp gas schedules follow `Config::bn128_repricing` and
+/// `Config::modexp_repricing`, and the KZG point evaluation precompile
+/// `Config::has_kzg_point_evaluation`.
+#[derive(Clone, Debug)]
+pub struct StandardPrecompiles {
+	precompiles: BTreeMap<H160, StandardPrecompile>,
+	/// EIP-1108, cheaper alt_bn128 operations.
+	bn128_repricing: bool,
+	/// EIP-2565, cheaper modexp.
+	modexp_repricing: bool,
+}
+
+impl StandardPrecompiles {
+	/// Create the precompile set of the hard fork described by `config`.
+	///
+	/// # Panics
+	///
+	/// Panics if `config` has the KZG point evaluation precompile and the
+	/// `kzg` feature is not enabled.
+	pub fn new(config: &Config) -> Self {
+		let byzantium = config.has_return_data;
+		let istanbul = config.has_chain_id;
+
+		let mut active = alloc::vec![
+			StandardPrecompile::ECRecover,
+			StandardPrecompile::Sha256,
+			StandardPrecompile::Ripemd160,
+			StandardPrecompile::Identity,
+		];
+		if byzantium {
+			active.extend([
+				StandardPrecompile::ModExp,
+				StandardPrecompile::Bn128Add,
+				StandardPrecompile::Bn128Mul,
+				StandardPrecompile::Bn128Pairing,
+			]);
+		}
+		if istanbul {
+			active.push(StandardPrecompile::Blake2F);
+		}
+		if config.has_kzg_point_evaluation {
+			if !cfg!(feature = "kzg") {
+				panic!("the KZG point evaluation precompile requires the `kzg` feature");
+			}
+			active.push(StandardPrecompile::PointEvaluation);
+		}
+
+		Self {
+			precompiles: active
+				.into_iter()
+				.map(|precompile| (precompile.address(), precompile))
+				.collect(),
+			bn128_repricing: config.bn128_repricing,
+			modexp_repricing: config.modexp_repricing,
+		}
+	}
+
+	/// Addresses of the active precompiles, in ascending order.
+	pub fn addresses(&self) -> impl Iterator<Item = H160> + '_ {
+		self.precompiles.keys().copied()
+	}
+
+	/// The precompile active at `address`, if any.
+	pub fn get(&self, address: H160) -> Option<StandardPrecompile> {
+		self.precompiles.get(&address).copied()
+	}
+
+	/// Gas charged for running `precompile` on `input`.
+	pub fn cost(&self, precompile: StandardPrecompile, input: &[u8]) -> u64 {
+		match precompile {
+			StandardPrecompile::ECRecover => ecrecover::COST,
+			StandardPrecompile::Sha256 => linear_cost(input, 60, 12),
+			StandardPrecompile::Ripemd160 => linear_cost(input, 600, 120),
+			StandardPrecompile::Identity => linear_cost(input, 15, 3),
+			StandardPrecompile::ModExp => modexp::cost(input, self.modexp_repricing),
+			StandardPrecompile::Bn128Add => {
+				if self.bn128_repricing {
+					150
+				} else {
+					500
+				}
+			}
+			StandardPrecompile::Bn128Mul => {
+				if self.bn128_repricing {
+					6000
+				} else {
+					40000
+				}
+			}
+			StandardPrecompile::Bn128Pairing => {
+				let pairs = (input.len() / bn128::PAIR_LEN) as u64;
+				if self.bn128_repricing {
+					45000u64.saturating_add(pairs.saturating_mul(34000))
+				} else {
+					100_000u64.saturating_add(pairs.saturating_mul(80000))
+				}
+			}
+			StandardPrecompile::Blake2F => blake2f::cost(input),
+			StandardPrecompile::PointEvaluation => 50000,
+		}
+	}
+
+	/// Run `precompile` on `input`, without charging any gas.
+	pub fn run(&self, precompile: StandardPrecompile, input: &[u8]) -> Result<Vec<u8>, ExitError> {
+		match precompile {
+			StandardPrecompile::ECRecover => Ok(ecrecover::run(input)),
+			StandardPrecompile::Sha256 => Ok(hash::sha256(input)),
+			StandardPrecompile::Ripemd160 => Ok(hash::ripemd160(input)),
+			StandardPrecompile::Identity => Ok(input.to_vec()),
+			StandardPrecompile::ModExp => modexp::run(input),
+			StandardPrecompile::Bn128Add => bn128::add(input),
+			StandardPrecompile::Bn128Mul => bn128::mul(input),
+			StandardPrecompile::Bn128Pairing => bn128::pairing(input),
+			StandardPrecompile::Blake2F => blake2f::run(input),
+			#[cfg(feature = "kzg")]
+			StandardPrecompile::PointEvaluation => kzg::run(input),
+			#[cfg(not(feature = "kzg"))]
+			StandardPrecompile::PointEvaluation => Err(error("kzg support is not enabled")),
+		}
+	}
+}
+
+impl PrecompileSet for StandardPrecompiles {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		let precompile = self.get(handle.code_address())?;
+
+		let cost = self.cost(precompile, handle.input());
+		if let Err(error) = handle.record_cost(cost) {
+			return Some(Err(error.into()));
+		}
+
+		Some(
+			self.run(precompile, handle.input())
+				.map(|output| PrecompileOutput {
+					exit_status: ExitSucceed::Returned,
+					output,
+				})
+				.map_err(Into::into),
+		)
+	}
+
+	fn is_precompile(&self, address: H160, _: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: self.precompiles.contains_key(&address),
+			extra_cost: 0,
+		}
+	}
+}
+
+/// `base + per_word * words`, with the input rounded up to 32-byte words.
+fn linear_cost(input: &[u8], base: u64, per_word: u64) -> u64 {
+	let words = input.chunks(32).len() as u64;
+	base.saturating_add(per_word.saturating_mul(words))
+}
+
+/// `len` bytes of `input` from `offset`, right-padded with zeros.
+fn padded(input: &[u8], offset: usize, len: usize) -> Cow<'_, [u8]> {
+	let start = offset.min(input.len());
+	let end = offset.saturating_add(len).min(input.len());
+	if end - start == len {
+		Cow::Borrowed(&input[start..end])
+	} else {
+		let mut padded = alloc::vec![0u8; len];
+		padded[..end - start].copy_from_slice(&input[start..end]);
+		Cow::Owned(padded)
+	}
+}
+
+fn error(message: &'static str) -> ExitError {
+	ExitError::Other(Cow::Borrowed(message))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(config: &Config, address: u64, input: &str) -> (u64, Result<Vec<u8>, ExitError>) {
+		let precompiles = StandardPrecompiles::new(config);
+		let precompile = precompiles
+			.get(H160::from_low_u64_be(address))
+			.expect("precompile is active");
+		let input = hex::decode(input).unwrap();
+		(
+			precompiles.cost(precompile, &input),
+			precompiles.run(precompile, &input),
+		)
+	}
+
+	fn assert_output(result: (u64, Result<Vec<u8>, ExitError>), gas: u64, output: &str) {
+		assert_eq!(result.0, gas);
+		assert_eq!(hex::encode(result.1.unwrap()), output);
+	}
+
+	#[test]
+	fn active_addresses_follow_the_fork() {
+		let count = |config: &Config| StandardPrecompiles::new(config).addresses().count();
+
+		assert_eq!(count(&Config::frontier()), 4);
+		assert_eq!(count(&Config::istanbul()), 9);
+		assert_eq!(count(&Config::shanghai()), 9);
+		#[cfg(feature = "kzg")]
+		assert_eq!(count(&Config::cancun()), 10);
+	}
+
+	#[cfg(not(feature = "kzg"))]
+	#[test]
+	#[should_panic(expected = "requires the `kzg` feature")]
+	fn point_evaluation_requires_kzg() {
+		StandardPrecompiles::new(&Config::cancun());
+	}
+
+	#[test]
+	fn ecrecover() {
+		let input = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+			000000000000000000000000000000000000000000000000000000000000001b\
+			38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+			789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02";
+		assert_output(
+			run(&Config::frontier(), 1, input),
+			3000,
+			"000000000000000000000000ceaccac640adf55b2028469bd36ba501f28b699d",
+		);
+
+		// An invalid recovery id returns no output rather than failing.
+		let invalid = input.replace("001b38d1", "001d38d1");
+		assert_output(run(&Config::frontier(), 1, &invalid), 3000, "");
+	}
+
+	#[test]
+	fn hashes_and_identity() {
+		let config = Config::frontier();
+		assert_output(
+			run(&config, 2, ""),
+			60,
+			"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+		);
+		assert_output(
+			run(&config, 3, ""),
+			600,
+			"0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31",
+		);
+		assert_output(run(&config, 4, "0102030405"), 18, "0102030405");
+	}
+
+	#[test]
+	fn modexp_pricing_follows_eip2565() {
+		let input = "0000000000000000000000000000000000000000000000000000000000000001\
+			0000000000000000000000000000000000000000000000000000000000000020\
+			0000000000000000000000000000000000000000000000000000000000000020\
+			03\
+			fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+			fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+		let one = "0000000000000000000000000000000000000000000000000000000000000001";
+
+		assert_output(run(&Config::istanbul(), 5, input), 13056, one);
+		assert_output(run(&Config::berlin(), 5, input), 1360, one);
+
+		let repriced = Config {
+			modexp_repricing: true,
+			..Config::istanbul()
+		};
+		assert_output(run(&repriced, 5, input), 1360, one);
+	}
+
+	#[test]
+	fn bn128_pricing_follows_eip1108() {
+		let g1 = "0000000000000000000000000000000000000000000000000000000000000001\
+			0000000000000000000000000000000000000000000000000000000000000002";
+		let double = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+			15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+		let byzantium = Config::byzantium();
+
+		assert_output(run(&byzantium, 6, &[g1, g1].concat()), 500, double);
+		assert_output(run(&Config::istanbul(), 6, &[g1, g1].concat()), 150, double);
+		let repriced = Config {
+			bn128_repricing: true,
+			..Config::byzantium()
+		};
+		assert_output(run(&repriced, 6, &[g1, g1].concat()), 150, double);
+
+		let two = "0000000000000000000000000000000000000000000000000000000000000002";
+		assert_output(run(&byzantium, 7, &[g1, two].concat()), 40000, double);
+		assert_output(
+			run(&Config::istanbul(), 7, &[g1, two].concat()),
+			6000,
+			double,
+		);
+
+		let one = "0000000000000000000000000000000000000000000000000000000000000001";
+		assert_output(run(&byzantium, 8, ""), 100_000, one);
+		assert_output(run(&Config::istanbul(), 8, ""), 45000, one);
+
+		// A point that is not on the curve fails.
+		let (_, result) = run(
+			&Config::istanbul(),
+			6,
+			&[g1, g1].concat().replace("02", "03"),
+		);
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn blake2f() {
+		let input = "0000000c\
+			48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+			d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+			6162630000000000000000000000000000000000000000000000000000000000\
+			0000000000000000000000000000000000000000000000000000000000000000\
+			0000000000000000000000000000000000000000000000000000000000000000\
+			0000000000000000000000000000000000000000000000000000000000000000\
+			0300000000000000000000000000000001";
+		assert_output(
+			run(&Config::istanbul(), 9, input),
+			12,
+			"ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+			7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+		);
+
+		// The final block indicator must be 0 or 1.
+		let (_, result) = run(&Config::istanbul(), 9, &[&input[..424], "02"].concat());
+		assert!(result.is_err());
+	}
+
+	#[cfg(feature = "kzg")]
+	#[test]
+	fn point_evaluation() {
+		let config = Config::cancun();
+
+		let input = "01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b\
+			564c0a11a0f704f4fc3e8acfe0f8245f0ad1347b378fbf96e206da11a5d36306\
+			24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a1\
+			8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca2\
+			5f26936857bc3a7c2539ea8ec3a952b7873033e038326e87ed3e1276fd140253\
+			fa08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16a";
+		assert_output(
+			run(&config, 10, input),
+			50000,
+			"0000000000000000000000000000000000000000000000000000000000001000\
+			73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+		);
+	}
+}
@@ -0,0 +1,115 @@
+use super::{error, padded};
+use crate::ExitError;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use num_bigint::BigUint;
+use primitive_types::U256;
+
+/// Lengths of base, exponent and modulus, from the 96-byte header.
+fn lengths(input: &[u8]) -> (U256, U256, U256) {
+	let header = padded(input, 0, 96);
+	(
+		U256::from_big_endian(&header[0..32]),
+		U256::from_big_endian(&header[32..64]),
+		U256::from_big_endian(&header[64..96]),
+	)
+}
+
+/// Offset into the input, saturating when it is beyond any addressable data.
+fn offset(value: U256) -> usize {
+	usize::try_from(value).unwrap_or(usize::MAX)
+}
+
+/// Gas cost as specified by EIP-198, or EIP-2565 after Berlin. Costs that do
+/// not fit into `u64` are reported as `u64::MAX`.
+pub fn cost(input: &[u8], eip2565: bool) -> u64 {
+	let (base_len, exp_len, mod_len) = lengths(input);
+	let max_len = base_len.max(mod_len);
+
+	let complexity = if eip2565 {
+		let words = max_len / 8 + if (max_len % 8).is_zero() { 0 } else { 1 };
+		words.checked_mul(words)
+	} else if max_len <= U256::from(64) {
+		max_len.checked_mul(max_len)
+	} else if max_len <= U256::from(1024) {
+		Some(max_len * max_len / 4 + max_len * 96 - 3072)
+	} else {
+		max_len
+			.checked_mul(max_len)
+			.and_then(|square| (square / 16).checked_add(max_len.checked_mul(480.into())?))
+			.map(|complexity| complexity - 199_680)
+	};
+
+	// The exponent only matters if it is followed by a non-empty base or modulus.
+	let iterations = if max_len.is_zero() {
+		Some(U256::zero())
+	} else {
+		adjusted_exp_len(input, base_len, exp_len)
+	}
+	.map(|iterations| iterations.max(U256::one()));
+
+	let cost = match (complexity, iterations) {
+		(Some(complexity), Some(iterations)) => complexity
+			.checked_mul(iterations)
+			.map(|cost| cost / if eip2565 { 3 } else { 20 }),
+		_ => None,
+	};
+
+	match cost {
+		Some(cost) if eip2565 => u64::try_from(cost.max(200.into())).unwrap_or(u64::MAX),
+		Some(cost) => u64::try_from(cost).unwrap_or(u64::MAX),
+		None => u64::MAX,
+	}
+}
+
+/// Bit length of the exponent minus one, with every exponent byte past the
+/// first 32 counting for 8 bits.
+fn adjusted_exp_len(input: &[u8], base_len: U256, exp_len: U256) -> Option<U256> {
+	let head_len = exp_len.min(32.into()).as_usize();
+	let head = padded(input, offset(base_len).saturating_add(96), head_len);
+	let head = U256::from_big_endian(&head);
+	let head_bits = if head.is_zero() { 0 } else { head.bits() - 1 };
+
+	if exp_len <= U256::from(32) {
+		Some(head_bits.into())
+	} else {
+		(exp_len - 32)
+			.checked_mul(8.into())?
+			.checked_add(head_bits.into())
+	}
+}
+
+pub fn run(input: &[u8]) -> Result<Vec<u8>, ExitError> {
+	let (base_len, exp_len, mod_len) = lengths(input);
+	if mod_len.is_zero() {
+		return Ok(Vec::new());
+	}
+
+	// Any length this large could never have been paid for.
+	let too_large =
+		|len: U256| usize::try_from(len).map_err(|_| error("modexp input is too large"));
+	let (base_len, exp_len, mod_len) = (
+		too_large(base_len)?,
+		too_large(exp_len)?,
+		too_large(mod_len)?,
+	);
+
+	let exp_offset = base_len.saturating_add(96);
+	let mod_offset = exp_offset.saturating_add(exp_len);
+
+	let base = padded(input, 96, base_len);
+	let exponent = padded(input, exp_offset, exp_len);
+	let modulus = padded(input, mod_offset, mod_len);
+
+	let modulus = BigUint::from_bytes_be(&modulus);
+	let mut output = alloc::vec![0u8; mod_len];
+	if modulus.bits() == 0 {
+		return Ok(output);
+	}
+
+	let result = BigUint::from_bytes_be(&base)
+		.modpow(&BigUint::from_bytes_be(&exponent), &modulus)
+		.to_bytes_be();
+	output[mod_len - result.len()..].copy_from_slice(&result);
+	Ok(output)
+}
@@ -0,0 +1,200 @@
+//! Receipts of executed transactions, encoded with the envelopes of the
+//! `ethereum` crate.
+
+use super::TransactionOutcome;
+use crate::backend::Trie;
+use alloc::vec::Vec;
+pub use ethbloom::Bloom;
+use ethbloom::Input;
+use ethereum::{EIP658ReceiptData, EnvelopedEncodable, FrontierReceiptData, Log, ReceiptAny};
+use primitive_types::{H160, H256, U256};
+
+/// What a receipt commits to about the outcome of its transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReceiptStatus {
+	/// State root after the transaction, before Byzantium.
+	PostState(H256),
+	/// Whether the transaction succeeded. See EIP-658.
+	Status(bool),
+}
+
+/// Receipt of a transaction in a block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Receipt {
+	/// EIP-2718 type of the transaction, zero for legacy transactions.
+	pub transaction_type: u8,
+	/// Status, or post-state root.
+	pub status: ReceiptStatus,
+	/// Gas used by the transaction and the ones before it in the block.
+	pub cumulative_gas_used: u64,
+	/// Bloom filter of the logs.
+	pub logs_bloom: Bloom,
+	/// Logs emitted by the transaction.
+	pub logs: Vec<Log>,
+	/// Gas used by the transaction alone. Not part of the encoding.
+	pub gas_used: u64,
+	/// Address of the created contract. Not part of the encoding.
+	pub contract_address: Option<H160>,
+}
+
+impl Receipt {
+	/// The receipt as one of the envelopes of the `ethereum` crate, which has
+	/// none for the types after EIP-1559.
+	pub fn envelope(&self) -> Option<ReceiptAny> {
+		let status_code = match self.status {
+			ReceiptStatus::PostState(state_root) => {
+				return match self.transaction_type {
+					0 => Some(ReceiptAny::Frontier(FrontierReceiptData {
+						state_root,
+						used_gas: U256::from(self.cumulative_gas_used),
+						logs_bloom: self.logs_bloom,
+						logs: self.logs.clone(),
+					})),
+					_ => None,
+				};
+			}
+			ReceiptStatus::Status(success) => u8::from(success),
+		};
+
+		let data = self.eip658_data(status_code);
+		match self.transaction_type {
+			0 => Some(ReceiptAny::EIP658(data)),
+			1 => Some(ReceiptAny::EIP2930(data)),
+			2 => Some(ReceiptAny::EIP1559(data)),
+			_ => None,
+		}
+	}
+
+	/// EIP-2718 encoding of the receipt, as committed to by the receipts
+	/// root: the RLP payload, prefixed by the type of typed transactions.
+	pub fn encode(&self) -> Vec<u8> {
+		if let Some(envelope) = self.envelope() {
+			return envelope.encode().to_vec();
+		}
+
+		// Later types share the EIP-658 payload.
+		let status_code = match self.status {
+			ReceiptStatus::Status(success) => u8::from(success),
+			ReceiptStatus::PostState(_) => 0,
+		};
+		let mut out = Vec::from([self.transaction_type]);
+		out.extend_from_slice(&rlp::encode(&self.eip658_data(status_code)));
+		out
+	}
+
+	fn eip658_data(&self, status_code: u8) -> EIP658ReceiptData {
+		EIP658ReceiptData {
+			status_code,
+			used_gas: U256::from(self.cumulative_gas_used),
+			logs_bloom: self.logs_bloom,
+			logs: self.logs.clone(),
+		}
+	}
+}
+
+/// Bloom filter of logs, accruing their addresses and topics.
+pub fn logs_bloom<'a, I: IntoIterator<Item = &'a Log>>(logs: I) -> Bloom {
+	let mut bloom = Bloom::zero();
+	for log in logs {
+		bloom.accrue(Input::Raw(log.address.as_bytes()));
+		for topic in &log.topics {
+			bloom.accrue(Input::Raw(topic.as_bytes()));
+		}
+	}
+	bloom
+}
+
+/// Root of the receipts trie of a block, mapping the RLP of the index of each
+/// receipt to its EIP-2718 encoding.
+pub fn receipts_root<'a, I: IntoIterator<Item = &'a Receipt>>(receipts: I) -> H256 {
+	let mut trie = Trie::new();
+	for (index, receipt) in receipts.into_iter().enumerate() {
+		trie.insert(&rlp::encode(&index), receipt.encode());
+	}
+	trie.root()
+}
+
+/// Builds the receipts of the transactions of a block, in order.
+#[derive(Clone, Debug, Default)]
+pub struct ReceiptBuilder {
+	cumulative_gas_used: u64,
+	logs_bloom: Bloom,
+	receipts: Vec<Receipt>,
+}
+
+impl ReceiptBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add the receipt of a transaction executed since Byzantium, with the
+	/// logs it emitted.
+	pub fn push(
+		&mut self,
+		transaction_type: u8,
+		outcome: &TransactionOutcome,
+		logs: Vec<Log>,
+	) -> &Receipt {
+		let status = ReceiptStatus::Status(outcome.exit_reason.is_succeed());
+		self.push_with_status(transaction_type, status, outcome, logs)
+	}
+
+	/// Add the receipt of a transaction executed before Byzantium, with the
+	/// state root after it.
+	pub fn push_with_post_state(
+		&mut self,
+		outcome: &TransactionOutcome,
+		logs: Vec<Log>,
+		state_root: H256,
+	) -> &Receipt {
+		self.push_with_status(0, ReceiptStatus::PostState(state_root), outcome, logs)
+	}
+
+	fn push_with_status(
+		&mut self,
+		transaction_type: u8,
+		status: ReceiptStatus,
+		outcome: &TransactionOutcome,
+		logs: Vec<Log>,
+	) -> &Receipt {
+		self.cumulative_gas_used += outcome.used_gas;
+		let logs_bloom = logs_bloom(&logs);
+		self.logs_bloom.accrue_bloom(&logs_bloom);
+
+		self.receipts.push(Receipt {
+			transaction_type,
+			status,
+			cumulative_gas_used: self.cumulative_gas_used,
+			logs_bloom,
+			logs,
+			gas_used: outcome.used_gas,
+			contract_address: outcome.contract_address,
+		});
+		self.receipts.last().expect("a receipt was just pushed")
+	}
+
+	/// Gas used by the transactions so far.
+	pub fn cumulative_gas_used(&self) -> u64 {
+		self.cumulative_gas_used
+	}
+
+	/// Bloom filter of the logs of all the receipts, as in the block header.
+	pub fn logs_bloom(&self) -> Bloom {
+		self.logs_bloom
+	}
+
+	/// The receipts so far, in the order of their transactions.
+	pub fn receipts(&self) -> &[Receipt] {
+		&self.receipts
+	}
+
+	/// Root of the receipts so far, as in the block header.
+	pub fn receipts_root(&self) -> H256 {
+		receipts_root(&self.receipts)
+	}
+
+	/// The receipts, in the order of their transactions.
+	pub fn finish(self) -> Vec<Receipt> {
+		self.receipts
+	}
+}
@@ -0,0 +1,320 @@
+//! Transactions paying for their gas. See [`StackExecutor::transact`].
+//!
+//! [`StackExecutor::transact`]: super::StackExecutor::transact
+
+use super::Authorization;
+use crate::{Config, ExitError, ExitReason};
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::convert::TryFrom;
+pub use ethereum::TransactionAction;
+use ethereum::{
+	AccessList, EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionV2,
+};
+use primitive_types::{H160, H256, U256};
+
+/// Gas consumed by each blob of a transaction. See EIP-4844.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+
+/// Version byte of the versioned hash of a KZG commitment.
+pub(crate) const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// A transaction, as executed by [`StackExecutor::transact`].
+///
+/// [`StackExecutor::transact`]: super::StackExecutor::transact
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transaction {
+	/// Sender of the transaction.
+	pub caller: H160,
+	/// Nonce of the transaction, which must be the one of the sender.
+	pub nonce: U256,
+	/// Call or create.
+	pub action: TransactionAction,
+	/// Transferred value.
+	pub value: U256,
+	/// Call data, or init code for creations.
+	pub data: Vec<u8>,
+	/// Gas limit.
+	pub gas_limit: u64,
+	/// Price of gas, or maximum fee per gas of EIP-1559 transactions.
+	pub gas_price: U256,
+	/// Maximum priority fee per gas of EIP-1559 transactions. Other
+	/// transactions tip everything above the base fee.
+	pub max_priority_fee_per_gas: Option<U256>,
+	/// EIP-2930 access list.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+	/// EIP-7702 authorization list, only used by calls.
+	pub authorization_list: Vec<Authorization>,
+	/// Maximum fee per blob gas of EIP-4844 transactions.
+	pub max_fee_per_blob_gas: Option<U256>,
+	/// Versioned hashes of the blobs of EIP-4844 transactions.
+	pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl Transaction {
+	/// Blob gas consumed by the transaction.
+	pub fn blob_gas(&self) -> u64 {
+		GAS_PER_BLOB * self.blob_versioned_hashes.len() as u64
+	}
+
+	/// Price paid per unit of gas in a block of the given base fee, assuming
+	/// the transaction pays at least the base fee.
+	pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+		match self.max_priority_fee_per_gas {
+			Some(max_priority_fee) => {
+				min(self.gas_price, base_fee.saturating_add(max_priority_fee))
+			}
+			None => self.gas_price,
+		}
+	}
+}
+
+/// Reason for a transaction to be invalid. Invalid transactions cannot be
+/// included in a block: they leave the state untouched and pay no fee.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvalidTransaction {
+	/// The nonce of the transaction is lower than the one of the sender.
+	NonceTooLow { expected: U256, actual: U256 },
+	/// The nonce of the transaction is higher than the one of the sender.
+	NonceTooHigh { expected: U256, actual: U256 },
+	/// The nonce of the sender cannot be incremented anymore. See EIP-2681.
+	NonceOverflow,
+	/// The sender has code, other than an EIP-7702 delegation. See EIP-3607.
+	SenderNotEoa,
+	/// The gas limit is above the gas limit of the block.
+	GasLimitAboveBlockGasLimit {
+		gas_limit: u64,
+		block_gas_limit: U256,
+	},
+	/// The gas limit does not cover the intrinsic gas of the transaction,
+	/// or its EIP-7623 calldata floor.
+	IntrinsicGasTooLow { gas_limit: u64, intrinsic_gas: u64 },
+	/// The init code is larger than allowed. See EIP-3860.
+	InitCodeTooLarge { size: usize, max_size: usize },
+	/// The authorization list is not supported by the hard fork, or used by
+	/// a contract creation.
+	UnsupportedAuthorizationList,
+	/// The gas price is below the base fee of the block.
+	GasPriceBelowBaseFee { gas_price: U256, base_fee: U256 },
+	/// The maximum priority fee is above the maximum fee.
+	PriorityFeeAboveMaxFee {
+		max_priority_fee_per_gas: U256,
+		max_fee_per_gas: U256,
+	},
+	/// The sender cannot afford the gas limit at the gas price, the blob gas
+	/// at the maximum blob gas price, and the transferred value.
+	InsufficientBalance { required: U256, balance: U256 },
+	/// The EIP-2718 transaction type is not supported by the hard fork.
+	UnsupportedType(u8),
+	/// The transaction is signed for another chain.
+	ChainIdMismatch { expected: U256, actual: u64 },
+	/// A blob transaction creates a contract.
+	BlobContractCreation,
+	/// A blob transaction has no blobs.
+	NoBlobs,
+	/// A blob transaction has more blobs than fit in a block.
+	TooManyBlobs { count: usize, max_count: usize },
+	/// A blob versioned hash is not of a KZG commitment.
+	InvalidBlobVersionedHash(H256),
+	/// The maximum fee per blob gas is below the blob base fee of the block.
+	BlobGasPriceBelowBlobBaseFee {
+		max_fee_per_blob_gas: U256,
+		blob_base_fee: U256,
+	},
+}
+
+/// Reason for a transaction not to be executed. The state changes of the
+/// executor must be discarded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransactionError {
+	/// The transaction is invalid.
+	Invalid(InvalidTransaction),
+	/// Accessing the state failed, e.g. on an estimate of a multi-version
+	/// execution, before the transaction could be fully executed and charged.
+	Aborted(ExitError),
+}
+
+impl From<InvalidTransaction> for TransactionError {
+	fn from(invalid: InvalidTransaction) -> Self {
+		TransactionError::Invalid(invalid)
+	}
+}
+
+impl From<ExitError> for TransactionError {
+	fn from(error: ExitError) -> Self {
+		TransactionError::Aborted(error)
+	}
+}
+
+/// Outcome of a valid transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionOutcome {
+	/// Exit reason of the transaction.
+	pub exit_reason: ExitReason,
+	/// Return data of the transaction.
+	pub output: Vec<u8>,
+	/// Gas used, after refunds.
+	pub used_gas: u64,
+	/// Price paid per unit of gas.
+	pub effective_gas_price: U256,
+	/// Blob gas used, paid at the blob base fee.
+	pub blob_gas_used: u64,
+	/// Address of the created contract, for successful creations.
+	pub contract_address: Option<H160>,
+}
+
+/// EIP-4844 blob transaction, which the `ethereum` crate does not provide.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EIP4844Transaction {
+	pub chain_id: u64,
+	pub nonce: U256,
+	pub max_priority_fee_per_gas: U256,
+	pub max_fee_per_gas: U256,
+	pub gas_limit: U256,
+	/// Called address. Blob transactions cannot create contracts.
+	pub to: H160,
+	pub value: U256,
+	pub input: Vec<u8>,
+	pub access_list: AccessList,
+	pub max_fee_per_blob_gas: U256,
+	pub blob_versioned_hashes: Vec<H256>,
+	pub odd_y_parity: bool,
+	pub r: H256,
+	pub s: H256,
+}
+
+/// A transaction of one of the EIP-2718 types, as executed by
+/// [`StackExecutor::transact_typed`].
+///
+/// [`StackExecutor::transact_typed`]: super::StackExecutor::transact_typed
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TypedTransaction {
+	Legacy(LegacyTransaction),
+	EIP2930(EIP2930Transaction),
+	EIP1559(EIP1559Transaction),
+	EIP4844(EIP4844Transaction),
+}
+
+impl From<TransactionV2> for TypedTransaction {
+	fn from(transaction: TransactionV2) -> Self {
+		match transaction {
+			TransactionV2::Legacy(transaction) => Self::Legacy(transaction),
+			TransactionV2::EIP2930(transaction) => Self::EIP2930(transaction),
+			TransactionV2::EIP1559(transaction) => Self::EIP1559(transaction),
+		}
+	}
+}
+
+impl TypedTransaction {
+	/// EIP-2718 type of the transaction, zero for legacy transactions.
+	pub fn transaction_type(&self) -> u8 {
+		match self {
+			Self::Legacy(_) => 0,
+			Self::EIP2930(_) => 1,
+			Self::EIP1559(_) => 2,
+			Self::EIP4844(_) => 3,
+		}
+	}
+
+	/// Whether the hard fork described by `config` accepts transactions of
+	/// this type.
+	pub fn is_supported(&self, config: &Config) -> bool {
+		match self {
+			Self::Legacy(_) => true,
+			// Access lists came with the EIP-2929 gas changes in Berlin.
+			Self::EIP2930(_) => config.increase_state_access_gas,
+			Self::EIP1559(_) => config.has_base_fee,
+			Self::EIP4844(_) => config.has_blob_base_fee,
+		}
+	}
+
+	/// Chain the transaction is signed for. Legacy transactions only have one
+	/// with EIP-155 signatures.
+	pub fn chain_id(&self) -> Option<u64> {
+		match self {
+			Self::Legacy(transaction) => transaction.signature.chain_id(),
+			Self::EIP2930(transaction) => Some(transaction.chain_id),
+			Self::EIP1559(transaction) => Some(transaction.chain_id),
+			Self::EIP4844(transaction) => Some(transaction.chain_id),
+		}
+	}
+
+	/// The transaction, sent by `caller`.
+	pub fn to_transaction(&self, caller: H160) -> Transaction {
+		let transaction =
+			|nonce, action, value, data: &Vec<u8>, gas_limit, gas_price| Transaction {
+				caller,
+				nonce,
+				action,
+				value,
+				data: data.clone(),
+				gas_limit: to_gas_limit(gas_limit),
+				gas_price,
+				max_priority_fee_per_gas: None,
+				access_list: Vec::new(),
+				authorization_list: Vec::new(),
+				max_fee_per_blob_gas: None,
+				blob_versioned_hashes: Vec::new(),
+			};
+
+		match self {
+			Self::Legacy(t) => transaction(
+				t.nonce,
+				t.action,
+				t.value,
+				&t.input,
+				t.gas_limit,
+				t.gas_price,
+			),
+			Self::EIP2930(t) => Transaction {
+				access_list: to_access_list(&t.access_list),
+				..transaction(
+					t.nonce,
+					t.action,
+					t.value,
+					&t.input,
+					t.gas_limit,
+					t.gas_price,
+				)
+			},
+			Self::EIP1559(t) => Transaction {
+				max_priority_fee_per_gas: Some(t.max_priority_fee_per_gas),
+				access_list: to_access_list(&t.access_list),
+				..transaction(
+					t.nonce,
+					t.action,
+					t.value,
+					&t.input,
+					t.gas_limit,
+					t.max_fee_per_gas,
+				)
+			},
+			Self::EIP4844(t) => Transaction {
+				max_priority_fee_per_gas: Some(t.max_priority_fee_per_gas),
+				access_list: to_access_list(&t.access_list),
+				max_fee_per_blob_gas: Some(t.max_fee_per_blob_gas),
+				blob_versioned_hashes: t.blob_versioned_hashes.clone(),
+				..transaction(
+					t.nonce,
+					TransactionAction::Call(t.to),
+					t.value,
+					&t.input,
+					t.gas_limit,
+					t.max_fee_per_gas,
+				)
+			},
+		}
+	}
+}
+
+/// Gas limits beyond `u64` saturate, failing the block gas limit check.
+fn to_gas_limit(gas_limit: U256) -> u64 {
+	u64::try_from(gas_limit).unwrap_or(u64::MAX)
+}
+
+fn to_access_list(access_list: &AccessList) -> Vec<(H160, Vec<H256>)> {
+	access_list
+		.iter()
+		.map(|item| (item.address, item.storage_keys.clone()))
+		.collect()
+}
@@ -1,7 +1,12 @@
 //! Ethereum Virtual Machine implementation in Rust
 
 #![deny(warnings)]
-#![forbid(unsafe_code, unused_variables)]
+#![forbid(unused_variables)]
+// The BLS12-381 precompiles call into `blst`. A module cannot lift `forbid`,
+// so with that feature `unsafe` code is denied instead, and only allowed in
+// the `blst` bindings of `executor::stack::precompile::bls12_381`.
+#![cfg_attr(not(feature = "bls12-381"), forbid(unsafe_code))]
+#![cfg_attr(feature = "bls12-381", deny(unsafe_code))]
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
@@ -27,5 +32,9 @@ macro_rules! event {
 }
 
 pub mod backend;
+pub mod block;
 pub mod executor;
 pub mod maybe_borrowed;
+// Worker threads and locks need `std`.
+#[cfg(all(feature = "mvcc", feature = "std"))]
+pub mod parallel;
@@ -0,0 +1,165 @@
+//! Multi-version memory shared by all worker threads.
+//!
+//! Account and storage writes of every transaction are kept in an
+//! [`MVHashMap`], alongside the locations each transaction last wrote and the
+//! reads of its last execution. The priority fees transactions owe the
+//! coinbase are kept apart, so that paying them does not make every
+//! transaction write the coinbase account.
+
+use crate::backend::Basic;
+use crate::executor::stack::{MVHashMap, MVHashMapError, TxnIndex, Version};
+use alloc::{sync::Arc, vec::Vec};
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// A piece of state tracked by the multi-version memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Location {
+	/// Balance and nonce of an account, and whether it exists at all.
+	Basic(H160),
+	/// Code of an account.
+	Code(H160),
+	/// Marker for a storage wipe through account deletion or re-creation.
+	StorageReset(H160),
+	/// A single storage slot.
+	Storage(H160, H256),
+}
+
+impl Location {
+	pub fn address(&self) -> H160 {
+		match self {
+			Location::Basic(address)
+			| Location::Code(address)
+			| Location::StorageReset(address)
+			| Location::Storage(address, _) => *address,
+		}
+	}
+}
+
+/// Value written to a [`Location`].
+#[derive(Clone, Debug)]
+pub enum Value {
+	/// `None` means the account does not exist.
+	Basic(Option<Basic>),
+	Code(Arc<Vec<u8>>),
+	StorageReset,
+	Storage(H256),
+}
+
+/// Fee a transaction owes the coinbase.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fee {
+	/// The transaction is not executed yet, or its execution was aborted.
+	Estimate,
+	/// Nothing is owed: the transaction was not executed, or it wrote the
+	/// coinbase account and credited its fee there.
+	Settled,
+	/// Priority fee to credit to the coinbase after the transaction.
+	Owed(U256),
+}
+
+/// Fees of the transactions from `from` up to the reading one, credited to
+/// the coinbase account by a read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeRead {
+	pub from: TxnIndex,
+	pub fees: Vec<Fee>,
+}
+
+pub type ReadDescriptor = crate::executor::stack::ReadDescriptor<Location>;
+pub type MemoryRead = Result<(Version, Arc<Value>), MVHashMapError>;
+
+pub struct MvMemory {
+	data: MVHashMap<Location, Value>,
+	fees: Vec<Mutex<Fee>>,
+	last_written_locations: Vec<Mutex<Vec<Location>>>,
+	last_read_set: Vec<Mutex<(Vec<ReadDescriptor>, Vec<FeeRead>)>>,
+}
+
+impl MvMemory {
+	pub fn new(num_txns: usize) -> Self {
+		Self {
+			data: MVHashMap::new(),
+			fees: (0..num_txns).map(|_| Mutex::new(Fee::Estimate)).collect(),
+			last_written_locations: (0..num_txns).map(|_| Mutex::new(Vec::new())).collect(),
+			last_read_set: (0..num_txns)
+				.map(|_| Mutex::new((Vec::new(), Vec::new())))
+				.collect(),
+		}
+	}
+
+	/// Read `location` as seen by transaction `txn_idx`.
+	pub fn read(&self, location: &Location, txn_idx: TxnIndex) -> MemoryRead {
+		self.data.read(location, txn_idx)
+	}
+
+	/// Fees owed by the transactions of `range`.
+	pub fn fees(&self, range: Range<TxnIndex>) -> Vec<Fee> {
+		self.fees[range]
+			.iter()
+			.map(|fee| *fee.lock().unwrap())
+			.collect()
+	}
+
+	/// Store the outcome of an execution. Returns whether it wrote a location
+	/// the previous incarnation did not write.
+	pub fn record(
+		&self,
+		version: Version,
+		read_set: (Vec<ReadDescriptor>, Vec<FeeRead>),
+		write_set: Vec<(Location, Value)>,
+		fee: Fee,
+	) -> bool {
+		let (txn_idx, _) = version;
+		*self.last_read_set[txn_idx].lock().unwrap() = read_set;
+		*self.fees[txn_idx].lock().unwrap() = fee;
+
+		let locations = write_set
+			.iter()
+			.map(|(location, _)| *location)
+			.collect::<Vec<_>>();
+		for (location, value) in write_set {
+			self.data.write(&location, version, value);
+		}
+
+		let mut last_written = self.last_written_locations[txn_idx].lock().unwrap();
+		let written = locations.iter().collect::<HashSet<_>>();
+		let previous = last_written.iter().collect::<HashSet<_>>();
+
+		for location in previous.difference(&written) {
+			self.data.delete(location, txn_idx);
+		}
+		let wrote_new_location = written.difference(&previous).next().is_some();
+
+		*last_written = locations;
+		wrote_new_location
+	}
+
+	/// Replace the writes and the fee of an aborted transaction by ESTIMATE
+	/// markers.
+	pub fn convert_writes_to_estimates(&self, txn_idx: TxnIndex) {
+		for location in self.last_written_locations[txn_idx].lock().unwrap().iter() {
+			self.data.mark_estimate(location, txn_idx);
+		}
+		*self.fees[txn_idx].lock().unwrap() = Fee::Estimate;
+	}
+
+	/// Whether every read of the last execution of `txn_idx` would still
+	/// observe the same version, and every fee read the same fees.
+	pub fn validate_read_set(&self, txn_idx: TxnIndex) -> bool {
+		let read_set = self.last_read_set[txn_idx].lock().unwrap();
+		let (reads, fee_reads) = &*read_set;
+		self.data.validate_reads(txn_idx, reads)
+			&& fee_reads
+				.iter()
+				.all(|read| self.fees(read.from..txn_idx) == read.fees)
+	}
+
+	/// Final value of every written location, together with the index of the
+	/// transaction that wrote it. Only meaningful once all transactions are done.
+	pub fn snapshot(&self) -> Vec<(Location, TxnIndex, Arc<Value>)> {
+		self.data.latest_writes()
+	}
+}
@@ -0,0 +1,441 @@
+//! # Parallel block execution
+//!
+//! Block-STM style executor running an ordered list of transactions over a
+//! pool of worker threads. Transactions are executed speculatively with
+//! [`MultiversionStackExecutor`] against a multi-version memory holding the
+//! writes of lower transactions, validated against their recorded reads, and
+//! re-executed on conflicts. The committed outcome is the same as executing the
+//! transactions one after the other with `StackExecutor::transact`, applying
+//! the state changes of each valid transaction to the backend before running
+//! the next one.
+//!
+//! Priority fees are not credited to the coinbase by the transactions
+//! themselves, which would make each of them depend on the previous one. They
+//! are kept apart in the multi-version memory, credited to the coinbase when a
+//! transaction reads it, and to the block state once all transactions are done.
+
+mod memory;
+mod scheduler;
+mod view;
+
+use self::memory::{Fee, FeeRead, Location, MvMemory, ReadDescriptor, Value};
+use self::scheduler::{Scheduler, Task};
+use self::view::SpeculativeView;
+use crate::backend::{Apply, Backend, Basic, Log};
+use crate::executor::stack::{
+	MemoryStackState, MultiversionAccess, MultiversionStackExecutor, MultiversionView,
+	PrecompileSet, StackSubstateMetadata, Transaction, TransactionError, TransactionOutcome,
+	TxnIndex, Version,
+};
+use crate::{ChainSpec, Config, ExitError, ExitReason};
+use alloc::{
+	boxed::Box,
+	collections::{BTreeMap, BTreeSet},
+	sync::Arc,
+	vec::Vec,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use primitive_types::{H160, H256, U256};
+use std::sync::Mutex;
+use std::thread;
+
+/// Outcome of a single transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionOutput {
+	/// Outcome of the transaction, or the reason it was not executed, in which
+	/// case it left the state untouched.
+	pub outcome: Result<TransactionOutcome, TransactionError>,
+	/// Logs emitted by the transaction.
+	pub logs: Vec<Log>,
+}
+
+/// Outcome of a block.
+#[derive(Clone, Debug)]
+pub struct BlockOutput {
+	/// Per-transaction outcomes, in block order.
+	pub transactions: Vec<TransactionOutput>,
+	/// State changes of the whole block, to be applied to the backend.
+	pub applies: Vec<Apply>,
+	/// Number of transaction executions, including the ones discarded on
+	/// conflicts.
+	pub executions: usize,
+}
+
+enum Execution {
+	Blocked(TxnIndex),
+	Done {
+		output: Box<TransactionOutput>,
+		reads: (Vec<ReadDescriptor>, Vec<FeeRead>),
+		writes: Vec<(Location, Value)>,
+		fee: Fee,
+	},
+}
+
+/// Block-STM parallel executor.
+pub struct ParallelExecutor<'config, 'precompiles, 'backend, B, P> {
+	config: &'config Config,
+	precompile_set: &'precompiles P,
+	backend: &'backend B,
+	concurrency: usize,
+}
+
+impl<'config, 'precompiles, 'backend, B, P> ParallelExecutor<'config, 'precompiles, 'backend, B, P>
+where
+	B: Backend + Sync,
+	P: PrecompileSet + Sync,
+{
+	/// Create a new parallel executor running `concurrency` worker threads.
+	pub fn new(
+		backend: &'backend B,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+		concurrency: usize,
+	) -> Self {
+		Self {
+			config,
+			precompile_set,
+			backend,
+			concurrency: concurrency.max(1),
+		}
+	}
+
+	/// Create a new parallel executor with the configuration `chain_spec`
+	/// schedules for the block of `backend`.
+	pub fn new_with_chain_spec(
+		backend: &'backend B,
+		chain_spec: &'config ChainSpec,
+		precompile_set: &'precompiles P,
+		concurrency: usize,
+	) -> Self {
+		Self::new(
+			backend,
+			backend.config(chain_spec),
+			precompile_set,
+			concurrency,
+		)
+	}
+
+	/// Execute the transactions in order. Empty accounts are deleted unless
+	/// `Config::empty_considered_exists` is set, so `applies` of the output
+	/// should be applied with `delete_empty` set accordingly.
+	pub fn execute(&self, transactions: &[Transaction]) -> BlockOutput {
+		let scheduler = Scheduler::new(transactions.len());
+		let memory = MvMemory::new(transactions.len());
+		let outputs = transactions
+			.iter()
+			.map(|_| Mutex::new(None))
+			.collect::<Vec<Mutex<Option<TransactionOutput>>>>();
+		let executions = AtomicUsize::new(0);
+
+		thread::scope(|scope| {
+			for _ in 0..self.concurrency.min(transactions.len()) {
+				scope.spawn(|| {
+					self.run_worker(transactions, &scheduler, &memory, &outputs, &executions)
+				});
+			}
+		});
+
+		// The coinbase as seen after the last transaction.
+		let view = SpeculativeView::new(
+			self.backend,
+			&memory,
+			transactions.len(),
+			H160::default(),
+			U256::zero(),
+			&[],
+			!self.config.empty_considered_exists,
+		);
+
+		BlockOutput {
+			transactions: outputs
+				.into_iter()
+				.map(|output| {
+					output
+						.into_inner()
+						.unwrap()
+						.expect("every transaction is executed before the scheduler is done")
+				})
+				.collect(),
+			applies: merge_writes(&memory, &view),
+			executions: executions.into_inner(),
+		}
+	}
+
+	fn run_worker(
+		&self,
+		transactions: &[Transaction],
+		scheduler: &Scheduler,
+		memory: &MvMemory,
+		outputs: &[Mutex<Option<TransactionOutput>>],
+		executions: &AtomicUsize,
+	) {
+		let mut task = None;
+		while !scheduler.done() {
+			task = match task {
+				Some(Task::Execution(version)) => self.try_execute(
+					version,
+					transactions,
+					scheduler,
+					memory,
+					outputs,
+					executions,
+				),
+				Some(Task::Validation(version)) => needs_reexecution(version, scheduler, memory),
+				None => {
+					let next = scheduler.next_task();
+					if next.is_none() {
+						thread::yield_now();
+					}
+					next
+				}
+			};
+		}
+	}
+
+	fn try_execute(
+		&self,
+		version: Version,
+		transactions: &[Transaction],
+		scheduler: &Scheduler,
+		memory: &MvMemory,
+		outputs: &[Mutex<Option<TransactionOutput>>],
+		executions: &AtomicUsize,
+	) -> Option<Task> {
+		let (txn_idx, incarnation) = version;
+		loop {
+			executions.fetch_add(1, Ordering::Relaxed);
+			match self.execute_transaction(txn_idx, &transactions[txn_idx], memory) {
+				Execution::Blocked(blocking_txn_idx) => {
+					if scheduler.add_dependency(txn_idx, blocking_txn_idx) {
+						return None;
+					}
+				}
+				Execution::Done {
+					output,
+					reads,
+					writes,
+					fee,
+				} => {
+					*outputs[txn_idx].lock().unwrap() = Some(*output);
+					let wrote_new_location = memory.record(version, reads, writes, fee);
+					return scheduler.finish_execution(txn_idx, incarnation, wrote_new_location);
+				}
+			}
+		}
+	}
+
+	fn execute_transaction(
+		&self,
+		txn_idx: TxnIndex,
+		transaction: &Transaction,
+		memory: &MvMemory,
+	) -> Execution {
+		let base_fee = if self.config.has_base_fee {
+			self.backend.block_base_fee_per_gas()
+		} else {
+			U256::zero()
+		};
+		let view = SpeculativeView::new(
+			self.backend,
+			memory,
+			txn_idx,
+			transaction.caller,
+			transaction.effective_gas_price(base_fee),
+			&transaction.blob_versioned_hashes,
+			!self.config.empty_considered_exists,
+		);
+		let metadata = StackSubstateMetadata::new(transaction.gas_limit, self.config);
+		let state = MemoryStackState::new(metadata, &view);
+		let mut executor = MultiversionStackExecutor::new_with_access(
+			state,
+			self.config,
+			self.precompile_set,
+			MultiversionAccess::new(&view),
+		);
+
+		let outcome = executor.transact_deferring_fee(transaction.clone());
+		let (mut applies, logs) = executor.into_state().deconstruct();
+
+		let blocking_txn_idx = match &outcome {
+			Ok((
+				TransactionOutcome {
+					exit_reason: ExitReason::Error(ExitError::NotEstimatedYet { txn_idx, .. }),
+					..
+				},
+				_,
+			))
+			| Err(TransactionError::Aborted(ExitError::NotEstimatedYet { txn_idx, .. })) => {
+				Some(*txn_idx as TxnIndex)
+			}
+			// Estimates hit through `Backend` reads do not stop the execution.
+			_ => view.blocking_txn_idx(),
+		};
+		if let Some(blocking_txn_idx) = blocking_txn_idx {
+			return Execution::Blocked(blocking_txn_idx);
+		}
+
+		// Transactions that are not executed only keep their reads.
+		let (outcome, writes, logs, fee) = match outcome {
+			Ok((outcome, fee)) => {
+				let coinbase = self.backend.block_coinbase();
+				let fee = credit_in_place(&mut applies, coinbase, fee);
+				let delete_empty = !self.config.empty_considered_exists;
+				let writes = write_set(applies, &view, delete_empty);
+				(Ok(outcome), writes, logs, fee)
+			}
+			Err(error) => (Err(error), Vec::new(), Vec::new(), Fee::Settled),
+		};
+
+		Execution::Done {
+			output: Box::new(TransactionOutput { outcome, logs }),
+			reads: (view.take_reads(), view.take_fee_reads()),
+			writes,
+			fee,
+		}
+	}
+}
+
+fn needs_reexecution(version: Version, scheduler: &Scheduler, memory: &MvMemory) -> Option<Task> {
+	let (txn_idx, incarnation) = version;
+	let aborted =
+		!memory.validate_read_set(txn_idx) && scheduler.try_validation_abort(txn_idx, incarnation);
+	if aborted {
+		memory.convert_writes_to_estimates(txn_idx);
+	}
+	scheduler.finish_validation(txn_idx, aborted)
+}
+
+/// Credit the priority fee of a transaction to the coinbase in its own state
+/// changes if it wrote the coinbase account, as the next readers start from
+/// that write. The fee is lost with the account if the transaction deleted it.
+fn credit_in_place(applies: &mut [Apply], coinbase: H160, fee: U256) -> Fee {
+	for apply in applies {
+		match apply {
+			Apply::Modify { address, basic, .. } if *address == coinbase => {
+				basic.balance = basic.balance.saturating_add(fee);
+				return Fee::Settled;
+			}
+			Apply::Delete { address } if *address == coinbase => return Fee::Settled,
+			_ => (),
+		}
+	}
+	Fee::Owed(fee)
+}
+
+/// Translate the state changes of one transaction into multi-version writes,
+/// following the semantics of `ApplyBackend::apply` on `MemoryBackend`.
+fn write_set<B: Backend>(
+	applies: Vec<Apply>,
+	backend: &B,
+	delete_empty: bool,
+) -> Vec<(Location, Value)> {
+	fn delete(writes: &mut Vec<(Location, Value)>, address: H160) {
+		writes.push((Location::Basic(address), Value::Basic(None)));
+		writes.push((Location::Code(address), Value::Code(Arc::new(Vec::new()))));
+		writes.push((Location::StorageReset(address), Value::StorageReset));
+	}
+
+	let mut writes = Vec::new();
+	for apply in applies {
+		match apply {
+			Apply::Modify {
+				address,
+				basic,
+				code,
+				storage,
+				reset_storage,
+			} => {
+				let is_empty = basic.balance == U256::zero()
+					&& basic.nonce == U256::zero()
+					&& match &code {
+						Some(code) => code.is_empty(),
+						None => backend.code(address).is_empty(),
+					};
+
+				if is_empty && delete_empty {
+					delete(&mut writes, address);
+					continue;
+				}
+
+				writes.push((Location::Basic(address), Value::Basic(Some(basic))));
+				if let Some(code) = code {
+					writes.push((Location::Code(address), Value::Code(Arc::new(code))));
+				}
+				if reset_storage {
+					writes.push((Location::StorageReset(address), Value::StorageReset));
+				}
+				for (index, value) in storage {
+					writes.push((Location::Storage(address, index), Value::Storage(value)));
+				}
+			}
+			Apply::Delete { address } => delete(&mut writes, address),
+		}
+	}
+	writes
+}
+
+#[derive(Default)]
+struct AccountChanges {
+	basic: Option<Option<Basic>>,
+	code: Option<Arc<Vec<u8>>>,
+	reset: Option<TxnIndex>,
+	storage: Vec<(TxnIndex, H256, H256)>,
+}
+
+/// Collapse the final multi-version memory into state changes for the block,
+/// `view` being the one of a transaction after the last one.
+fn merge_writes<B: Backend>(memory: &MvMemory, view: &SpeculativeView<B>) -> Vec<Apply> {
+	let mut accounts = BTreeMap::<H160, AccountChanges>::new();
+	for (location, txn_idx, value) in memory.snapshot() {
+		let changes = accounts.entry(location.address()).or_default();
+		match (location, &*value) {
+			(Location::Basic(_), Value::Basic(basic)) => changes.basic = Some(basic.clone()),
+			(Location::Code(_), Value::Code(code)) => changes.code = Some(code.clone()),
+			(Location::StorageReset(_), Value::StorageReset) => changes.reset = Some(txn_idx),
+			(Location::Storage(_, index), Value::Storage(value)) => {
+				changes.storage.push((txn_idx, index, *value))
+			}
+			_ => (),
+		}
+	}
+
+	let fees = memory.fees(0..view.txn_idx());
+	if fees.iter().any(|fee| matches!(fee, Fee::Owed(_))) {
+		let changes = accounts.entry(view.block_coinbase()).or_default();
+		changes.basic = Some(view.coinbase());
+	}
+
+	let mut applies = Vec::new();
+	let mut deletes = BTreeSet::new();
+	for (address, changes) in accounts {
+		match changes.basic {
+			Some(Some(basic)) => {
+				let reset = changes.reset;
+				let storage = changes
+					.storage
+					.into_iter()
+					.filter(|(txn_idx, _, _)| !matches!(reset, Some(reset) if *txn_idx < reset))
+					.map(|(_, index, value)| (index, value))
+					.collect();
+
+				applies.push(Apply::Modify {
+					address,
+					basic,
+					code: changes.code.map(|code| code.as_ref().clone()),
+					storage,
+					reset_storage: reset.is_some(),
+				});
+			}
+			Some(None) => {
+				deletes.insert(address);
+			}
+			// Every transaction touching an account writes its basic information.
+			None => (),
+		}
+	}
+
+	for address in deletes {
+		applies.push(Apply::Delete { address });
+	}
+	applies
+}
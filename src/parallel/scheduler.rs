@@ -0,0 +1,241 @@
+//! Collaborative scheduler of the Block-STM algorithm.
+//!
+//! Execution and validation tasks are handed out in transaction order from two
+//! shared indices. Aborted or blocked transactions lower those indices again so
+//! that every affected transaction is (re-)executed and (re-)validated before
+//! the block is considered done.
+
+use crate::executor::stack::{Incarnation, TxnIndex, Version};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ExecutionStatus {
+	ReadyToExecute,
+	Executing,
+	Executed,
+	Aborting,
+}
+
+#[derive(Debug)]
+struct TxnStatus {
+	incarnation: Incarnation,
+	status: ExecutionStatus,
+}
+
+/// Task handed out to a worker thread.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Task {
+	Execution(Version),
+	Validation(Version),
+}
+
+pub struct Scheduler {
+	num_txns: usize,
+	execution_idx: AtomicUsize,
+	validation_idx: AtomicUsize,
+	decrease_cnt: AtomicUsize,
+	num_active_tasks: AtomicUsize,
+	done_marker: AtomicBool,
+	txn_dependency: Vec<Mutex<Vec<TxnIndex>>>,
+	txn_status: Vec<Mutex<TxnStatus>>,
+}
+
+impl Scheduler {
+	pub fn new(num_txns: usize) -> Self {
+		Self {
+			num_txns,
+			execution_idx: AtomicUsize::new(0),
+			validation_idx: AtomicUsize::new(0),
+			decrease_cnt: AtomicUsize::new(0),
+			num_active_tasks: AtomicUsize::new(0),
+			done_marker: AtomicBool::new(num_txns == 0),
+			txn_dependency: (0..num_txns).map(|_| Mutex::new(Vec::new())).collect(),
+			txn_status: (0..num_txns)
+				.map(|_| {
+					Mutex::new(TxnStatus {
+						incarnation: 0,
+						status: ExecutionStatus::ReadyToExecute,
+					})
+				})
+				.collect(),
+		}
+	}
+
+	/// Whether all transactions have been executed and validated.
+	pub fn done(&self) -> bool {
+		self.done_marker.load(Ordering::SeqCst)
+	}
+
+	/// Fetch the next task, preferring validation of lower transactions.
+	pub fn next_task(&self) -> Option<Task> {
+		if self.validation_idx.load(Ordering::SeqCst) < self.execution_idx.load(Ordering::SeqCst) {
+			self.next_version_to_validate().map(Task::Validation)
+		} else {
+			self.next_version_to_execute().map(Task::Execution)
+		}
+	}
+
+	/// Register `txn_idx` as waiting on `blocking_txn_idx`. Returns `false` if
+	/// the blocking transaction has finished executing in the meantime, in which
+	/// case the caller should re-execute right away.
+	pub fn add_dependency(&self, txn_idx: TxnIndex, blocking_txn_idx: TxnIndex) -> bool {
+		{
+			let mut dependencies = self.txn_dependency[blocking_txn_idx].lock().unwrap();
+			if self.txn_status[blocking_txn_idx].lock().unwrap().status == ExecutionStatus::Executed
+			{
+				return false;
+			}
+
+			self.txn_status[txn_idx].lock().unwrap().status = ExecutionStatus::Aborting;
+			dependencies.push(txn_idx);
+		}
+
+		self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+		true
+	}
+
+	/// Mark an execution as finished. May hand back the validation task of the
+	/// same version.
+	pub fn finish_execution(
+		&self,
+		txn_idx: TxnIndex,
+		incarnation: Incarnation,
+		wrote_new_location: bool,
+	) -> Option<Task> {
+		{
+			let mut status = self.txn_status[txn_idx].lock().unwrap();
+			debug_assert_eq!(status.status, ExecutionStatus::Executing);
+			status.status = ExecutionStatus::Executed;
+		}
+
+		let dependents = core::mem::take(&mut *self.txn_dependency[txn_idx].lock().unwrap());
+		self.resume_dependencies(dependents);
+
+		if self.validation_idx.load(Ordering::SeqCst) > txn_idx {
+			if wrote_new_location {
+				self.decrease_validation_idx(txn_idx);
+			} else {
+				return Some(Task::Validation((txn_idx, incarnation)));
+			}
+		}
+
+		self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+		None
+	}
+
+	/// Abort the given version after a failed validation. Only the first
+	/// validation failure of an incarnation succeeds.
+	pub fn try_validation_abort(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> bool {
+		let mut status = self.txn_status[txn_idx].lock().unwrap();
+		if status.incarnation == incarnation && status.status == ExecutionStatus::Executed {
+			status.status = ExecutionStatus::Aborting;
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Mark a validation as finished. May hand back the re-execution task of an
+	/// aborted transaction.
+	pub fn finish_validation(&self, txn_idx: TxnIndex, aborted: bool) -> Option<Task> {
+		if aborted {
+			self.set_ready_status(txn_idx);
+			self.decrease_validation_idx(txn_idx + 1);
+			if self.execution_idx.load(Ordering::SeqCst) > txn_idx {
+				if let Some(version) = self.try_incarnate(txn_idx) {
+					return Some(Task::Execution(version));
+				}
+			}
+		}
+
+		self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+		None
+	}
+
+	fn decrease_execution_idx(&self, target_idx: TxnIndex) {
+		self.execution_idx.fetch_min(target_idx, Ordering::SeqCst);
+		self.decrease_cnt.fetch_add(1, Ordering::SeqCst);
+	}
+
+	fn decrease_validation_idx(&self, target_idx: TxnIndex) {
+		self.validation_idx.fetch_min(target_idx, Ordering::SeqCst);
+		self.decrease_cnt.fetch_add(1, Ordering::SeqCst);
+	}
+
+	fn check_done(&self) {
+		let observed_cnt = self.decrease_cnt.load(Ordering::SeqCst);
+		let execution_idx = self.execution_idx.load(Ordering::SeqCst);
+		let validation_idx = self.validation_idx.load(Ordering::SeqCst);
+
+		if core::cmp::min(execution_idx, validation_idx) >= self.num_txns
+			&& self.num_active_tasks.load(Ordering::SeqCst) == 0
+			&& observed_cnt == self.decrease_cnt.load(Ordering::SeqCst)
+		{
+			self.done_marker.store(true, Ordering::SeqCst);
+		}
+	}
+
+	fn try_incarnate(&self, txn_idx: TxnIndex) -> Option<Version> {
+		if txn_idx < self.num_txns {
+			let mut status = self.txn_status[txn_idx].lock().unwrap();
+			if status.status == ExecutionStatus::ReadyToExecute {
+				status.status = ExecutionStatus::Executing;
+				return Some((txn_idx, status.incarnation));
+			}
+		}
+
+		None
+	}
+
+	fn next_version_to_execute(&self) -> Option<Version> {
+		if self.execution_idx.load(Ordering::SeqCst) >= self.num_txns {
+			self.check_done();
+			return None;
+		}
+
+		self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+		let idx_to_execute = self.execution_idx.fetch_add(1, Ordering::SeqCst);
+		let version = self.try_incarnate(idx_to_execute);
+		if version.is_none() {
+			self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+		}
+		version
+	}
+
+	fn next_version_to_validate(&self) -> Option<Version> {
+		if self.validation_idx.load(Ordering::SeqCst) >= self.num_txns {
+			self.check_done();
+			return None;
+		}
+
+		self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+		let idx_to_validate = self.validation_idx.fetch_add(1, Ordering::SeqCst);
+		if idx_to_validate < self.num_txns {
+			let status = self.txn_status[idx_to_validate].lock().unwrap();
+			if status.status == ExecutionStatus::Executed {
+				return Some((idx_to_validate, status.incarnation));
+			}
+		}
+
+		self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+		None
+	}
+
+	fn set_ready_status(&self, txn_idx: TxnIndex) {
+		let mut status = self.txn_status[txn_idx].lock().unwrap();
+		debug_assert_eq!(status.status, ExecutionStatus::Aborting);
+		status.incarnation += 1;
+		status.status = ExecutionStatus::ReadyToExecute;
+	}
+
+	fn resume_dependencies(&self, dependents: Vec<TxnIndex>) {
+		if let Some(min_dependent) = dependents.iter().min().copied() {
+			for txn_idx in dependents {
+				self.set_ready_status(txn_idx);
+			}
+			self.decrease_execution_idx(min_dependent);
+		}
+	}
+}
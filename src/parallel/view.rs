@@ -0,0 +1,318 @@
+//! Speculative state seen by a single transaction incarnation.
+
+use super::memory::{Fee, FeeRead, Location, MemoryRead, MvMemory, ReadDescriptor, Value};
+use crate::backend::{Backend, Basic};
+use crate::executor::stack::{MVHashMapError, MultiversionView, ReadOrigin, TxnIndex};
+use crate::ExitError;
+use alloc::{sync::Arc, vec::Vec};
+use core::cell::{Cell, RefCell};
+use primitive_types::{H160, H256, U256};
+
+/// Backend of one transaction incarnation. Account and storage values are
+/// resolved against the writes of lower transactions in the multi-version
+/// memory first and against the base backend otherwise. Every read is recorded
+/// for validation. The environment of the transaction is served alongside the
+/// one of the block.
+///
+/// The coinbase account is seen with the fees of the lower transactions
+/// credited, as they are kept out of the multi-version map.
+///
+/// Hitting an estimate marker does not stop the execution, since `Backend`
+/// reads are infallible. The blocking transaction is remembered instead, and
+/// the incarnation is discarded once it returns.
+pub struct SpeculativeView<'a, B> {
+	backend: &'a B,
+	memory: &'a MvMemory,
+	txn_idx: TxnIndex,
+	origin: H160,
+	gas_price: U256,
+	blob_hashes: &'a [H256],
+	delete_empty: bool,
+	reads: RefCell<Vec<ReadDescriptor>>,
+	fee_reads: RefCell<Vec<FeeRead>>,
+	blocking_txn_idx: Cell<Option<TxnIndex>>,
+}
+
+impl<'a, B: Backend> SpeculativeView<'a, B> {
+	pub fn new(
+		backend: &'a B,
+		memory: &'a MvMemory,
+		txn_idx: TxnIndex,
+		origin: H160,
+		gas_price: U256,
+		blob_hashes: &'a [H256],
+		delete_empty: bool,
+	) -> Self {
+		Self {
+			backend,
+			memory,
+			txn_idx,
+			origin,
+			gas_price,
+			blob_hashes,
+			delete_empty,
+			reads: RefCell::new(Vec::new()),
+			fee_reads: RefCell::new(Vec::new()),
+			blocking_txn_idx: Cell::new(None),
+		}
+	}
+
+	/// Reads of the fees credited to the coinbase account so far.
+	pub fn take_fee_reads(&self) -> Vec<FeeRead> {
+		self.fee_reads.take()
+	}
+
+	/// The coinbase account, crediting the fees owed by the transactions after
+	/// the last one that wrote it. `None` means the account does not exist.
+	pub fn coinbase(&self) -> Option<Basic> {
+		let address = self.backend.block_coinbase();
+		let (from, mut account) = match self.read_location(Location::Basic(address)) {
+			Ok(((txn_idx, _), value)) => match &*value {
+				Value::Basic(basic) => (txn_idx + 1, basic.clone()),
+				_ => (txn_idx + 1, None),
+			},
+			Err(MVHashMapError::NotFound) => {
+				let account = if self.backend.exists(address) {
+					Some(self.backend.basic(address))
+				} else {
+					None
+				};
+				(0, account)
+			}
+			Err(MVHashMapError::Dependency(_)) => return None,
+		};
+
+		let fees = self.memory.fees(from..self.txn_idx);
+		for (txn_idx, fee) in (from..).zip(&fees) {
+			match fee {
+				Fee::Estimate => {
+					self.block_on(txn_idx);
+					return None;
+				}
+				Fee::Settled => (),
+				// Like a deposit, which touches the account.
+				Fee::Owed(fee) => {
+					let mut basic = account.unwrap_or_default();
+					basic.balance = basic.balance.saturating_add(*fee);
+					let is_empty = basic.balance == U256::zero()
+						&& basic.nonce == U256::zero()
+						&& self.code(address).is_empty();
+					account = if is_empty && self.delete_empty {
+						None
+					} else {
+						Some(basic)
+					};
+				}
+			}
+		}
+		self.fee_reads.borrow_mut().push(FeeRead { from, fees });
+		account
+	}
+
+	/// The first lower transaction whose pending writes this incarnation hit.
+	pub fn blocking_txn_idx(&self) -> Option<TxnIndex> {
+		self.blocking_txn_idx.get()
+	}
+
+	fn block_on(&self, txn_idx: TxnIndex) {
+		if self.blocking_txn_idx.get().is_none() {
+			self.blocking_txn_idx.set(Some(txn_idx));
+		}
+	}
+
+	fn read_location(&self, location: Location) -> MemoryRead {
+		let read = self.memory.read(&location, self.txn_idx);
+		match &read {
+			Ok((version, _)) => self.reads.borrow_mut().push(ReadDescriptor {
+				key: location,
+				origin: ReadOrigin::Version(*version),
+			}),
+			Err(MVHashMapError::NotFound) => self.reads.borrow_mut().push(ReadDescriptor {
+				key: location,
+				origin: ReadOrigin::Storage,
+			}),
+			Err(MVHashMapError::Dependency((txn_idx, _))) => self.block_on(*txn_idx),
+		}
+		read
+	}
+
+	/// Account written by lower transactions, `None` meaning the account does
+	/// not exist. The outer `None` means the base backend value is still current.
+	fn read_basic(&self, address: H160) -> Option<Option<Basic>> {
+		match self.read_location(Location::Basic(address)) {
+			Ok((_, value)) => match &*value {
+				Value::Basic(basic) => Some(basic.clone()),
+				_ => Some(None),
+			},
+			Err(MVHashMapError::NotFound) => None,
+			Err(MVHashMapError::Dependency(_)) => Some(None),
+		}
+	}
+
+	/// Storage value written by lower transactions. `None` means the base
+	/// backend value is still current.
+	fn read_storage(&self, address: H160, index: H256) -> Option<H256> {
+		let slot = self.read_location(Location::Storage(address, index));
+		let reset = self.read_location(Location::StorageReset(address));
+
+		match (slot, reset) {
+			(Err(MVHashMapError::NotFound), Err(MVHashMapError::NotFound)) => None,
+			(Ok((_, value)), Err(MVHashMapError::NotFound)) => Some(storage_value(&value)),
+			(Ok(((slot, _), value)), Ok(((reset, _), _))) if slot >= reset => {
+				Some(storage_value(&value))
+			}
+			_ => Some(H256::default()),
+		}
+	}
+
+	fn check_estimate(&self, location: Location) -> Result<(), ExitError> {
+		if let Err(MVHashMapError::Dependency((txn_idx, incarnation))) =
+			self.memory.read(&location, self.txn_idx)
+		{
+			self.block_on(txn_idx);
+			return Err(ExitError::NotEstimatedYet {
+				txn_idx: txn_idx as u64,
+				incarnation: incarnation as u64,
+			});
+		}
+		Ok(())
+	}
+}
+
+fn storage_value(value: &Value) -> H256 {
+	match value {
+		Value::Storage(value) => *value,
+		_ => H256::default(),
+	}
+}
+
+impl<'a, B: Backend> Backend for SpeculativeView<'a, B> {
+	fn gas_price(&self) -> U256 {
+		self.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+	fn block_number(&self) -> U256 {
+		self.backend.block_number()
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.backend.block_coinbase()
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.backend.block_timestamp()
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.backend.block_base_fee_per_gas()
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.backend.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: U256) -> Option<H256> {
+		if index >= U256::from(self.blob_hashes.len()) {
+			return None;
+		}
+		Some(self.blob_hashes[index.as_usize()])
+	}
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		if address == self.backend.block_coinbase() {
+			return self.coinbase().is_some();
+		}
+		match self.read_basic(address) {
+			Some(basic) => basic.is_some(),
+			None => self.backend.exists(address),
+		}
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		if address == self.backend.block_coinbase() {
+			return self.coinbase().unwrap_or_default();
+		}
+		match self.read_basic(address) {
+			Some(basic) => basic.unwrap_or_default(),
+			None => self.backend.basic(address),
+		}
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		match self.read_location(Location::Code(address)) {
+			Ok((_, value)) => match &*value {
+				Value::Code(code) => code.as_ref().clone(),
+				_ => Vec::new(),
+			},
+			Err(MVHashMapError::NotFound) => self.backend.code(address),
+			Err(MVHashMapError::Dependency(_)) => Vec::new(),
+		}
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.read_storage(address, index)
+			.unwrap_or_else(|| self.backend.storage(address, index))
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		match self.read_storage(address, index) {
+			Some(value) => Some(value),
+			None => self.backend.original_storage(address, index),
+		}
+	}
+}
+
+impl<'a, B: Backend> MultiversionView for SpeculativeView<'a, B> {
+	type ReadDescriptor = ReadDescriptor;
+	type ReadResult = MemoryRead;
+	type TxnIdx = TxnIndex;
+
+	fn take_reads(&self) -> Vec<ReadDescriptor> {
+		self.reads.take()
+	}
+
+	/// Only reports estimate markers, here and in the account reads below. The
+	/// value itself is left to the `Backend` implementation, reached through
+	/// the executor state, so that writes buffered in the transaction's own
+	/// substate take precedence.
+	fn read(&self, address: &H160, key: &H256) -> Result<Option<Arc<H256>>, ExitError> {
+		self.check_estimate(Location::Storage(*address, *key))?;
+		self.check_estimate(Location::StorageReset(*address))?;
+		Ok(None)
+	}
+
+	fn read_basic(&self, address: &H160) -> Result<Option<Basic>, ExitError> {
+		self.check_estimate(Location::Basic(*address))?;
+		Ok(None)
+	}
+
+	fn read_code(&self, address: &H160) -> Result<Option<Arc<Vec<u8>>>, ExitError> {
+		self.check_estimate(Location::Code(*address))?;
+		Ok(None)
+	}
+
+	fn read_exists(&self, address: &H160) -> Result<Option<bool>, ExitError> {
+		self.check_estimate(Location::Basic(*address))?;
+		Ok(None)
+	}
+
+	fn txn_idx(&self) -> TxnIndex {
+		self.txn_idx
+	}
+
+	fn read_dependency(&self) -> bool {
+		self.blocking_txn_idx.get().is_some()
+	}
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use common::{account, address, vicinity};
+use ethereum::{LegacyTransaction, TransactionSignature, TransactionV2};
+use evm::backend::{Backend, MemoryBackend};
+use evm::block::{Block, BlockEnv, BlockError, BlockExecutor, Ommer, Withdrawal};
+use evm::executor::stack::{
+	EIP4844Transaction, ReceiptStatus, TransactionAction, TypedTransaction,
+};
+use evm::{Config, BEACON_ROOTS_ADDRESS};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+const BALANCE: u64 = 1_000_000_000;
+const BASE_FEE: u64 = 10;
+
+// Store the first word of the call data at the slot of the block timestamp.
+const BEACON_ROOTS: &str = "6000354255";
+// Store the first blob hash at slot zero.
+const BLOB_HASH: &str = "600049600055";
+
+fn caller() -> H160 {
+	address(1)
+}
+
+fn coinbase() -> H160 {
+	address(0xc0)
+}
+
+fn backend() -> MemoryBackend {
+	common::backend(
+		vicinity(),
+		[
+			(caller(), account(1, BALANCE, Vec::new())),
+			(
+				BEACON_ROOTS_ADDRESS,
+				account(1, 0, hex::decode(BEACON_ROOTS).unwrap()),
+			),
+			(
+				address(0x100),
+				account(1, 0, hex::decode(BLOB_HASH).unwrap()),
+			),
+		],
+	)
+}
+
+fn env() -> BlockEnv {
+	BlockEnv {
+		parent_hash: H256::repeat_byte(0x99),
+		number: U256::from(100),
+		coinbase: coinbase(),
+		timestamp: U256::from(1_000),
+		difficulty: U256::zero(),
+		randomness: Some(H256::zero()),
+		gas_limit: U256::from(100_000),
+		base_fee_per_gas: U256::from(BASE_FEE),
+		blob_base_fee: U256::one(),
+		parent_beacon_block_root: Some(H256::repeat_byte(0xbe)),
+	}
+}
+
+fn legacy(nonce: u64, gas_limit: u64) -> TypedTransaction {
+	TransactionV2::Legacy(LegacyTransaction {
+		nonce: U256::from(nonce),
+		gas_price: U256::from(20),
+		gas_limit: U256::from(gas_limit),
+		action: TransactionAction::Call(address(2)),
+		value: U256::from(100),
+		input: Vec::new(),
+		signature: TransactionSignature::new(37, H256::repeat_byte(1), H256::repeat_byte(1))
+			.unwrap(),
+	})
+	.into()
+}
+
+fn block(transactions: Vec<TypedTransaction>) -> Block {
+	Block {
+		env: env(),
+		transactions: transactions.into_iter().map(|t| (t, caller())).collect(),
+		ommers: Vec::new(),
+		withdrawals: Vec::new(),
+	}
+}
+
+fn balance(backend: &MemoryBackend, address: H160) -> U256 {
+	backend.basic(address).balance
+}
+
+#[test]
+fn transactions_run_in_order() {
+	let config = Config::london();
+	let mut backend = backend();
+	let precompiles = BTreeMap::new();
+
+	let output = BlockExecutor::new(&mut backend, &config, &precompiles)
+		.unwrap()
+		.execute(&block(vec![legacy(1, 30_000), legacy(2, 30_000)]))
+		.unwrap();
+	assert_eq!(output.gas_used, 2 * 21000);
+	let cumulative = output
+		.receipts
+		.iter()
+		.map(|receipt| receipt.cumulative_gas_used)
+		.collect::<Vec<_>>();
+	assert_eq!(cumulative, vec![21000, 2 * 21000]);
+	assert_eq!(output.receipts[1].status, ReceiptStatus::Status(true));
+
+	assert_eq!(backend.basic(caller()).nonce, U256::from(3));
+	assert_eq!(balance(&backend, address(2)), U256::from(200));
+	// The priority fees, and the block reward of 2 ether before the Merge.
+	assert_eq!(
+		balance(&backend, coinbase()),
+		U256::from(2 * 21000 * (20 - BASE_FEE)) + U256::exp10(18) * 2
+	);
+}
+
+#[test]
+fn transactions_fit_in_the_block_gas_limit() {
+	let config = Config::london();
+	let mut backend = backend();
+	let precompiles = BTreeMap::new();
+
+	let error = BlockExecutor::new(&mut backend, &config, &precompiles)
+		.unwrap()
+		.execute(&block(vec![legacy(1, 50_000), legacy(2, 80_000)]))
+		.unwrap_err();
+	assert_eq!(
+		error,
+		BlockError::GasLimitExceeded {
+			index: 1,
+			gas_limit: 80_000,
+			gas_left: U256::from(100_000 - 21000),
+		}
+	);
+}
+
+#[test]
+fn withdrawals_are_credited_since_shanghai() {
+	let withdrawal = Withdrawal {
+		index: 0,
+		validator_index: 7,
+		address: address(3),
+		amount: 5,
+	};
+	let block = Block {
+		withdrawals: vec![withdrawal],
+		..block(Vec::new())
+	};
+	let precompiles = BTreeMap::new();
+
+	let mut backend = backend();
+	assert_eq!(
+		BlockExecutor::new(&mut backend, &Config::london(), &precompiles)
+			.unwrap()
+			.execute(&block),
+		Err(BlockError::UnsupportedWithdrawals)
+	);
+
+	let output = BlockExecutor::new(&mut backend, &Config::shanghai(), &precompiles)
+		.unwrap()
+		.execute(&block)
+		.unwrap();
+	assert_eq!(output.gas_used, 0);
+	assert_eq!(balance(&backend, address(3)), U256::from(5_000_000_000u64));
+}
+
+#[test]
+fn miners_are_rewarded_before_the_merge() {
+	let ether = U256::exp10(18);
+	let forks = [
+		(Config::byzantium(), 3),
+		(Config::constantinople(), 2),
+		(Config::petersburg(), 2),
+		(Config::istanbul(), 2),
+		(Config::berlin(), 2),
+		(Config::london(), 2),
+	];
+	let precompiles = BTreeMap::new();
+	let block = Block {
+		ommers: vec![Ommer {
+			coinbase: address(4),
+			number: U256::from(99),
+		}],
+		..block(Vec::new())
+	};
+
+	for (config, reward) in forks.iter() {
+		let mut backend = backend();
+		BlockExecutor::new(&mut backend, config, &precompiles)
+			.unwrap()
+			.execute(&block)
+			.unwrap();
+		let reward = ether * U256::from(*reward);
+		assert_eq!(balance(&backend, coinbase()), reward + reward / 32);
+		assert_eq!(balance(&backend, address(4)), reward * 7 / 8);
+	}
+
+	let mut backend = self::backend();
+	BlockExecutor::new(&mut backend, &Config::merge(), &precompiles)
+		.unwrap()
+		.execute(&block)
+		.unwrap();
+	assert_eq!(balance(&backend, coinbase()), U256::zero());
+}
+
+#[test]
+fn hard_forks_before_byzantium_are_unsupported() {
+	let precompiles = BTreeMap::new();
+	let mut backend = backend();
+
+	for config in [
+		Config::frontier(),
+		Config::homestead(),
+		Config::tangerine_whistle(),
+		Config::spurious_dragon(),
+	]
+	.iter()
+	{
+		assert_eq!(config.block_reward, 5_000_000_000_000_000_000);
+		assert!(matches!(
+			BlockExecutor::new(&mut backend, config, &precompiles),
+			Err(BlockError::UnsupportedHardFork)
+		));
+	}
+}
+
+#[test]
+fn beacon_block_root_is_stored_since_cancun() {
+	let config = Config::cancun();
+	let precompiles = BTreeMap::new();
+
+	let mut backend = backend();
+	BlockExecutor::new(&mut backend, &config, &precompiles)
+		.unwrap()
+		.execute(&block(Vec::new()))
+		.unwrap();
+	assert_eq!(
+		backend.storage(BEACON_ROOTS_ADDRESS, H256::from_low_u64_be(1_000)),
+		H256::repeat_byte(0xbe)
+	);
+
+	let block = Block {
+		env: BlockEnv {
+			parent_beacon_block_root: None,
+			..env()
+		},
+		..block(Vec::new())
+	};
+	assert_eq!(
+		BlockExecutor::new(&mut backend, &config, &precompiles)
+			.unwrap()
+			.execute(&block),
+		Err(BlockError::MissingParentBeaconBlockRoot)
+	);
+}
+
+#[test]
+fn blob_hashes_are_those_of_the_transaction() {
+	let config = Config::cancun();
+	let mut backend = backend();
+	let precompiles = BTreeMap::new();
+	let mut blob_hash = H256::repeat_byte(0xab);
+	blob_hash.0[0] = 1;
+
+	let blob = TypedTransaction::EIP4844(EIP4844Transaction {
+		chain_id: 1,
+		nonce: U256::one(),
+		max_priority_fee_per_gas: U256::zero(),
+		max_fee_per_gas: U256::from(BASE_FEE),
+		gas_limit: U256::from(50_000),
+		to: address(0x100),
+		value: U256::zero(),
+		input: Vec::new(),
+		access_list: Vec::new(),
+		max_fee_per_blob_gas: U256::one(),
+		blob_versioned_hashes: vec![blob_hash],
+		odd_y_parity: false,
+		r: H256::repeat_byte(1),
+		s: H256::repeat_byte(1),
+	});
+	let output = BlockExecutor::new(&mut backend, &config, &precompiles)
+		.unwrap()
+		.execute(&block(vec![blob]))
+		.unwrap();
+	assert_eq!(backend.storage(address(0x100), H256::zero()), blob_hash);
+	assert_eq!(output.blob_gas_used, 1 << 17);
+	assert_eq!(output.receipts[0].transaction_type, 3);
+}
@@ -0,0 +1,149 @@
+#![cfg(feature = "bls12-381")]
+
+mod common;
+
+use evm::backend::MemoryBackend;
+use evm::executor::stack::{
+	Bls12381Precompile, Bls12381Precompiles, Chain, MemoryStackState, StackExecutor,
+	StackSubstateMetadata, StandardPrecompiles,
+};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, U256};
+use serde_json::Value;
+
+/// Run the EIP-2537 vectors of `fixture` against `precompile`, checking the
+/// output and gas of the successful ones, and that the failing ones fail.
+fn check_fixture(precompile: Bls12381Precompile, fixture: &str, failing: &str) {
+	let precompiles = Bls12381Precompiles::new(&Config::prague());
+
+	let vectors: Vec<Value> = serde_json::from_str(fixture).unwrap();
+	for vector in vectors {
+		let name = vector["Name"].as_str().unwrap();
+		let input = hex::decode(vector["Input"].as_str().unwrap()).unwrap();
+		let output = precompiles
+			.run(precompile, &input)
+			.unwrap_or_else(|error| panic!("{}: {:?}", name, error));
+
+		assert_eq!(
+			hex::encode(output),
+			vector["Expected"].as_str().unwrap(),
+			"{}",
+			name
+		);
+		assert_eq!(
+			precompiles.cost(precompile, &input),
+			vector["Gas"].as_u64().unwrap(),
+			"{}",
+			name
+		);
+	}
+
+	let vectors: Vec<Value> = serde_json::from_str(failing).unwrap();
+	for vector in vectors {
+		let name = vector["Name"].as_str().unwrap();
+		let input = hex::decode(vector["Input"].as_str().unwrap()).unwrap();
+		assert!(precompiles.run(precompile, &input).is_err(), "{}", name);
+	}
+}
+
+#[test]
+fn g1_add() {
+	check_fixture(
+		Bls12381Precompile::G1Add,
+		include_str!("fixtures/eip2537/add_G1_bls.json"),
+		include_str!("fixtures/eip2537/fail-add_G1_bls.json"),
+	);
+}
+
+#[test]
+fn g1_msm() {
+	check_fixture(
+		Bls12381Precompile::G1Msm,
+		include_str!("fixtures/eip2537/msm_G1_bls.json"),
+		include_str!("fixtures/eip2537/fail-msm_G1_bls.json"),
+	);
+}
+
+#[test]
+fn g2_add() {
+	check_fixture(
+		Bls12381Precompile::G2Add,
+		include_str!("fixtures/eip2537/add_G2_bls.json"),
+		include_str!("fixtures/eip2537/fail-add_G2_bls.json"),
+	);
+}
+
+#[test]
+fn g2_msm() {
+	check_fixture(
+		Bls12381Precompile::G2Msm,
+		include_str!("fixtures/eip2537/msm_G2_bls.json"),
+		include_str!("fixtures/eip2537/fail-msm_G2_bls.json"),
+	);
+}
+
+#[test]
+fn pairing_check() {
+	check_fixture(
+		Bls12381Precompile::PairingCheck,
+		include_str!("fixtures/eip2537/pairing_check_bls.json"),
+		include_str!("fixtures/eip2537/fail-pairing_check_bls.json"),
+	);
+}
+
+#[test]
+fn map_fp_to_g1() {
+	check_fixture(
+		Bls12381Precompile::MapFpToG1,
+		include_str!("fixtures/eip2537/map_fp_to_G1_bls.json"),
+		include_str!("fixtures/eip2537/fail-map_fp_to_G1_bls.json"),
+	);
+}
+
+#[test]
+fn map_fp2_to_g2() {
+	check_fixture(
+		Bls12381Precompile::MapFp2ToG2,
+		include_str!("fixtures/eip2537/map_fp2_to_G2_bls.json"),
+		include_str!("fixtures/eip2537/fail-map_fp2_to_G2_bls.json"),
+	);
+}
+
+#[test]
+fn composes_with_standard_precompiles() {
+	let config = Config {
+		has_kzg_point_evaluation: cfg!(feature = "kzg"),
+		..Config::prague()
+	};
+	let backend = MemoryBackend::new(common::vicinity(), Default::default());
+	let precompiles = Chain::new(
+		StandardPrecompiles::new(&config),
+		Bls12381Precompiles::new(&config),
+	);
+
+	let call = |address: u64, input: Vec<u8>| {
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+		let (reason, output) = executor.transact_call(
+			H160::from_low_u64_be(1),
+			H160::from_low_u64_be(address),
+			U256::zero(),
+			input,
+			100_000,
+			Vec::new(),
+		);
+		(reason, output, executor.used_gas())
+	};
+
+	// Identity, from the standard set.
+	let (reason, output, _) = call(0x04, vec![1, 2, 3]);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output, vec![1, 2, 3]);
+
+	// Multiplying the point at infinity by zero.
+	let (reason, output, used_gas) = call(0x0c, vec![0; 160]);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output, vec![0; 128]);
+	assert_eq!(used_gas, 21000 + 4 * 160 + 12000);
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Apply, Backend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, CreateScheme, ExitError, ExitReason, ExitSucceed, Opcode};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// TSTORE(0, 1), DELEGATECALL 0x200, then SSTORE(0, TLOAD(0)).
+const TSTORE_AND_DELEGATE_REVERTING: &str = "600160005d60006000600060006102005af45060005c60005500";
+// TSTORE(0, 1), DELEGATECALL 0x201, then SSTORE(0, TLOAD(0)).
+const TSTORE_AND_DELEGATE: &str = "600160005d60006000600060006102015af45060005c60005500";
+// TSTORE(0, 2), then REVERT.
+const TSTORE_AND_REVERT: &str = "600260005d60006000fd";
+// TSTORE(0, 2), then STOP.
+const TSTORE_AND_STOP: &str = "600260005d00";
+// MSTORE(0, 0xab), MCOPY(32, 0, 32), then SSTORE(0, MLOAD(32)).
+const MCOPY: &str = "60ab6000526020600060205e60205160005500";
+// SSTORE(0, BLOBHASH(1)), SSTORE(1, BLOBBASEFEE).
+const BLOB: &str = "6001496000554a60015500";
+// SELFDESTRUCT(1).
+const SELFDESTRUCT: &str = "6001ff";
+// CREATE a contract whose init code is SELFDESTRUCT(1).
+const CREATE_AND_SELFDESTRUCT: &str = "626001ff6000526003601d6000f000";
+
+fn backend() -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_blob_base_fee: U256::from(7),
+		blob_hashes: vec![H256::repeat_byte(0x11), H256::repeat_byte(0x22)],
+		..vicinity()
+	};
+
+	let mut accounts = vec![(address(1), account(0, 1_000_000, Vec::new()))];
+	for (n, code) in [
+		(0x100, TSTORE_AND_DELEGATE_REVERTING),
+		(0x101, TSTORE_AND_DELEGATE),
+		(0x200, TSTORE_AND_REVERT),
+		(0x201, TSTORE_AND_STOP),
+		(0x300, MCOPY),
+		(0x400, BLOB),
+		(0x500, SELFDESTRUCT),
+		(0x600, CREATE_AND_SELFDESTRUCT),
+	] {
+		accounts.push((address(n), account(1, 10, hex::decode(code).unwrap())));
+	}
+
+	common::backend(vicinity, accounts)
+}
+
+/// Call `target` and return the exit reason and its storage slots 0 and 1.
+fn call(config: &Config, target: u64) -> (ExitReason, H256, H256) {
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(100_000, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let (reason, _) = executor.transact_call(
+		address(1),
+		address(target),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	let state = executor.state();
+	(
+		reason,
+		state.storage(address(target), H256::from_low_u64_be(0)),
+		state.storage(address(target), H256::from_low_u64_be(1)),
+	)
+}
+
+#[test]
+fn reverted_tstore_is_discarded() {
+	let config = Config::cancun();
+
+	let (reason, value, _) = call(&config, 0x100);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(value, H256::from_low_u64_be(1));
+
+	let (reason, value, _) = call(&config, 0x101);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(value, H256::from_low_u64_be(2));
+}
+
+#[test]
+fn mcopy() {
+	let (reason, value, _) = call(&Config::cancun(), 0x300);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(value, H256::from_low_u64_be(0xab));
+
+	let (reason, _, _) = call(&Config::shanghai(), 0x300);
+	assert_eq!(
+		reason,
+		ExitReason::Error(ExitError::InvalidCode(Opcode::MCOPY))
+	);
+}
+
+#[test]
+fn blob_hash_and_blob_base_fee() {
+	let (reason, hash, fee) = call(&Config::cancun(), 0x400);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(hash, H256::repeat_byte(0x22));
+	assert_eq!(fee, H256::from_low_u64_be(7));
+}
+
+/// Call `target` and return the exit reason and the state changes to be applied.
+fn call_and_apply(config: &Config, target: u64) -> (ExitReason, Vec<Apply>) {
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(100_000, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let (reason, _) = executor.transact_call(
+		address(1),
+		address(target),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	let (applies, _) = executor.into_state().deconstruct();
+	(reason, applies)
+}
+
+fn is_deleted(applies: &[Apply], target: H160) -> bool {
+	applies
+		.iter()
+		.any(|apply| matches!(apply, Apply::Delete { address } if *address == target))
+}
+
+fn balance(applies: &[Apply], target: H160) -> Option<U256> {
+	applies.iter().find_map(|apply| match apply {
+		Apply::Modify { address, basic, .. } if *address == target => Some(basic.balance),
+		_ => None,
+	})
+}
+
+#[test]
+fn selfdestruct_of_existing_account() {
+	let (reason, applies) = call_and_apply(&Config::shanghai(), 0x500);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Suicided));
+	assert!(is_deleted(&applies, address(0x500)));
+	assert_eq!(balance(&applies, address(1)), Some(U256::from(1_000_010)));
+
+	let (reason, applies) = call_and_apply(&Config::cancun(), 0x500);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Suicided));
+	assert!(!is_deleted(&applies, address(0x500)));
+	assert_eq!(balance(&applies, address(0x500)), Some(U256::zero()));
+	assert_eq!(balance(&applies, address(1)), Some(U256::from(1_000_010)));
+}
+
+#[test]
+fn selfdestruct_in_same_transaction_as_create() {
+	let config = Config::cancun();
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+	let created = executor
+		.create_address(CreateScheme::Legacy {
+			caller: address(0x600),
+		})
+		.unwrap();
+
+	let (reason, applies) = call_and_apply(&Config::cancun(), 0x600);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert!(is_deleted(&applies, created));
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "with-codec")]
+
+mod common;
+
+use common::account;
+use evm::backend::MemoryAccount;
+use primitive_types::H256;
+use scale_codec::{Decode, Encode};
+
+#[test]
+fn memory_accounts_round_trip() {
+	let slots = (1..=16).map(|n| (H256::from_low_u64_be(n), H256::repeat_byte(n as u8)));
+	let mut account = account(3, 1_000, vec![0x60, 0x00]);
+	account.storage.extend(slots.clone());
+	let mut reversed = account.clone();
+	reversed.storage = slots.rev().collect();
+
+	let encoded = account.encode();
+	// The storage is encoded in order, whatever the order of the map.
+	assert_eq!(reversed.encode(), encoded);
+	assert_eq!(
+		MemoryAccount::decode(&mut encoded.as_slice()).unwrap(),
+		account
+	);
+}
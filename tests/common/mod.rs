@@ -0,0 +1,47 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use evm::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+use primitive_types::{H160, U256};
+
+pub fn address(n: u64) -> H160 {
+	H160::from_low_u64_be(n)
+}
+
+/// Vicinity of a block on chain 1, with everything else zeroed.
+pub fn vicinity() -> MemoryVicinity {
+	MemoryVicinity {
+		gas_price: U256::zero(),
+		origin: H160::default(),
+		block_hashes: Vec::new(),
+		block_number: Default::default(),
+		block_coinbase: Default::default(),
+		block_timestamp: Default::default(),
+		block_difficulty: Default::default(),
+		block_gas_limit: Default::default(),
+		chain_id: U256::one(),
+		block_base_fee_per_gas: U256::zero(),
+		block_blob_base_fee: U256::zero(),
+		blob_hashes: Vec::new(),
+		block_randomness: None,
+	}
+}
+
+/// Account with empty storage.
+pub fn account(nonce: u64, balance: u64, code: Vec<u8>) -> MemoryAccount {
+	MemoryAccount {
+		nonce: U256::from(nonce),
+		balance: U256::from(balance),
+		storage: hashbrown::HashMap::default(),
+		code,
+	}
+}
+
+/// Memory backend holding the given accounts.
+pub fn backend<I>(vicinity: MemoryVicinity, accounts: I) -> MemoryBackend
+where
+	I: IntoIterator<Item = (H160, MemoryAccount)>,
+{
+	MemoryBackend::new(vicinity, accounts.into_iter().collect())
+}
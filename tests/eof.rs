@@ -0,0 +1,251 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Backend, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitError, ExitReason, ExitSucceed, Opcode, NON_RETURNING};
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+
+fn eof_config() -> Config {
+	Config {
+		has_eof: true,
+		..Config::prague()
+	}
+}
+
+/// Build a container from code sections with their types, subcontainers
+/// and data.
+fn container(sections: &[(u8, u8, u16, &[u8])], containers: &[&[u8]], data: &[u8]) -> Vec<u8> {
+	let mut raw = vec![0xef, 0x00, 0x01, 0x01];
+	raw.extend_from_slice(&(sections.len() as u16 * 4).to_be_bytes());
+	raw.push(0x02);
+	raw.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+	for (_, _, _, code) in sections {
+		raw.extend_from_slice(&(code.len() as u16).to_be_bytes());
+	}
+	if !containers.is_empty() {
+		raw.push(0x03);
+		raw.extend_from_slice(&(containers.len() as u16).to_be_bytes());
+		for container in containers {
+			raw.extend_from_slice(&(container.len() as u16).to_be_bytes());
+		}
+	}
+	raw.push(0x04);
+	raw.extend_from_slice(&(data.len() as u16).to_be_bytes());
+	raw.push(0x00);
+	for (inputs, outputs, max_stack_increase, _) in sections {
+		raw.push(*inputs);
+		raw.push(*outputs);
+		raw.extend_from_slice(&max_stack_increase.to_be_bytes());
+	}
+	for (_, _, _, code) in sections {
+		raw.extend_from_slice(code);
+	}
+	for container in containers {
+		raw.extend_from_slice(container);
+	}
+	raw.extend_from_slice(data);
+	raw
+}
+
+/// A runtime container that stops, declaring two bytes of data which are
+/// only supplied as auxiliary data on deployment.
+fn deployed_container(aux_data: Option<&[u8]>) -> Vec<u8> {
+	let mut raw = container(&[(0, NON_RETURNING, 0, &[0x00])], &[], &[0, 0]);
+	raw.truncate(raw.len() - 2);
+	if let Some(aux_data) = aux_data {
+		raw.extend_from_slice(aux_data);
+	}
+	raw
+}
+
+/// An initcode container copying two bytes of calldata as the auxiliary
+/// data of the deployed container.
+fn init_container() -> Vec<u8> {
+	// CALLDATACOPY(0, 0, 2); RETURNCONTRACT[0](0, 2)
+	let code = [0x60, 0x02, 0x5f, 0x5f, 0x37, 0x60, 0x02, 0x5f, 0xee, 0x00];
+	container(
+		&[(0, NON_RETURNING, 3, &code)],
+		&[&deployed_container(None)],
+		&[],
+	)
+}
+
+fn backend(accounts: Vec<(H160, Vec<u8>)>) -> MemoryBackend {
+	common::backend(
+		vicinity(),
+		accounts
+			.into_iter()
+			.map(|(address, code)| (address, account(1, 1_000_000, code))),
+	)
+}
+
+/// Call the EOF `code` deployed at 0x100 next to `accounts`, returning the
+/// exit reason and the first `slots` storage slots of 0x100.
+fn call(
+	config: &Config,
+	code: Vec<u8>,
+	accounts: Vec<(H160, Vec<u8>)>,
+	slots: u64,
+) -> (ExitReason, Vec<H256>, MemoryBackend) {
+	let mut accounts = accounts;
+	accounts.push((address(1), Vec::new()));
+	accounts.push((address(0x100), code));
+	let backend = backend(accounts);
+
+	let gas_limit = 10_000_000;
+	let metadata = StackSubstateMetadata::new(gas_limit, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let (reason, _) = executor.transact_call(
+		address(1),
+		address(0x100),
+		U256::zero(),
+		Vec::new(),
+		gas_limit,
+		Vec::new(),
+	);
+	let values = (0..slots)
+		.map(|slot| {
+			executor
+				.state()
+				.storage(address(0x100), H256::from_low_u64_be(slot))
+		})
+		.collect();
+
+	let (values_to_apply, logs) = executor.into_state().deconstruct();
+	let mut backend = backend;
+	evm::backend::ApplyBackend::apply(&mut backend, values_to_apply, logs, false);
+	(reason, values, backend)
+}
+
+#[test]
+fn executes_functions_and_relative_jumps() {
+	// CALLF[1](5); RJUMPI(+1) if the result is 10, skipping INVALID; then
+	// SSTORE(0, result).
+	let main = [
+		0x60, 0x05, 0xe3, 0x00, 0x01, 0x80, 0x60, 0x0a, 0x14, 0xe1, 0x00, 0x01, 0xfe, 0x5f, 0x55,
+		0x00,
+	];
+	// DUP1; ADD; RETF
+	let double = [0x80, 0x01, 0xe4];
+	let code = container(
+		&[(0, NON_RETURNING, 3, &main), (1, 1, 1, &double)],
+		&[],
+		&[],
+	);
+
+	let (reason, values, _) = call(&eof_config(), code, Vec::new(), 1);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(values[0], H256::from_low_u64_be(10));
+}
+
+#[test]
+fn reads_data_section() {
+	// SSTORE(0, DATALOADN[0]); SSTORE(1, DATASIZE)
+	let main = [0xd1, 0x00, 0x00, 0x5f, 0x55, 0xd2, 0x60, 0x01, 0x55, 0x00];
+	let code = container(&[(0, NON_RETURNING, 2, &main)], &[], &[0x42; 33]);
+
+	let (reason, values, _) = call(&eof_config(), code, Vec::new(), 2);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(values[0], H256::repeat_byte(0x42));
+	assert_eq!(values[1], H256::from_low_u64_be(33));
+}
+
+#[test]
+fn rejects_invalid_containers() {
+	let invalid = vec![0xef, 0x00, 0x01, 0x00];
+	let (reason, _, _) = call(&eof_config(), invalid, Vec::new(), 0);
+	assert_eq!(
+		reason,
+		ExitReason::Error(ExitError::InvalidCode(Opcode::EOFMAGIC))
+	);
+
+	// Without EOF, containers are legacy code starting with 0xEF.
+	let code = container(&[(0, NON_RETURNING, 0, &[0x00])], &[], &[]);
+	let (reason, _, _) = call(&Config::prague(), code, Vec::new(), 0);
+	assert_eq!(
+		reason,
+		ExitReason::Error(ExitError::InvalidCode(Opcode::EOFMAGIC))
+	);
+}
+
+#[test]
+fn ext_calls_push_status() {
+	let main = [
+		// SSTORE(0, EXTCALL(0x200, 0, 0, 0)), reverting.
+		0x5f, 0x5f, 0x5f, 0x61, 0x02, 0x00, 0xf8, 0x5f, 0x55,
+		// SSTORE(1, ISZERO(EXTCALL(0x300, 0, 0, 0))), succeeding.
+		0x5f, 0x5f, 0x5f, 0x61, 0x03, 0x00, 0xf8, 0x15, 0x60, 0x01, 0x55,
+		// SSTORE(2, EXTDELEGATECALL(0x300, 0, 0)), rejecting legacy code.
+		0x5f, 0x5f, 0x61, 0x03, 0x00, 0xf9, 0x60, 0x02, 0x55,
+		// SSTORE(3, EXTCALL(0x400, 0, 0, 0)), failing.
+		0x5f, 0x5f, 0x5f, 0x61, 0x04, 0x00, 0xf8, 0x60, 0x03, 0x55, 0x00,
+	];
+	let code = container(&[(0, NON_RETURNING, 4, &main)], &[], &[]);
+	let accounts = vec![
+		(address(0x200), vec![0x5f, 0x5f, 0xfd]),
+		(address(0x300), vec![0x00]),
+		(address(0x400), vec![0xfe]),
+	];
+
+	let (reason, values, _) = call(&eof_config(), code, accounts, 4);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(values[0], H256::from_low_u64_be(1));
+	assert_eq!(values[1], H256::from_low_u64_be(1));
+	assert_eq!(values[2], H256::from_low_u64_be(1));
+	assert_eq!(values[3], H256::from_low_u64_be(2));
+}
+
+#[test]
+fn eofcreate_deploys_subcontainer() {
+	let main = [
+		// MSTORE(0, 0xabcd)
+		0x61, 0xab, 0xcd, 0x5f, 0x52,
+		// SSTORE(0, EOFCREATE[0](value: 0, salt: 7, input: memory[30..32]))
+		0x60, 0x02, 0x60, 0x1e, 0x60, 0x07, 0x5f, 0xec, 0x00, 0x5f, 0x55, 0x00,
+	];
+	let code = container(&[(0, NON_RETURNING, 4, &main)], &[&init_container()], &[]);
+
+	let (reason, values, backend) = call(&eof_config(), code, Vec::new(), 1);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+
+	let mut preimage = vec![0xff];
+	preimage.extend_from_slice(H256::from(address(0x100)).as_bytes());
+	preimage.extend_from_slice(H256::from_low_u64_be(7).as_bytes());
+	let created = H160::from(H256::from_slice(Keccak256::digest(&preimage).as_slice()));
+
+	assert_eq!(values[0], H256::from(created));
+	assert_eq!(
+		backend.code(created),
+		deployed_container(Some(&[0xab, 0xcd]))
+	);
+}
+
+#[test]
+fn creation_transaction_deploys_initcontainer() {
+	let config = eof_config();
+	let backend = backend(vec![(address(1), Vec::new())]);
+	let metadata = StackSubstateMetadata::new(1_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let created = executor
+		.create_address(evm::CreateScheme::Legacy { caller: address(1) })
+		.unwrap();
+	let mut init_code = init_container();
+	init_code.extend_from_slice(&[0xab, 0xcd]);
+	let (reason, _) =
+		executor.transact_create(address(1), U256::zero(), init_code, 1_000_000, Vec::new());
+
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(
+		executor.state().code(created),
+		deployed_container(Some(&[0xab, 0xcd]))
+	);
+}
@@ -0,0 +1,281 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{ApplyBackend, Backend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{ChainSpec, Config, ExitError, ExitReason, ExitSucceed, Opcode};
+use primitive_types::{H256, U256};
+use std::collections::BTreeMap;
+
+// EXTCODESIZE(0), BALANCE(0), SLOAD(0).
+const STATE_ACCESS: &str = "60003b50600031506000545000";
+// EXP(2, 0xffff).
+const EXP: &str = "61ffff60020a00";
+// CALL(0xffff, 0x200, 0, 0, 0, 0, 0).
+const CALL_WITH_MORE_GAS: &str = "6000600060006000600061020061fffff100";
+// CALL(0, 0x400, 0, 0, 0, 0, 0).
+const CALL_EMPTY: &str = "600060006000600060006104006000f100";
+// STATICCALL(0xffff, 0x200, 0, 0, 0, 0).
+const STATICCALL: &str = "600060006000600061020061fffffa00";
+// EXTCODEHASH(0).
+const EXTCODEHASH: &str = "60003f00";
+// PUSH0.
+const PUSH0: &str = "5f00";
+// RETURN(0, 100).
+const RETURN_100_BYTES: &str = "60646000f3";
+
+fn backend(target: Option<(&str, u64)>) -> MemoryBackend {
+	backend_at(vicinity(), target)
+}
+
+fn backend_at(vicinity: MemoryVicinity, target: Option<(&str, u64)>) -> MemoryBackend {
+	let mut accounts = vec![
+		(address(1), account(0, 1_000_000, Vec::new())),
+		(address(0x200), account(0, 1, Vec::new())),
+		(address(0x400), account(0, 0, Vec::new())),
+	];
+	if let Some((code, original)) = target {
+		let mut target = account(0, 0, hex::decode(code).unwrap());
+		if original != 0 {
+			target
+				.storage
+				.insert(H256::zero(), H256::from_low_u64_be(original));
+		}
+		accounts.push((address(0x100), target));
+	}
+
+	common::backend(vicinity, accounts)
+}
+
+/// Call `code` deployed at 0x100 with slot 0 set to `original`, returning the
+/// exit reason and the used gas, and applying the changes to the backend.
+fn call(
+	config: &Config,
+	code: &str,
+	original: u64,
+	gas_limit: u64,
+) -> (ExitReason, u64, MemoryBackend) {
+	let mut backend = backend(Some((code, original)));
+	let metadata = StackSubstateMetadata::new(gas_limit, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let (reason, _) = executor.transact_call(
+		address(1),
+		address(0x100),
+		U256::zero(),
+		Vec::new(),
+		gas_limit,
+		Vec::new(),
+	);
+	let used_gas = executor.used_gas();
+	let (values, logs) = executor.into_state().deconstruct();
+	backend.apply(values, logs, !config.empty_considered_exists);
+	(reason, used_gas, backend)
+}
+
+/// Transaction gas used by a call running `gas` of execution gas with
+/// `refund` refunded.
+fn used_gas(config: &Config, gas: u64, refund: u64) -> u64 {
+	let used = config.gas_transaction_call + gas;
+	used - std::cmp::min(used / config.max_refund_quotient, refund)
+}
+
+#[test]
+fn state_access_repricing() {
+	// Three PUSH1 and three POP around EXTCODESIZE, BALANCE and SLOAD.
+	for (config, gas) in [
+		(Config::homestead(), 9 + 6 + 20 + 20 + 50),
+		(Config::tangerine_whistle(), 9 + 6 + 700 + 400 + 200),
+		(Config::istanbul(), 9 + 6 + 700 + 700 + 800),
+	] {
+		let (reason, used, _) = call(&config, STATE_ACCESS, 0, 100_000);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(used, used_gas(&config, gas, 0));
+	}
+}
+
+#[test]
+fn exp_byte_repricing() {
+	for (config, gas) in [
+		(Config::tangerine_whistle(), 6 + 10 + 2 * 10),
+		(Config::spurious_dragon(), 6 + 10 + 2 * 50),
+	] {
+		let (reason, used, _) = call(&config, EXP, 0, 100_000);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(used, used_gas(&config, gas, 0));
+	}
+}
+
+#[test]
+fn call_with_more_gas_than_available() {
+	let (reason, _, _) = call(&Config::homestead(), CALL_WITH_MORE_GAS, 0, 50_000);
+	assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+
+	// EIP-150 caps the call gas at all but one 64th of the available gas.
+	let config = Config::tangerine_whistle();
+	let (reason, used, _) = call(&config, CALL_WITH_MORE_GAS, 0, 50_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(used, used_gas(&config, 7 * 3 + 700, 0));
+}
+
+#[test]
+fn touched_empty_account_is_cleared() {
+	let (reason, _, backend) = call(&Config::tangerine_whistle(), CALL_EMPTY, 0, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert!(backend.state().contains_key(&address(0x400)));
+
+	let (reason, _, backend) = call(&Config::spurious_dragon(), CALL_EMPTY, 0, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert!(!backend.state().contains_key(&address(0x400)));
+}
+
+#[test]
+fn static_call_requires_byzantium() {
+	let (reason, _, _) = call(&Config::spurious_dragon(), STATICCALL, 0, 100_000);
+	assert_eq!(
+		reason,
+		ExitReason::Error(ExitError::InvalidCode(Opcode::STATICCALL))
+	);
+
+	let (reason, _, _) = call(&Config::byzantium(), STATICCALL, 0, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+}
+
+#[test]
+fn ext_code_hash_requires_constantinople() {
+	let (reason, _, _) = call(&Config::byzantium(), EXTCODEHASH, 0, 100_000);
+	assert_eq!(
+		reason,
+		ExitReason::Error(ExitError::InvalidCode(Opcode::EXTCODEHASH))
+	);
+
+	let config = Config::constantinople();
+	let (reason, used, _) = call(&config, EXTCODEHASH, 0, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(used, used_gas(&config, 3 + 400, 0));
+}
+
+/// EIP-1283 test cases, as `(code, original, gas, refund)`.
+const EIP1283_CASES: [(&str, u64, u64, u64); 17] = [
+	("60006000556000600055", 0, 412, 0),
+	("60006000556001600055", 0, 20212, 0),
+	("60016000556000600055", 0, 20212, 19800),
+	("60016000556002600055", 0, 20212, 0),
+	("60016000556001600055", 0, 20212, 0),
+	("60006000556000600055", 1, 5212, 15000),
+	("60006000556001600055", 1, 5212, 4800),
+	("60006000556002600055", 1, 5212, 0),
+	("60026000556000600055", 1, 5212, 15000),
+	("60026000556003600055", 1, 5212, 0),
+	("60026000556001600055", 1, 5212, 4800),
+	("60026000556002600055", 1, 5212, 0),
+	("60016000556000600055", 1, 5212, 15000),
+	("60016000556002600055", 1, 5212, 0),
+	("60016000556001600055", 1, 412, 0),
+	("600160005560006000556001600055", 0, 40218, 19800),
+	("600060005560016000556000600055", 1, 10218, 19800),
+];
+
+#[test]
+fn constantinople_net_gas_metering() {
+	let config = Config::constantinople();
+	for (code, original, gas, refund) in EIP1283_CASES {
+		let (reason, used, _) = call(&config, code, original, 100_000);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(used, used_gas(&config, gas, refund), "{}", code);
+	}
+}
+
+#[test]
+fn petersburg_removes_net_gas_metering() {
+	// Every SSTORE costs 20000 to set a zero slot and 5000 otherwise, and
+	// clearing a slot refunds 15000.
+	let config = Config::petersburg();
+	for (code, original, gas, refund) in [
+		("60006000556000600055", 0, 10012, 0),
+		("60016000556000600055", 0, 25012, 15000),
+		("60006000556001600055", 1, 25012, 15000),
+		("600160005560006000556001600055", 0, 45018, 15000),
+	] {
+		let (reason, used, _) = call(&config, code, original, 100_000);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(used, used_gas(&config, gas, refund), "{}", code);
+	}
+}
+
+/// Create a contract returning 100 bytes of code with enough gas for the
+/// init code but not for the code deposit.
+fn create_without_deposit_gas(config: &Config) -> (ExitReason, Option<Vec<u8>>) {
+	let backend = backend(None);
+	let init_code = hex::decode(RETURN_100_BYTES).unwrap();
+	// Four non-zero bytes and a zero byte, then four words of memory and two PUSH1.
+	let gas_limit = config.gas_transaction_create
+		+ 4 * config.gas_transaction_non_zero_data
+		+ config.gas_transaction_zero_data
+		+ 12 + 6 + 1000;
+	let metadata = StackSubstateMetadata::new(gas_limit, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+	let created = executor
+		.create_address(evm::CreateScheme::Legacy { caller: address(1) })
+		.unwrap();
+
+	let (reason, _) =
+		executor.transact_create(address(1), U256::zero(), init_code, gas_limit, Vec::new());
+	let state = executor.state();
+	let code = if state.exists(created) {
+		Some(state.code(created))
+	} else {
+		None
+	};
+	(reason, code)
+}
+
+#[test]
+fn code_deposit_out_of_gas() {
+	let (reason, code) = create_without_deposit_gas(&Config::frontier());
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(code, Some(Vec::new()));
+
+	let (reason, _) = create_without_deposit_gas(&Config::homestead());
+	assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+}
+
+#[test]
+fn chain_spec_resolves_config_from_backend() {
+	let mainnet = ChainSpec::mainnet();
+	for (timestamp, reason) in [
+		(
+			1_681_338_443,
+			ExitReason::Error(ExitError::InvalidCode(Opcode::PUSH0)),
+		),
+		(1_681_338_455, ExitReason::Succeed(ExitSucceed::Stopped)),
+	] {
+		let backend = backend_at(
+			MemoryVicinity {
+				block_number: U256::from(17_034_869),
+				block_timestamp: U256::from(timestamp),
+				..vicinity()
+			},
+			Some((PUSH0, 0)),
+		);
+		let config = backend.config(&mainnet);
+		let metadata = StackSubstateMetadata::new(100_000, config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+		let (exit_reason, _) = executor.transact_call(
+			address(1),
+			address(0x100),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		assert_eq!(exit_reason, reason);
+	}
+}
@@ -0,0 +1,257 @@
+#![cfg(feature = "mvcc")]
+
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{ApplyBackend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{
+	MemoryStackState, StackExecutor, StackSubstateMetadata, Transaction, TransactionAction,
+};
+use evm::parallel::{ParallelExecutor, TransactionOutput};
+use evm::Config;
+use primitive_types::{H256, U256};
+use std::collections::{BTreeMap, HashMap};
+
+// SLOAD(0) + 1, stored back to slot 0 and emitted as LOG0.
+const COUNTER: &str = "6000546001018060005560005260206000a000";
+// SELFDESTRUCT to the caller.
+const SUICIDE: &str = "33ff";
+// SSTORE(0, 1), returning empty code.
+const INIT_STORE: &str = "600160005500";
+// SSTORE(ORIGIN, GASPRICE), then SSTORE(GASPRICE, ORIGIN).
+const ENVIRONMENT: &str = "3a3255323a5500";
+// SSTORE(0, BALANCE(COINBASE)).
+const COINBASE_BALANCE: &str = "413160005500";
+
+fn coinbase() -> u64 {
+	0xc0
+}
+
+fn backend() -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_coinbase: address(coinbase()),
+		block_gas_limit: U256::from(30_000_000),
+		..vicinity()
+	};
+
+	let mut accounts: Vec<_> = (1..=8)
+		.map(|n| (address(n), account(0, 10_000_000, Vec::new())))
+		.collect();
+	accounts.push((address(0x100), account(1, 0, hex::decode(COUNTER).unwrap())));
+	accounts.push((
+		address(0x200),
+		account(1, 500, hex::decode(SUICIDE).unwrap()),
+	));
+	accounts.push((
+		address(0x400),
+		account(1, 0, hex::decode(COINBASE_BALANCE).unwrap()),
+	));
+	accounts.push((address(coinbase()), account(0, 10_000_000, Vec::new())));
+
+	common::backend(vicinity, accounts)
+}
+
+fn transaction(caller: u64, action: TransactionAction, value: u64, data: &str) -> Transaction {
+	Transaction {
+		caller: address(caller),
+		nonce: U256::zero(),
+		action,
+		value: U256::from(value),
+		data: hex::decode(data).unwrap(),
+		gas_limit: 100_000,
+		gas_price: U256::from(2),
+		max_priority_fee_per_gas: None,
+		access_list: Vec::new(),
+		authorization_list: Vec::new(),
+		max_fee_per_blob_gas: None,
+		blob_versioned_hashes: Vec::new(),
+	}
+}
+
+fn call(caller: u64, target: u64, value: u64) -> Transaction {
+	transaction(caller, TransactionAction::Call(address(target)), value, "")
+}
+
+fn transactions() -> Vec<Transaction> {
+	let mut transactions = Vec::new();
+	for round in 0..4 {
+		for caller in 1..=8 {
+			transactions.push(call(caller, 0x100, 0));
+			transactions.push(call(caller, (caller % 8) + 1, 10 + round));
+		}
+		transactions.push(transaction(
+			round + 1,
+			TransactionAction::Create,
+			7,
+			INIT_STORE,
+		));
+		transactions.push(call(round + 2, 0x200, 3));
+		transactions.push(call(round + 3, 0x300, 0));
+		// The coinbase is read, and written, with the fees of the previous
+		// transactions credited.
+		transactions.push(call(round + 4, 0x400, 0));
+		transactions.push(call(coinbase(), round + 5, 1));
+	}
+
+	let mut nonces = HashMap::new();
+	for transaction in &mut transactions {
+		let nonce = nonces.entry(transaction.caller).or_insert(0u64);
+		transaction.nonce = U256::from(*nonce);
+		*nonce += 1;
+	}
+	// A nonce reused by a later transaction of the block makes it invalid.
+	transactions.push(call(1, 0x100, 0));
+	transactions
+}
+
+fn execute_sequential(
+	backend: &mut MemoryBackend,
+	config: &Config,
+	transactions: &[Transaction],
+) -> Vec<TransactionOutput> {
+	let precompiles = BTreeMap::new();
+	transactions
+		.iter()
+		.map(|transaction| {
+			let metadata = StackSubstateMetadata::new(transaction.gas_limit, config);
+			let state = MemoryStackState::new(metadata, &*backend);
+			let mut executor =
+				StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+			let outcome = executor.transact(transaction.clone());
+			let (applies, logs) = executor.into_state().deconstruct();
+			if outcome.is_err() {
+				return TransactionOutput {
+					outcome,
+					logs: Vec::new(),
+				};
+			}
+
+			backend.apply(applies, logs.clone(), !config.empty_considered_exists);
+			TransactionOutput { outcome, logs }
+		})
+		.collect()
+}
+
+fn assert_matches_sequential(config: &Config, concurrency: usize) {
+	let transactions = transactions();
+
+	let mut expected_backend = backend();
+	let expected = execute_sequential(&mut expected_backend, config, &transactions);
+
+	let mut backend = backend();
+	let precompiles = BTreeMap::new();
+	let output =
+		ParallelExecutor::new(&backend, config, &precompiles, concurrency).execute(&transactions);
+
+	assert_eq!(output.transactions, expected);
+	assert!(expected[..expected.len() - 1]
+		.iter()
+		.all(|output| output.outcome.is_ok()));
+	assert!(expected[expected.len() - 1].outcome.is_err());
+	backend.apply(output.applies, Vec::new(), !config.empty_considered_exists);
+	assert_eq!(backend.state(), expected_backend.state());
+	assert_eq!(
+		backend.state()[&address(0x100)].storage[&H256::zero()],
+		H256::from_low_u64_be(32)
+	);
+}
+
+#[test]
+fn parallel_matches_sequential() {
+	for concurrency in [1, 2, 4, 8] {
+		assert_matches_sequential(&Config::london(), concurrency);
+		assert_matches_sequential(&Config::frontier(), concurrency);
+	}
+}
+
+#[test]
+fn transactions_see_their_own_environment() {
+	let base_fee = U256::from(7);
+	let vicinity = MemoryVicinity {
+		gas_price: U256::from(1),
+		origin: address(0x99),
+		block_gas_limit: U256::from(30_000_000),
+		block_base_fee_per_gas: base_fee,
+		..vicinity()
+	};
+	let mut accounts: Vec<_> = (1..=8)
+		.map(|n| (address(n), account(0, 100_000_000, Vec::new())))
+		.collect();
+	accounts.push((
+		address(0x300),
+		account(1, 0, hex::decode(ENVIRONMENT).unwrap()),
+	));
+	let mut backend = common::backend(vicinity, accounts);
+
+	// Legacy and EIP-1559 transactions, paying different gas prices.
+	let transactions = (1..=8)
+		.map(|caller| Transaction {
+			gas_price: U256::from(100 + caller),
+			max_priority_fee_per_gas: if caller % 2 == 0 {
+				Some(U256::from(caller))
+			} else {
+				None
+			},
+			..call(caller, 0x300, 0)
+		})
+		.collect::<Vec<_>>();
+
+	let config = Config::london();
+	let precompiles = BTreeMap::new();
+	let output = ParallelExecutor::new(&backend, &config, &precompiles, 4).execute(&transactions);
+	for output in &output.transactions {
+		assert!(output.outcome.as_ref().unwrap().exit_reason.is_succeed());
+	}
+	backend.apply(output.applies, Vec::new(), true);
+
+	let storage = &backend.state()[&address(0x300)].storage;
+	for transaction in &transactions {
+		let origin = H256::from(transaction.caller);
+		let gas_price = H256::from_low_u64_be(transaction.effective_gas_price(base_fee).as_u64());
+		assert_eq!(storage[&origin], gas_price);
+		assert_eq!(storage[&gas_price], origin);
+	}
+}
+
+#[test]
+fn independent_transactions_are_executed_once() {
+	let vicinity = MemoryVicinity {
+		block_coinbase: address(coinbase()),
+		block_gas_limit: U256::from(30_000_000),
+		..vicinity()
+	};
+	let accounts = (1..=64).map(|n| (address(n), account(0, 10_000_000, Vec::new())));
+	let backend = common::backend(vicinity, accounts);
+
+	// Every transaction pays a fee to the coinbase, and nothing else is shared.
+	let transactions = (1..=64)
+		.map(|caller| call(caller, 0x1000 + caller, 1))
+		.collect::<Vec<_>>();
+
+	let config = Config::london();
+	let precompiles = BTreeMap::new();
+	let output = ParallelExecutor::new(&backend, &config, &precompiles, 8).execute(&transactions);
+	assert_eq!(output.executions, transactions.len());
+
+	let mut expected_backend = backend.clone();
+	let expected = execute_sequential(&mut expected_backend, &config, &transactions);
+	assert_eq!(output.transactions, expected);
+	let mut backend = backend;
+	backend.apply(output.applies, Vec::new(), true);
+	assert_eq!(backend.state(), expected_backend.state());
+	assert_eq!(
+		backend.state()[&address(coinbase())].balance,
+		U256::from(64 * 21000 * 2)
+	);
+}
+
+#[test]
+fn empty_block() {
+	let backend = backend();
+	let precompiles = BTreeMap::new();
+	let config = Config::london();
+	let output = ParallelExecutor::new(&backend, &config, &precompiles, 4).execute(&[]);
+
+	assert!(output.transactions.is_empty());
+	assert!(output.applies.is_empty());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Backend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{
+	Config, ExitError, ExitReason, ExitSucceed, HISTORY_SERVE_WINDOW, HISTORY_STORAGE_ADDRESS,
+};
+use primitive_types::{H256, U256};
+use std::collections::BTreeMap;
+
+// SSTORE(0, BLOCKHASH(9990)).
+const BLOCKHASH: &str = "6127064060005500";
+
+const BLOCK_NUMBER: u64 = 10_000;
+
+fn backend() -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_number: U256::from(BLOCK_NUMBER),
+		..vicinity()
+	};
+
+	let mut history = account(1, 1_000_000, Vec::new());
+	for number in BLOCK_NUMBER - 300..BLOCK_NUMBER {
+		history.storage.insert(
+			H256::from_low_u64_be(number % HISTORY_SERVE_WINDOW),
+			H256::from_low_u64_be(number),
+		);
+	}
+
+	common::backend(
+		vicinity,
+		[
+			(address(1), account(1, 1_000_000, Vec::new())),
+			(
+				address(0x100),
+				account(1, 1_000_000, hex::decode(BLOCKHASH).unwrap()),
+			),
+			(HISTORY_STORAGE_ADDRESS, history),
+		],
+	)
+}
+
+/// Call `target`, returning the exit reason, the used gas and its storage slot 0.
+fn call(config: &Config, target: u64, data: Vec<u8>, gas_limit: u64) -> (ExitReason, u64, H256) {
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(gas_limit, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let (reason, _) = executor.transact_call(
+		address(1),
+		address(target),
+		U256::zero(),
+		data,
+		gas_limit,
+		Vec::new(),
+	);
+	let used_gas = executor.used_gas();
+	let value = executor.state().storage(address(target), H256::zero());
+	(reason, used_gas, value)
+}
+
+#[test]
+fn blockhash_reads_history_storage() {
+	let (reason, _, hash) = call(&Config::prague(), 0x100, Vec::new(), 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(hash, H256::from_low_u64_be(9990));
+
+	let (reason, _, hash) = call(&Config::cancun(), 0x100, Vec::new(), 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(hash, H256::zero());
+}
+
+#[test]
+fn calldata_floor() {
+	// 1000 non-zero bytes cost 21000 + 16 * 1000 = 37000 gas, and are
+	// floored at 21000 + 10 * 4 * 1000 = 61000 gas.
+	let data = vec![0xff; 1000];
+
+	let (reason, used_gas, _) = call(&Config::cancun(), 0x200, data.clone(), 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(used_gas, 37_000);
+
+	let (reason, used_gas, _) = call(&Config::prague(), 0x200, data.clone(), 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(used_gas, 61_000);
+
+	let (reason, _, _) = call(&Config::cancun(), 0x200, data.clone(), 50_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+
+	let (reason, _, _) = call(&Config::prague(), 0x200, data, 50_000);
+	assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::{address, vicinity};
+use evm::backend::MemoryBackend;
+use evm::executor::stack::{
+	AddressRange, Chain, ClosurePrecompiles, DenyList, ForkGated, IsPrecompileResult,
+	MemoryStackState, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput,
+	PrecompileResult, PrecompileSet, StackExecutor, StackSubstateMetadata,
+};
+use evm::{Config, Context, ExitReason, ExitSucceed, ForkActivation};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+fn backend() -> MemoryBackend {
+	MemoryBackend::new(vicinity(), Default::default())
+}
+
+/// Call `target` through `precompiles`, returning the exit reason and output.
+fn call<P: PrecompileSet>(precompiles: &P, target: H160) -> (ExitReason, Vec<u8>) {
+	let config = Config::cancun();
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, precompiles, false);
+
+	executor.transact_call(
+		address(1),
+		target,
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	)
+}
+
+fn returned(output: &[u8]) -> (ExitReason, Vec<u8>) {
+	(ExitReason::Succeed(ExitSucceed::Returned), output.to_vec())
+}
+
+fn not_a_precompile() -> (ExitReason, Vec<u8>) {
+	(ExitReason::Succeed(ExitSucceed::Stopped), Vec::new())
+}
+
+fn output(byte: u8) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+	Ok((
+		PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			output: vec![byte],
+		},
+		0,
+	))
+}
+
+fn first(
+	_: &[u8],
+	_: Option<u64>,
+	_: &Context,
+	_: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+	output(1)
+}
+
+fn second(
+	_: &[u8],
+	_: Option<u64>,
+	_: &Context,
+	_: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+	output(2)
+}
+
+/// Answers with a fixed `extra_cost` for checking any address.
+struct Costly {
+	is_precompile: bool,
+	extra_cost: u64,
+}
+
+impl PrecompileSet for Costly {
+	fn execute(&self, _: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		None
+	}
+
+	fn is_precompile(&self, _: H160, remaining_gas: u64) -> IsPrecompileResult {
+		if remaining_gas < self.extra_cost {
+			return IsPrecompileResult::OutOfGas;
+		}
+		IsPrecompileResult::Answer {
+			is_precompile: self.is_precompile,
+			extra_cost: self.extra_cost,
+		}
+	}
+}
+
+#[test]
+fn chain_gives_precedence_to_the_first_set() {
+	let mut a: BTreeMap<H160, PrecompileFn> = BTreeMap::new();
+	a.insert(address(0x10), first);
+	let mut b: BTreeMap<H160, PrecompileFn> = BTreeMap::new();
+	b.insert(address(0x10), second);
+	b.insert(address(0x11), second);
+	let chain = Chain::new(a, b);
+
+	assert_eq!(call(&chain, address(0x10)), returned(&[1]));
+	assert_eq!(call(&chain, address(0x11)), returned(&[2]));
+	assert_eq!(call(&chain, address(0x12)), not_a_precompile());
+}
+
+#[test]
+fn chain_adds_up_extra_costs() {
+	let chain = |first: Costly, second: Costly| Chain::new(first, second);
+	let costly = |is_precompile, extra_cost| Costly {
+		is_precompile,
+		extra_cost,
+	};
+
+	// The second set is not asked when the first one answers.
+	assert_eq!(
+		chain(costly(true, 100), costly(true, 50)).is_precompile(address(0x10), 1000),
+		IsPrecompileResult::Answer {
+			is_precompile: true,
+			extra_cost: 100,
+		}
+	);
+	assert_eq!(
+		chain(costly(false, 100), costly(true, 50)).is_precompile(address(0x10), 1000),
+		IsPrecompileResult::Answer {
+			is_precompile: true,
+			extra_cost: 150,
+		}
+	);
+	assert_eq!(
+		chain(costly(false, 100), costly(false, 50)).is_precompile(address(0x10), 1000),
+		IsPrecompileResult::Answer {
+			is_precompile: false,
+			extra_cost: 150,
+		}
+	);
+
+	// The second set only gets the gas left after the first check.
+	assert_eq!(
+		chain(costly(false, 100), costly(false, 50)).is_precompile(address(0x10), 120),
+		IsPrecompileResult::OutOfGas
+	);
+	assert_eq!(
+		chain(costly(false, 100), costly(false, 50)).is_precompile(address(0x10), 50),
+		IsPrecompileResult::OutOfGas
+	);
+}
+
+#[test]
+fn closures_keep_state() {
+	let calls = Arc::new(AtomicU64::new(0));
+	let counter = calls.clone();
+
+	let precompiles = ClosurePrecompiles::new().with(address(0x10), move |handle| {
+		handle.record_cost(100)?;
+		let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			output: vec![count as u8],
+		})
+	});
+
+	assert_eq!(call(&precompiles, address(0x10)), returned(&[1]));
+	assert_eq!(call(&precompiles, address(0x10)), returned(&[2]));
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn address_range_covers_its_addresses() {
+	let range = AddressRange::new(address(0x1000)..=address(0x10ff), |handle| {
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			output: handle.code_address().as_bytes()[18..].to_vec(),
+		})
+	});
+
+	assert_eq!(call(&range, address(0x1000)), returned(&[0x10, 0x00]));
+	assert_eq!(call(&range, address(0x10ff)), returned(&[0x10, 0xff]));
+	assert_eq!(call(&range, address(0x1100)), not_a_precompile());
+	assert_eq!(
+		range.is_precompile(address(0x0fff), 0),
+		IsPrecompileResult::Answer {
+			is_precompile: false,
+			extra_cost: 0,
+		}
+	);
+}
+
+#[test]
+fn fork_gated_sets_follow_activation() {
+	let set = || {
+		let mut set: BTreeMap<H160, PrecompileFn> = BTreeMap::new();
+		set.insert(address(0x10), first);
+		set
+	};
+
+	let active = ForkGated::new(
+		set(),
+		ForkActivation::Timestamp(100),
+		U256::zero(),
+		U256::from(100),
+	);
+	assert!(active.is_active());
+	assert_eq!(call(&active, address(0x10)), returned(&[1]));
+
+	let inactive = ForkGated::new(
+		set(),
+		ForkActivation::Timestamp(100),
+		U256::zero(),
+		U256::from(99),
+	);
+	assert_eq!(call(&inactive, address(0x10)), not_a_precompile());
+
+	let gated = ForkGated::with_config(set(), &Config::cancun(), |config| {
+		config.has_authorization_list
+	});
+	assert_eq!(
+		gated.is_precompile(address(0x10), 0),
+		IsPrecompileResult::Answer {
+			is_precompile: false,
+			extra_cost: 0,
+		}
+	);
+}
+
+#[test]
+fn deny_list_disables_addresses() {
+	let mut set: BTreeMap<H160, PrecompileFn> = BTreeMap::new();
+	set.insert(address(0x10), first);
+	set.insert(address(0x11), first);
+	let set = DenyList::new(set, [address(0x11)]);
+
+	assert_eq!(call(&set, address(0x10)), returned(&[1]));
+	assert_eq!(call(&set, address(0x11)), not_a_precompile());
+	assert_eq!(
+		set.is_precompile(address(0x11), 0),
+		IsPrecompileResult::Answer {
+			is_precompile: false,
+			extra_cost: 0,
+		}
+	);
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::{account, address, vicinity};
+use ethbloom::Input;
+use evm::backend::{MemoryBackend, MemoryVicinity, EMPTY_TRIE_ROOT};
+use evm::executor::stack::{
+	logs_bloom, receipts_root, Log, MemoryStackState, Receipt, ReceiptBuilder, ReceiptStatus,
+	StackExecutor, StackSubstateMetadata, Transaction, TransactionAction,
+};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// LOG1 with topic 0xff and no data.
+const LOGGER: &str = "60ff60006000a100";
+
+fn caller() -> H160 {
+	address(1)
+}
+
+fn logger() -> H160 {
+	address(0x100)
+}
+
+fn backend() -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_gas_limit: U256::from(30_000_000),
+		..vicinity()
+	};
+
+	common::backend(
+		vicinity,
+		[
+			(caller(), account(0, 1_000_000_000, Vec::new())),
+			(logger(), account(1, 0, hex::decode(LOGGER).unwrap())),
+		],
+	)
+}
+
+fn transaction(nonce: u64, action: TransactionAction) -> Transaction {
+	Transaction {
+		caller: caller(),
+		nonce: U256::from(nonce),
+		action,
+		value: U256::zero(),
+		data: Vec::new(),
+		gas_limit: 100_000,
+		gas_price: U256::from(1),
+		max_priority_fee_per_gas: Some(U256::zero()),
+		access_list: Vec::new(),
+		authorization_list: Vec::new(),
+		max_fee_per_blob_gas: None,
+		blob_versioned_hashes: Vec::new(),
+	}
+}
+
+#[test]
+fn receipts_accumulate_gas_and_logs() {
+	let config = Config::london();
+	let backend = backend();
+	let precompiles = BTreeMap::new();
+	let mut receipts = ReceiptBuilder::new();
+
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+	let call = executor
+		.transact(transaction(0, TransactionAction::Call(logger())))
+		.unwrap();
+	let create = executor
+		.transact(transaction(1, TransactionAction::Create))
+		.unwrap();
+	let (_, logs) = executor.into_state().deconstruct();
+	let logs = logs.into_iter().collect::<Vec<_>>();
+	assert_eq!(logs.len(), 1);
+
+	let first = receipts.push(2, &call, logs).clone();
+	assert_eq!(first.status, ReceiptStatus::Status(true));
+	assert_eq!(first.cumulative_gas_used, 21000 + 3 * 3 + 375 + 375);
+	assert!(first
+		.logs_bloom
+		.contains_input(Input::Raw(logger().as_bytes())));
+	assert!(first
+		.logs_bloom
+		.contains_input(Input::Raw(H256::from_low_u64_be(0xff).as_bytes())));
+	assert!(!first
+		.logs_bloom
+		.contains_input(Input::Raw(caller().as_bytes())));
+	// Typed receipts are prefixed by their type.
+	assert_eq!(first.encode()[0], 2);
+
+	let second = receipts.push(2, &create, Vec::new()).clone();
+	assert_eq!(
+		second.cumulative_gas_used,
+		first.cumulative_gas_used + create.used_gas
+	);
+	assert!(second.contract_address.is_some());
+	assert_eq!(second.contract_address, create.contract_address);
+	assert!(second.logs_bloom.is_empty());
+
+	assert_eq!(receipts.logs_bloom(), first.logs_bloom);
+	assert_eq!(receipts.receipts(), [first.clone(), second.clone()]);
+	assert_eq!(
+		receipts.receipts_root(),
+		receipts_root(&[first.clone(), second.clone()])
+	);
+	assert_eq!(receipts.finish(), vec![first, second]);
+}
+
+#[test]
+fn receipt_encodings() {
+	let config = Config::london();
+	let backend = backend();
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+	let outcome = executor
+		.transact(transaction(0, TransactionAction::Call(address(2))))
+		.unwrap();
+
+	// An empty bloom, and no logs.
+	let payload = |status: &[u8]| {
+		let mut stream = rlp::RlpStream::new_list(4);
+		stream.append_raw(status, 1);
+		stream.append(&U256::from(21000));
+		stream.append(&[0u8; 256].as_ref());
+		stream.begin_list(0);
+		stream.out().to_vec()
+	};
+
+	let mut receipts = ReceiptBuilder::new();
+	let legacy = receipts.push(0, &outcome, Vec::new()).encode();
+	assert_eq!(legacy, payload(&rlp::encode(&1u8)));
+
+	let mut receipts = ReceiptBuilder::new();
+	let blob = receipts.push(3, &outcome, Vec::new()).encode();
+	assert_eq!(blob, [&[3u8][..], &payload(&rlp::encode(&1u8))].concat());
+
+	let mut receipts = ReceiptBuilder::new();
+	let state_root = H256::repeat_byte(0x11);
+	let frontier = receipts
+		.push_with_post_state(&outcome, Vec::new(), state_root)
+		.encode();
+	assert_eq!(frontier, payload(&rlp::encode(&state_root)));
+}
+
+#[test]
+fn receipts_roots() {
+	let receipt = |transaction_type, success, cumulative_gas_used, logs: Vec<Log>| Receipt {
+		transaction_type,
+		status: ReceiptStatus::Status(success),
+		cumulative_gas_used,
+		logs_bloom: logs_bloom(&logs),
+		logs,
+		gas_used: 0,
+		contract_address: None,
+	};
+	let root = |hex: &str| H256::from_str(hex).unwrap();
+
+	assert_eq!(receipts_root(&[]), EMPTY_TRIE_ROOT);
+
+	// Blocks with a single successful transfer, legacy or EIP-1559, on
+	// mainnet and in the ethereum/tests blockchain tests.
+	assert_eq!(
+		receipts_root(&[receipt(0, true, 21000, Vec::new())]),
+		root("056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2")
+	);
+	assert_eq!(
+		receipts_root(&[receipt(2, true, 21000, Vec::new())]),
+		root("f78dfb743fbd92ade140711c8bbc542b5e307f0ab7984eff35d751969fe57efa")
+	);
+
+	// Computed independently of this crate.
+	let log = Log {
+		address: address(1),
+		topics: vec![H256::from_low_u64_be(0xff)],
+		data: Vec::new(),
+	};
+	let receipts = [
+		receipt(0, true, 21000, Vec::new()),
+		receipt(1, false, 50000, Vec::new()),
+		receipt(2, true, 80000, vec![log]),
+	];
+	assert_eq!(
+		receipts_root(&receipts),
+		root("724deb9ea84aeddb7b946041b23e5fe2d08b3f616df6563fbfb25a6f5b34ff37")
+	);
+}
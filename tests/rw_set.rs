@@ -0,0 +1,124 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::MemoryBackend;
+use evm::executor::stack::{
+	MemoryStackState, RwSet, StackExecutor, StackSubstateMetadata, StateKey, StateValue,
+};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// SSTORE(0, 1), then REVERT.
+const STORE_AND_REVERT: &str = "600160005560006000fd";
+// SSTORE(1, 1), then CALL 0x100 and STOP.
+const STORE_AND_CALL: &str = "6001600155600060006000600060006101005af100";
+
+fn backend() -> MemoryBackend {
+	let mut accounts: Vec<_> = (1..=3)
+		.map(|n| (address(n), account(0, 1_000_000, Vec::new())))
+		.collect();
+	accounts.push((
+		address(0x100),
+		account(1, 0, hex::decode(STORE_AND_REVERT).unwrap()),
+	));
+	accounts.push((
+		address(0x200),
+		account(1, 0, hex::decode(STORE_AND_CALL).unwrap()),
+	));
+
+	common::backend(vicinity(), accounts)
+}
+
+fn transfer_rw_set(backend: &MemoryBackend, caller: H160, target: H160) -> RwSet {
+	let config = Config::london();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, true);
+
+	let _reason = executor.transact_call(
+		caller,
+		target,
+		U256::from(100),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	executor.rw_set().unwrap().clone()
+}
+
+#[test]
+fn transfers_are_tracked() {
+	let backend = backend();
+	let rw_set = transfer_rw_set(&backend, address(1), address(0x10));
+
+	assert_eq!(
+		rw_set.reads().get(&StateKey::Balance(address(1))),
+		Some(&StateValue::Balance(U256::from(1_000_000)))
+	);
+	assert_eq!(
+		rw_set.writes().get(&StateKey::Balance(address(1))),
+		Some(&StateValue::Balance(U256::from(999_900)))
+	);
+	assert_eq!(
+		rw_set.writes().get(&StateKey::Balance(address(0x10))),
+		Some(&StateValue::Balance(U256::from(100)))
+	);
+	assert_eq!(
+		rw_set.writes().get(&StateKey::Nonce(address(1))),
+		Some(&StateValue::Nonce(U256::one()))
+	);
+	assert_eq!(
+		rw_set.writes().get(&StateKey::Exists(address(0x10))),
+		Some(&StateValue::Exists(true))
+	);
+}
+
+#[test]
+fn transfers_to_the_same_account_conflict() {
+	let backend = backend();
+	let first = transfer_rw_set(&backend, address(1), address(0x10));
+	let second = transfer_rw_set(&backend, address(2), address(0x10));
+	let third = transfer_rw_set(&backend, address(3), address(0x20));
+
+	assert!(first.conflicts_with(&second));
+	assert!(!first.conflicts_with(&third));
+	assert!(!second.conflicts_with(&third));
+}
+
+#[test]
+fn reverted_writes_are_dropped() {
+	let backend = backend();
+	let rw_set = transfer_rw_set(&backend, address(1), address(0x100));
+
+	assert!(!rw_set
+		.writes()
+		.contains_key(&StateKey::Storage(address(0x100), H256::zero())));
+	assert!(!rw_set
+		.writes()
+		.contains_key(&StateKey::Balance(address(0x100))));
+	assert_eq!(
+		rw_set.writes().get(&StateKey::Nonce(address(1))),
+		Some(&StateValue::Nonce(U256::one()))
+	);
+	assert!(rw_set
+		.reads()
+		.contains_key(&StateKey::Balance(address(0x100))));
+}
+
+#[test]
+fn writes_of_committed_calls_are_kept() {
+	let backend = backend();
+	let rw_set = transfer_rw_set(&backend, address(1), address(0x200));
+
+	assert_eq!(
+		rw_set
+			.writes()
+			.get(&StateKey::Storage(address(0x200), H256::from_low_u64_be(1))),
+		Some(&StateValue::Storage(H256::from_low_u64_be(1)))
+	);
+	assert!(!rw_set
+		.writes()
+		.contains_key(&StateKey::Storage(address(0x100), H256::zero())));
+}
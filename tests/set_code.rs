@@ -0,0 +1,167 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Apply, MemoryBackend};
+use evm::executor::stack::{Authorization, MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{delegation_designator, Config, ExitError, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+// SSTORE(0, 0x2a).
+const STORE: &str = "602a60005500";
+// CALL(0, 0x20, 0, 0, 0, 0, 0).
+const CALL: &str = "6000600060006000600060206000f100";
+
+/// Test recovery taking the authority from the low bytes of `r`.
+fn recover_from_r(authorization: &Authorization) -> Option<H160> {
+	Some(H160::from(authorization.r))
+}
+
+fn authorization(authority: u64, delegate: u64, nonce: u64) -> Authorization {
+	Authorization {
+		chain_id: U256::one(),
+		address: address(delegate),
+		nonce,
+		y_parity: false,
+		r: address(authority).into(),
+		s: H256::from_low_u64_be(1),
+	}
+}
+
+fn backend() -> MemoryBackend {
+	common::backend(
+		vicinity(),
+		[
+			(1, Vec::new()),
+			(0x10, hex::decode(CALL).unwrap()),
+			(0x20, delegation_designator(address(0x30))),
+			(0x30, Vec::new()),
+			(0x100, hex::decode(STORE).unwrap()),
+		]
+		.map(|(n, code)| (address(n), account(1, 1_000_000, code))),
+	)
+}
+
+/// Call `target` with the authorization list, returning the exit reason, the
+/// used gas and the state changes.
+fn call(
+	config: &Config,
+	target: u64,
+	authorization_list: Vec<Authorization>,
+) -> (ExitReason, u64, Vec<Apply>) {
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(200_000, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+	executor.set_authority_recovery(recover_from_r);
+
+	let (reason, _) = executor.transact_call_with_authorizations(
+		address(1),
+		address(target),
+		U256::zero(),
+		Vec::new(),
+		200_000,
+		Vec::new(),
+		authorization_list,
+	);
+	let used_gas = executor.used_gas();
+	let (applies, _) = executor.into_state().deconstruct();
+	(reason, used_gas, applies)
+}
+
+fn modified(applies: &[Apply], target: H160) -> Option<(U256, Option<Vec<u8>>, Option<H256>)> {
+	applies.iter().find_map(|apply| match apply {
+		Apply::Modify {
+			address,
+			basic,
+			code,
+			storage,
+			..
+		} if *address == target => Some((
+			basic.nonce,
+			code.clone(),
+			storage.get(&H256::zero()).copied(),
+		)),
+		_ => None,
+	})
+}
+
+#[test]
+fn authorization_delegates_the_authority() {
+	let (reason, _, applies) = call(&Config::prague(), 0xa, vec![authorization(0xa, 0x100, 0)]);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+
+	let (nonce, code, value) = modified(&applies, address(0xa)).unwrap();
+	assert_eq!(nonce, U256::one());
+	assert_eq!(code, Some(delegation_designator(address(0x100))));
+	assert_eq!(value, Some(H256::from_low_u64_be(0x2a)));
+}
+
+#[test]
+fn invalid_authorizations_are_skipped() {
+	let mut wrong_chain = authorization(0xa, 0x100, 0);
+	wrong_chain.chain_id = U256::from(2);
+	let mut high_s = authorization(0xa, 0x100, 0);
+	high_s.s = H256::repeat_byte(0xff);
+
+	for authorization in [
+		authorization(0xa, 0x100, 1),
+		authorization(0x100, 0x100, 1),
+		wrong_chain,
+		high_s,
+	] {
+		let (reason, used_gas, applies) = call(&Config::prague(), 0xa, vec![authorization]);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(used_gas, 21000 + 25000);
+		assert_eq!(modified(&applies, address(0xa)).and_then(|m| m.1), None);
+	}
+}
+
+#[test]
+fn existing_authority_is_refunded() {
+	// The authority 0x30 exists, so 12500 of the 25000 are refunded, capped
+	// at a fifth of the used gas.
+	let (reason, used_gas, _) = call(&Config::prague(), 0xa, vec![authorization(0x30, 0x100, 1)]);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(used_gas, 46000 - 46000 / 5);
+}
+
+#[test]
+fn call_to_delegating_account_pays_for_the_delegation_target() {
+	let (reason, delegating, _) = call(&Config::prague(), 0x10, Vec::new());
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+
+	let config = Config::cancun();
+	let (reason, plain, _) = call(&config, 0x10, Vec::new());
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(delegating - plain, 2600);
+}
+
+#[test]
+fn authorization_list_requires_eip7702() {
+	let (reason, _, _) = call(&Config::cancun(), 0xa, vec![authorization(0xa, 0x100, 0)]);
+	assert!(matches!(reason, ExitReason::Error(ExitError::Other(_))));
+}
+
+#[cfg(feature = "precompiles")]
+#[test]
+fn recover_authority() {
+	use k256::ecdsa::SigningKey;
+	use sha3::{Digest, Keccak256};
+
+	let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+	let public = key.verifying_key().to_encoded_point(false);
+	let authority = H160::from_slice(&Keccak256::digest(&public.as_bytes()[1..])[12..]);
+
+	let mut authorization = authorization(0, 0x100, 0);
+	let (signature, recovery_id) = key
+		.sign_prehash_recoverable(authorization.signing_hash().as_bytes())
+		.unwrap();
+	let bytes = signature.to_bytes();
+	authorization.r = H256::from_slice(&bytes[..32]);
+	authorization.s = H256::from_slice(&bytes[32..]);
+	authorization.y_parity = recovery_id.is_y_odd();
+
+	assert_eq!(authorization.recover_authority(), Some(authority));
+}
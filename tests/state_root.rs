@@ -0,0 +1,151 @@
+mod common;
+
+use common::{account, vicinity};
+use evm::backend::{Apply, ApplyBackend, Basic, MemoryBackend, StateTrie, EMPTY_TRIE_ROOT};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// Store the call value at slot one, and clear slot two.
+const STORE: &str = "346001556000600255";
+
+const ETHER: u64 = 1_000_000_000_000_000_000;
+
+fn sender() -> H160 {
+	H160::from_str("a94f5374fce5edbc8e2a8697c15331677e6ebf0b").unwrap()
+}
+
+fn contract() -> H160 {
+	H160::from_str("095e7baea6a6c7c4c2dfeb977efac326af552d87").unwrap()
+}
+
+fn root(hex: &str) -> H256 {
+	H256::from_str(hex).unwrap()
+}
+
+// Pre-state in the style of the ethereum/tests state tests.
+fn backend() -> MemoryBackend {
+	let mut contract_account = account(0, ETHER, hex::decode(STORE).unwrap());
+	contract_account
+		.storage
+		.insert(H256::from_low_u64_be(1), H256::from_low_u64_be(0xff));
+	contract_account
+		.storage
+		.insert(H256::from_low_u64_be(2), H256::from_low_u64_be(0xee));
+
+	common::backend(
+		vicinity(),
+		[
+			(sender(), account(0, ETHER, Vec::new())),
+			(contract(), contract_account),
+		],
+	)
+}
+
+#[test]
+fn empty_state() {
+	let mut backend = MemoryBackend::new(vicinity(), hashbrown::HashMap::default());
+	assert_eq!(backend.state_root(), EMPTY_TRIE_ROOT);
+	assert_eq!(backend.storage_root(sender()), EMPTY_TRIE_ROOT);
+	assert_eq!(StateTrie::new().root(), EMPTY_TRIE_ROOT);
+}
+
+// The expected roots are computed independently of this crate, from the
+// definitions of the yellow paper.
+#[test]
+fn known_roots() {
+	let mut backend = backend();
+	assert_eq!(
+		backend.state_root(),
+		root("b924d3d10017fd9b33e5dd19d9e9adb7fcde1457535ca99fc4b4b28bcba977e0")
+	);
+	assert_eq!(
+		backend.storage_root(contract()),
+		root("2c50a152cad3a7b3db7f39b3532805eff6a27973960ad3712eb88d12e5b721aa")
+	);
+	assert_eq!(backend.storage_root(sender()), EMPTY_TRIE_ROOT);
+}
+
+#[test]
+fn roots_are_updated_incrementally() {
+	let config = Config::cancun();
+	let mut backend = backend();
+	let pre_state_root = backend.state_root();
+
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+	let (reason, _) = executor.transact_call(
+		sender(),
+		contract(),
+		U256::from(7),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	assert!(reason.is_succeed());
+	let (values, logs) = executor.into_state().deconstruct();
+
+	let mut trie = StateTrie::from_memory(backend.state());
+	assert_eq!(trie.root(), pre_state_root);
+	trie.apply(&values, true);
+	backend.apply(values, logs, true);
+
+	let post_state_root = root("f61c1e3518a3ebc3f8399f415be42245f6e1e09195dc34fdee51044c62559c39");
+	assert_eq!(backend.state_root(), post_state_root);
+	assert_eq!(trie.root(), post_state_root);
+	assert_eq!(
+		backend.storage_root(contract()),
+		root("2e7827dc2c61c322f13f77e6f25dd18844ccc48426dde70301d2d57d138fced8")
+	);
+	assert_eq!(
+		StateTrie::from_memory(backend.state()).root(),
+		post_state_root
+	);
+}
+
+#[test]
+fn empty_and_deleted_accounts_leave_the_trie() {
+	let values = vec![
+		Apply::Modify {
+			address: H160::from_low_u64_be(0x200),
+			basic: Basic::default(),
+			code: None,
+			storage: BTreeMap::new(),
+			reset_storage: false,
+		},
+		Apply::Delete {
+			address: contract(),
+		},
+	];
+
+	let mut backend = backend();
+	let pre_state_root = backend.state_root();
+	backend.apply(values.clone(), Vec::new(), false);
+	assert_eq!(
+		backend.state_root(),
+		StateTrie::from_memory(backend.state()).root()
+	);
+	assert_ne!(backend.state_root(), pre_state_root);
+	assert_eq!(backend.storage_root(contract()), EMPTY_TRIE_ROOT);
+
+	let mut backend = self::backend();
+	// Build the tries before the changes, so that they are applied to them.
+	backend.state_root();
+	backend.apply(values[..1].to_vec(), Vec::new(), true);
+	assert_eq!(backend.state_root(), pre_state_root);
+}
+
+#[test]
+fn direct_state_changes_rebuild_the_tries() {
+	let mut backend = backend();
+	let pre_state_root = backend.state_root();
+
+	backend.state_mut().get_mut(&sender()).unwrap().nonce = U256::one();
+	assert_ne!(backend.state_root(), pre_state_root);
+	backend.state_mut().get_mut(&sender()).unwrap().nonce = U256::zero();
+	assert_eq!(backend.state_root(), pre_state_root);
+}
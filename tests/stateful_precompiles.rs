@@ -0,0 +1,156 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Backend, MemoryBackend};
+use evm::executor::stack::{
+	ClosurePrecompiles, MemoryStackState, PrecompileOutput, StackExecutor, StackSubstateMetadata,
+	StateKey, StateValue,
+};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H256, U256};
+
+// STATICCALL 0x10 without input, then return its success flag.
+const STATIC_CALL_COUNTER: &str = "600060006000600060105afa60005260206000f3";
+
+fn word(value: U256) -> Vec<u8> {
+	let mut word = vec![0; 32];
+	value.to_big_endian(&mut word);
+	word
+}
+
+fn backend() -> MemoryBackend {
+	common::backend(
+		vicinity(),
+		[
+			(address(2), account(0, 1_000, Vec::new())),
+			(
+				address(0x100),
+				account(1, 0, hex::decode(STATIC_CALL_COUNTER).unwrap()),
+			),
+		],
+	)
+}
+
+/// A counter at `0x10`, incremented on each call, and a precompile at `0x11`
+/// returning the balance of `0x02`.
+fn precompiles() -> ClosurePrecompiles {
+	ClosurePrecompiles::new()
+		.with(address(0x10), |handle| {
+			let count = U256::from_big_endian(handle.storage(H256::zero())?.as_bytes()) + 1;
+			handle.set_storage(H256::zero(), H256::from_slice(&word(count)))?;
+			Ok(PrecompileOutput {
+				exit_status: ExitSucceed::Returned,
+				output: word(count),
+			})
+		})
+		.with(address(0x11), |handle| {
+			Ok(PrecompileOutput {
+				exit_status: ExitSucceed::Returned,
+				output: word(handle.balance(address(2))?),
+			})
+		})
+}
+
+#[test]
+fn storage_is_charged_like_sload_and_sstore() {
+	let config = Config::cancun();
+	let backend = backend();
+	let precompiles = precompiles();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let (reason, output) = executor.transact_call(
+		address(1),
+		address(0x10),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output, word(U256::one()));
+	// A cold SLOAD, then an SSTORE of a new value to the warm slot.
+	assert_eq!(executor.used_gas(), 21000 + 2100 + 20000);
+	assert_eq!(
+		executor.state().storage(address(0x10), H256::zero()),
+		H256::from_slice(&word(U256::one()))
+	);
+}
+
+#[test]
+fn balance_is_charged_like_balance() {
+	let config = Config::cancun();
+	let backend = backend();
+	let precompiles = precompiles();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let (reason, output) = executor.transact_call(
+		address(1),
+		address(0x11),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output, word(U256::from(1_000)));
+	assert_eq!(executor.used_gas(), 21000 + 2600);
+}
+
+#[test]
+fn storage_writes_fail_in_static_calls() {
+	let config = Config::cancun();
+	let backend = backend();
+	let precompiles = precompiles();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let (reason, output) = executor.transact_call(
+		address(1),
+		address(0x100),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output, word(U256::zero()));
+	assert_eq!(
+		executor.state().storage(address(0x10), H256::zero()),
+		H256::zero()
+	);
+}
+
+#[test]
+fn storage_accesses_are_recorded() {
+	let config = Config::cancun();
+	let backend = backend();
+	let precompiles = precompiles();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, true);
+
+	let _reason = executor.transact_call(
+		address(1),
+		address(0x10),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+
+	let key = StateKey::Storage(address(0x10), H256::zero());
+	let rw_set = executor.rw_set().unwrap();
+	assert_eq!(
+		rw_set.reads().get(&key),
+		Some(&StateValue::Storage(H256::zero()))
+	);
+	assert_eq!(
+		rw_set.writes().get(&key),
+		Some(&StateValue::Storage(H256::from_slice(&word(U256::one()))))
+	);
+}
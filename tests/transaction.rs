@@ -0,0 +1,242 @@
+mod common;
+
+use common::{account, address, vicinity};
+use evm::backend::{Backend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{
+	InvalidTransaction, MemoryStackState, StackExecutor, StackSubstateMetadata, Transaction,
+	TransactionAction, TransactionError,
+};
+use evm::{Config, CreateScheme, ExitReason, ExitSucceed};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+
+const BALANCE: u64 = 1_000_000_000;
+const BASE_FEE: u64 = 10;
+
+fn caller() -> H160 {
+	address(1)
+}
+
+fn coinbase() -> H160 {
+	address(0xc0)
+}
+
+fn backend(caller_code: Vec<u8>) -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_coinbase: coinbase(),
+		block_gas_limit: U256::from(30_000_000),
+		block_base_fee_per_gas: U256::from(BASE_FEE),
+		..vicinity()
+	};
+	common::backend(vicinity, [(caller(), account(1, BALANCE, caller_code))])
+}
+
+fn transaction() -> Transaction {
+	Transaction {
+		caller: caller(),
+		nonce: U256::one(),
+		action: TransactionAction::Call(address(2)),
+		value: U256::from(100),
+		data: Vec::new(),
+		gas_limit: 50_000,
+		gas_price: U256::from(30),
+		max_priority_fee_per_gas: Some(U256::from(5)),
+		access_list: Vec::new(),
+		authorization_list: Vec::new(),
+		max_fee_per_blob_gas: None,
+		blob_versioned_hashes: Vec::new(),
+	}
+}
+
+fn balance(state: &MemoryStackState<'_, '_, MemoryBackend>, address: H160) -> U256 {
+	state.basic(address).balance
+}
+
+#[test]
+fn pays_for_used_gas() {
+	let config = Config::london();
+	let backend = backend(Vec::new());
+	let metadata = StackSubstateMetadata::new(50_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let outcome = executor.transact(transaction()).unwrap();
+	assert_eq!(
+		outcome.exit_reason,
+		ExitReason::Succeed(ExitSucceed::Stopped)
+	);
+	assert_eq!(outcome.used_gas, 21000);
+	// The priority fee is capped by the maximum fee.
+	assert_eq!(outcome.effective_gas_price, U256::from(15));
+
+	let state = executor.state();
+	assert_eq!(
+		balance(state, caller()),
+		U256::from(BALANCE - 100 - 21000 * 15)
+	);
+	assert_eq!(balance(state, address(2)), U256::from(100));
+	assert_eq!(balance(state, coinbase()), U256::from(21000 * 5));
+	assert_eq!(state.basic(caller()).nonce, U256::from(2));
+}
+
+#[test]
+fn legacy_transactions_tip_above_base_fee() {
+	let config = Config::london();
+	let backend = backend(Vec::new());
+	let metadata = StackSubstateMetadata::new(50_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let outcome = executor
+		.transact(Transaction {
+			gas_price: U256::from(20),
+			max_priority_fee_per_gas: None,
+			..transaction()
+		})
+		.unwrap();
+	assert_eq!(outcome.effective_gas_price, U256::from(20));
+
+	let state = executor.state();
+	assert_eq!(
+		balance(state, caller()),
+		U256::from(BALANCE - 100 - 21000 * 20)
+	);
+	assert_eq!(
+		balance(state, coinbase()),
+		U256::from(21000 * (20 - BASE_FEE))
+	);
+}
+
+#[test]
+fn creations_return_the_contract_address() {
+	let config = Config::london();
+	let backend = backend(Vec::new());
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	let expected = executor
+		.create_address(CreateScheme::Legacy { caller: caller() })
+		.unwrap();
+	let outcome = executor
+		.transact(Transaction {
+			action: TransactionAction::Create,
+			data: vec![0x00],
+			gas_limit: 100_000,
+			..transaction()
+		})
+		.unwrap();
+	assert!(outcome.exit_reason.is_succeed());
+	assert_eq!(outcome.contract_address, Some(expected));
+}
+
+#[test]
+fn invalid_transactions_leave_the_state_untouched() {
+	let config = Config::london();
+	let backend = backend(Vec::new());
+	let precompiles = BTreeMap::new();
+
+	let invalid = |transaction: Transaction| {
+		let metadata = StackSubstateMetadata::new(transaction.gas_limit, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+		let result = executor.transact(transaction);
+		assert_eq!(balance(executor.state(), caller()), U256::from(BALANCE));
+		assert_eq!(executor.state().basic(caller()).nonce, U256::one());
+		match result {
+			Err(TransactionError::Invalid(error)) => error,
+			result => panic!("valid transaction: {:?}", result),
+		}
+	};
+
+	assert_eq!(
+		invalid(Transaction {
+			nonce: U256::zero(),
+			..transaction()
+		}),
+		InvalidTransaction::NonceTooLow {
+			expected: U256::one(),
+			actual: U256::zero(),
+		}
+	);
+	assert_eq!(
+		invalid(Transaction {
+			nonce: U256::from(2),
+			..transaction()
+		}),
+		InvalidTransaction::NonceTooHigh {
+			expected: U256::one(),
+			actual: U256::from(2),
+		}
+	);
+	assert_eq!(
+		invalid(Transaction {
+			gas_limit: 30_000_001,
+			..transaction()
+		}),
+		InvalidTransaction::GasLimitAboveBlockGasLimit {
+			gas_limit: 30_000_001,
+			block_gas_limit: U256::from(30_000_000),
+		}
+	);
+	assert_eq!(
+		invalid(Transaction {
+			gas_limit: 20_999,
+			..transaction()
+		}),
+		InvalidTransaction::IntrinsicGasTooLow {
+			gas_limit: 20_999,
+			intrinsic_gas: 21000,
+		}
+	);
+	assert_eq!(
+		invalid(Transaction {
+			gas_price: U256::from(BASE_FEE - 1),
+			max_priority_fee_per_gas: None,
+			..transaction()
+		}),
+		InvalidTransaction::GasPriceBelowBaseFee {
+			gas_price: U256::from(BASE_FEE - 1),
+			base_fee: U256::from(BASE_FEE),
+		}
+	);
+	assert_eq!(
+		invalid(Transaction {
+			max_priority_fee_per_gas: Some(U256::from(31)),
+			..transaction()
+		}),
+		InvalidTransaction::PriorityFeeAboveMaxFee {
+			max_priority_fee_per_gas: U256::from(31),
+			max_fee_per_gas: U256::from(30),
+		}
+	);
+	// The balance covers the gas limit at the maximum fee.
+	assert_eq!(
+		invalid(Transaction {
+			value: U256::from(BALANCE - 50_000 * 30 + 1),
+			..transaction()
+		}),
+		InvalidTransaction::InsufficientBalance {
+			required: U256::from(BALANCE + 1),
+			balance: U256::from(BALANCE),
+		}
+	);
+}
+
+#[test]
+fn senders_cannot_have_code() {
+	let config = Config::london();
+	let backend = backend(vec![0x00]);
+	let metadata = StackSubstateMetadata::new(50_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+
+	assert_eq!(
+		executor.transact(transaction()),
+		Err(InvalidTransaction::SenderNotEoa.into())
+	);
+}
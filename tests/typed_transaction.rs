@@ -0,0 +1,314 @@
+mod common;
+
+use common::{account, address, vicinity};
+use ethereum::{
+	AccessListItem, EIP1559Transaction, EIP2930Transaction, LegacyTransaction,
+	TransactionSignature, TransactionV2,
+};
+use evm::backend::{Backend, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{
+	EIP4844Transaction, InvalidTransaction, MemoryStackState, StackExecutor, StackSubstateMetadata,
+	TransactionAction, TransactionError, TypedTransaction, GAS_PER_BLOB,
+};
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+const BALANCE: u64 = 1_000_000_000;
+const BASE_FEE: u64 = 10;
+const BLOB_BASE_FEE: u64 = 2;
+const CHAIN_ID: u64 = 1;
+
+// SSTORE(0, ORIGIN), SSTORE(1, GASPRICE), then SSTORE(2 + i, BLOBHASH(i))
+// for i in 0..3.
+const ENVIRONMENT: &str = "326000553a600155600049600255600149600355600249600455";
+
+fn caller() -> H160 {
+	address(1)
+}
+
+fn coinbase() -> H160 {
+	address(0xc0)
+}
+
+fn blob_hash(version: u8) -> H256 {
+	let mut hash = H256::repeat_byte(0xab);
+	hash.0[0] = version;
+	hash
+}
+
+fn backend() -> MemoryBackend {
+	let vicinity = MemoryVicinity {
+		block_coinbase: coinbase(),
+		block_gas_limit: U256::from(30_000_000),
+		chain_id: U256::from(CHAIN_ID),
+		block_base_fee_per_gas: U256::from(BASE_FEE),
+		block_blob_base_fee: U256::from(BLOB_BASE_FEE),
+		..vicinity()
+	};
+	common::backend(vicinity, [(caller(), account(0, BALANCE, Vec::new()))])
+}
+
+fn legacy(v: u64) -> TypedTransaction {
+	TransactionV2::Legacy(LegacyTransaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(20),
+		gas_limit: U256::from(50_000),
+		action: TransactionAction::Call(address(2)),
+		value: U256::from(100),
+		input: Vec::new(),
+		signature: TransactionSignature::new(v, H256::repeat_byte(1), H256::repeat_byte(1))
+			.unwrap(),
+	})
+	.into()
+}
+
+fn eip1559() -> EIP1559Transaction {
+	EIP1559Transaction {
+		chain_id: CHAIN_ID,
+		nonce: U256::zero(),
+		max_priority_fee_per_gas: U256::from(5),
+		max_fee_per_gas: U256::from(30),
+		gas_limit: U256::from(50_000),
+		action: TransactionAction::Call(address(2)),
+		value: U256::from(100),
+		input: Vec::new(),
+		access_list: Vec::new(),
+		odd_y_parity: false,
+		r: H256::repeat_byte(1),
+		s: H256::repeat_byte(1),
+	}
+}
+
+fn eip4844() -> EIP4844Transaction {
+	EIP4844Transaction {
+		chain_id: CHAIN_ID,
+		nonce: U256::zero(),
+		max_priority_fee_per_gas: U256::from(5),
+		max_fee_per_gas: U256::from(30),
+		gas_limit: U256::from(50_000),
+		to: address(2),
+		value: U256::from(100),
+		input: Vec::new(),
+		access_list: Vec::new(),
+		max_fee_per_blob_gas: U256::from(BLOB_BASE_FEE),
+		blob_versioned_hashes: vec![blob_hash(1), blob_hash(1)],
+		odd_y_parity: false,
+		r: H256::repeat_byte(1),
+		s: H256::repeat_byte(1),
+	}
+}
+
+fn transact(
+	config: &Config,
+	transaction: &TypedTransaction,
+) -> (
+	Result<evm::executor::stack::TransactionOutcome, TransactionError>,
+	U256,
+) {
+	let backend = backend();
+	let metadata = StackSubstateMetadata::new(50_000, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles, false);
+
+	let result = executor.transact_typed(transaction, caller());
+	let balance = executor.state().basic(caller()).balance;
+	(result, balance)
+}
+
+#[test]
+fn types_follow_the_hard_fork() {
+	let eip2930 = TypedTransaction::EIP2930(EIP2930Transaction {
+		chain_id: CHAIN_ID,
+		nonce: U256::zero(),
+		gas_price: U256::from(20),
+		gas_limit: U256::from(50_000),
+		action: TransactionAction::Call(address(2)),
+		value: U256::zero(),
+		input: Vec::new(),
+		access_list: Vec::new(),
+		odd_y_parity: false,
+		r: H256::repeat_byte(1),
+		s: H256::repeat_byte(1),
+	});
+
+	assert_eq!(
+		transact(&Config::istanbul(), &eip2930).0,
+		Err(InvalidTransaction::UnsupportedType(1).into())
+	);
+	assert_eq!(
+		transact(&Config::berlin(), &TypedTransaction::EIP1559(eip1559())).0,
+		Err(InvalidTransaction::UnsupportedType(2).into())
+	);
+	assert_eq!(
+		transact(&Config::shanghai(), &TypedTransaction::EIP4844(eip4844())).0,
+		Err(InvalidTransaction::UnsupportedType(3).into())
+	);
+	assert!(transact(&Config::berlin(), &eip2930).0.is_ok());
+	assert!(transact(&Config::istanbul(), &legacy(27)).0.is_ok());
+}
+
+#[test]
+fn chain_ids_must_match() {
+	let config = Config::london();
+	// EIP-155 signature for chain 2.
+	assert_eq!(
+		transact(&config, &legacy(2 * 2 + 35)).0,
+		Err(InvalidTransaction::ChainIdMismatch {
+			expected: U256::from(CHAIN_ID),
+			actual: 2,
+		}
+		.into())
+	);
+	assert!(transact(&config, &legacy(CHAIN_ID * 2 + 35)).0.is_ok());
+	assert_eq!(
+		transact(
+			&config,
+			&TypedTransaction::EIP1559(EIP1559Transaction {
+				chain_id: 5,
+				..eip1559()
+			})
+		)
+		.0,
+		Err(InvalidTransaction::ChainIdMismatch {
+			expected: U256::from(CHAIN_ID),
+			actual: 5,
+		}
+		.into())
+	);
+}
+
+#[test]
+fn eip1559_transactions_pay_the_effective_gas_price() {
+	let item = AccessListItem {
+		address: address(3),
+		storage_keys: vec![H256::zero()],
+	};
+	let transaction = TypedTransaction::EIP1559(EIP1559Transaction {
+		access_list: vec![item],
+		..eip1559()
+	});
+
+	let (outcome, balance) = transact(&Config::london(), &transaction);
+	let outcome = outcome.unwrap();
+	assert_eq!(outcome.effective_gas_price, U256::from(BASE_FEE + 5));
+	// The access list is charged with the intrinsic gas.
+	assert_eq!(outcome.used_gas, 21000 + 2400 + 1900);
+	assert_eq!(outcome.blob_gas_used, 0);
+	assert_eq!(
+		balance,
+		U256::from(BALANCE - 100 - outcome.used_gas * (BASE_FEE + 5))
+	);
+}
+
+#[test]
+fn blob_transactions_burn_blob_gas() {
+	let (outcome, balance) = transact(&Config::cancun(), &TypedTransaction::EIP4844(eip4844()));
+	let outcome = outcome.unwrap();
+	assert_eq!(outcome.blob_gas_used, 2 * GAS_PER_BLOB);
+	assert_eq!(
+		balance,
+		U256::from(BALANCE - 100 - 21000 * (BASE_FEE + 5) - 2 * GAS_PER_BLOB * BLOB_BASE_FEE)
+	);
+}
+
+#[test]
+fn transactions_see_their_own_environment() {
+	// The environment of the block is not the one of the transaction.
+	let vicinity = MemoryVicinity {
+		gas_price: U256::from(1),
+		origin: address(0x99),
+		blob_hashes: vec![blob_hash(1); 3],
+		block_gas_limit: U256::from(30_000_000),
+		block_base_fee_per_gas: U256::from(BASE_FEE),
+		block_blob_base_fee: U256::from(BLOB_BASE_FEE),
+		..vicinity()
+	};
+	let backend = common::backend(
+		vicinity,
+		[
+			(caller(), account(0, BALANCE, Vec::new())),
+			(
+				address(0x300),
+				account(1, 0, hex::decode(ENVIRONMENT).unwrap()),
+			),
+		],
+	);
+
+	let mut second_hash = blob_hash(1);
+	second_hash.0[31] = 2;
+	let transaction = TypedTransaction::EIP4844(EIP4844Transaction {
+		to: address(0x300),
+		gas_limit: U256::from(200_000),
+		blob_versioned_hashes: vec![blob_hash(1), second_hash],
+		..eip4844()
+	});
+
+	let config = Config::cancun();
+	let metadata = StackSubstateMetadata::new(200_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles, false);
+	let outcome = executor.transact_typed(&transaction, caller()).unwrap();
+	assert!(outcome.exit_reason.is_succeed());
+
+	let slot = |index: u64| {
+		executor
+			.state()
+			.storage(address(0x300), H256::from_low_u64_be(index))
+	};
+	assert_eq!(slot(0), H256::from(caller()));
+	assert_eq!(slot(1), H256::from_low_u64_be(BASE_FEE + 5));
+	assert_eq!(slot(2), blob_hash(1));
+	assert_eq!(slot(3), second_hash);
+	assert_eq!(slot(4), H256::zero());
+}
+
+#[test]
+fn invalid_blob_transactions() {
+	let config = Config::cancun();
+	let invalid = |transaction: EIP4844Transaction| {
+		let (result, balance) = transact(&config, &TypedTransaction::EIP4844(transaction));
+		assert_eq!(balance, U256::from(BALANCE));
+		match result {
+			Err(TransactionError::Invalid(error)) => error,
+			result => panic!("valid transaction: {:?}", result),
+		}
+	};
+
+	assert_eq!(
+		invalid(EIP4844Transaction {
+			blob_versioned_hashes: Vec::new(),
+			..eip4844()
+		}),
+		InvalidTransaction::NoBlobs
+	);
+	assert_eq!(
+		invalid(EIP4844Transaction {
+			blob_versioned_hashes: vec![blob_hash(1); 7],
+			..eip4844()
+		}),
+		InvalidTransaction::TooManyBlobs {
+			count: 7,
+			max_count: 6,
+		}
+	);
+	assert_eq!(
+		invalid(EIP4844Transaction {
+			blob_versioned_hashes: vec![blob_hash(1), blob_hash(2)],
+			..eip4844()
+		}),
+		InvalidTransaction::InvalidBlobVersionedHash(blob_hash(2))
+	);
+	assert_eq!(
+		invalid(EIP4844Transaction {
+			max_fee_per_blob_gas: U256::from(BLOB_BASE_FEE - 1),
+			..eip4844()
+		}),
+		InvalidTransaction::BlobGasPriceBelowBlobBaseFee {
+			max_fee_per_blob_gas: U256::from(BLOB_BASE_FEE - 1),
+			blob_base_fee: U256::from(BLOB_BASE_FEE),
+		}
+	);
+}